@@ -0,0 +1,321 @@
+//! Procedural macros for writing [Triton assembly][tasm] that is parsed at compile
+//! time.
+//!
+//! The macros of this crate are drop-in replacements for the declarative macros
+//! [`triton_isa::triton_program!`] and [`triton_isa::triton_asm!`]. Those parse
+//! the assembly only at runtime, meaning that a typo results in a panic once the
+//! code is executed. In contrast, the macros of this crate report parse errors as
+//! compile errors, pointing at the offending token.
+//!
+//! Like their declarative counterparts, the macros support string-like
+//! interpolation of runtime values using curly braces: `{expression}` inserts the
+//! expression's [`Display`](std::fmt::Display) representation, and `{&list}` inserts
+//! a list of instructions. Since the interpolated values are not known at compile
+//! time, the surrounding assembly can only be checked for well-formedness. In
+//! particular, the existence of labels is not checked for programs containing
+//! interpolations.
+//!
+//! The generated code refers to crate `triton_isa`, which must be a dependency.
+//!
+//! [tasm]: https://triton-vm.org/spec/instructions.html
+
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::Finish;
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use proc_macro2::TokenTree;
+use quote::quote;
+use quote::quote_spanned;
+use triton_isa::parser;
+use triton_isa::parser::ParseError;
+use triton_isa::program::Program;
+
+use crate::source::Source;
+
+mod source;
+
+/// Compile an entire program written in [Triton assembly][tasm], checking it for
+/// errors at compile time. See [`triton_isa::triton_program!`] for details.
+///
+/// # Examples
+///
+/// ```
+/// # use triton_isa_macros::triton_program;
+/// let push_argument = 5;
+/// let program = triton_program!(
+///     read_io 1 push {push_argument} mul
+///     call check_eq_15
+///     halt
+///     check_eq_15:
+///         push 15 eq assert
+///         return
+/// );
+/// # assert_eq!(13, program.len_bwords());
+/// ```
+///
+/// Errors in the assembly are reported at compile time:
+///
+/// ```compile_fail
+/// # use triton_isa_macros::triton_program;
+/// let program = triton_program!(push 1 pusj 2 halt);
+/// ```
+///
+/// This includes calls to labels that don't exist:
+///
+/// ```compile_fail
+/// # use triton_isa_macros::triton_program;
+/// let program = triton_program!(call does_not_exist halt);
+/// ```
+///
+/// [tasm]: https://triton-vm.org/spec/instructions.html
+#[proc_macro]
+pub fn triton_program(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand_triton_program(input.into()).into()
+}
+
+/// Compile [Triton assembly][tasm] into a list of labelled instructions, checking
+/// it for errors at compile time. See [`triton_isa::triton_asm!`] for details.
+///
+/// # Examples
+///
+/// ```
+/// # use triton_isa_macros::triton_asm;
+/// let label = "my_label";
+/// let instructions = triton_asm!(
+///     push 1 call {label}
+///     {label}: halt
+/// );
+/// assert_eq!(4, instructions.len());
+///
+/// let instructions = triton_asm![sponge_absorb; 3];
+/// assert_eq!(3, instructions.len());
+///
+/// let num_iterations = 4;
+/// let instructions = triton_asm!(
+///     repeat {num_iterations} { dup 0 write_io 1 }
+///     return
+/// );
+/// assert_eq!(14, instructions.len());
+/// ```
+///
+/// Errors in the assembly are reported at compile time:
+///
+/// ```compile_fail
+/// # use triton_isa_macros::triton_asm;
+/// let instructions = triton_asm!(push 1 dup 16);
+/// ```
+///
+/// [tasm]: https://triton-vm.org/spec/instructions.html
+#[proc_macro]
+pub fn triton_asm(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand_triton_asm(input.into()).into()
+}
+
+fn expand_triton_program(input: TokenStream) -> TokenStream {
+    let source = Source::new(input);
+    let validation_result = if source.has_interpolations() {
+        // labels might be interpolated; only check syntax
+        tokenize(&source)
+    } else {
+        Program::from_code(&source.code).map(|_| ())
+    };
+    if let Err(err) = validation_result {
+        return compile_errors(&source, &err);
+    }
+
+    let instructions = labelled_instructions(&source);
+    quote!({
+        let labelled_instructions = #instructions;
+        ::triton_isa::program::Program::new(&labelled_instructions)
+    })
+}
+
+fn expand_triton_asm(input: TokenStream) -> TokenStream {
+    let Some((instruction, repetitions)) = split_repetition(&input) else {
+        let source = Source::new(input);
+        if let Err(err) = tokenize(&source) {
+            return compile_errors(&source, &err);
+        }
+        return labelled_instructions(&source);
+    };
+
+    let source = Source::new(instruction);
+    let num_tokens = match parser::tokenize(&source.code).finish() {
+        Ok((_, tokens)) => tokens.len(),
+        Err(errors) => {
+            let err = ParseError {
+                input: &source.code,
+                errors,
+            };
+            return compile_errors(&source, &err);
+        }
+    };
+    if num_tokens != 1 && !source.has_interpolations() {
+        let message = "exactly one instruction can be repeated";
+        return quote_spanned!(source.span_at(0)=> { ::std::compile_error!(#message) });
+    }
+
+    let instructions = labelled_instructions(&source);
+    quote!({
+        let mut instructions = #instructions;
+        ::std::vec![instructions.remove(0); #repetitions]
+    })
+}
+
+/// Splits input of the form `instruction; repetitions` at the `;`, if present.
+fn split_repetition(input: &TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let tokens = input.clone().into_iter().collect::<Vec<_>>();
+    let is_semicolon = |token: &_| matches!(token, TokenTree::Punct(p) if p.as_char() == ';');
+    let semicolon_position = tokens.iter().position(is_semicolon)?;
+    let (instruction, repetitions) = tokens.split_at(semicolon_position);
+    let instruction = instruction.iter().cloned().collect();
+    let repetitions = repetitions[1..].iter().cloned().collect();
+
+    Some((instruction, repetitions))
+}
+
+fn tokenize(source: &Source) -> Result<(), ParseError> {
+    let (_, _) = parser::tokenize(&source.code)
+        .finish()
+        .map_err(|errors| ParseError {
+            input: &source.code,
+            errors,
+        })?;
+    Ok(())
+}
+
+/// An expression that evaluates to the labelled instructions of the given source.
+fn labelled_instructions(source: &Source) -> TokenStream {
+    let source_code = source.to_runtime_source_code();
+    quote!({
+        let source_code = #source_code;
+        let (_, instructions) = ::triton_isa::parser::tokenize(&source_code).unwrap();
+        ::triton_isa::parser::to_labelled_instructions(&instructions)
+    })
+}
+
+fn compile_errors(source: &Source, err: &ParseError) -> TokenStream {
+    let compile_errors = error_messages(&err.errors)
+        .into_iter()
+        .map(|(position, message)| {
+            let span = span_at(source, position);
+            quote_spanned!(span=> ::std::compile_error!(#message))
+        })
+        .collect::<Vec<_>>();
+
+    quote!({ #(#compile_errors);* })
+}
+
+/// The errors worth reporting, each with the position it applies to.
+///
+/// Errors are either a single failure, described by a chain of entries from the
+/// innermost parser outwards, or a list of independent failures, as reported for
+/// duplicate or missing labels, for example. The latter only carry context.
+fn error_messages<'a>(errors: &VerboseError<&'a str>) -> Vec<(&'a str, String)> {
+    let context_messages = errors
+        .errors
+        .iter()
+        .filter_map(|(position, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some((*position, (*context).to_string())),
+            _ => None,
+        });
+
+    let is_chain = !matches!(
+        errors.errors.first(),
+        Some((_, VerboseErrorKind::Context(_)))
+    );
+    if !is_chain {
+        return context_messages.collect();
+    }
+
+    let message = context_messages
+        .chain(errors.errors.first().map(|(position, kind)| {
+            let message = match kind {
+                VerboseErrorKind::Char(c) => format!("expected '{c}'"),
+                _ => "unexpected token".to_string(),
+            };
+            (*position, message)
+        }))
+        .next();
+
+    message.into_iter().collect()
+}
+
+fn span_at(source: &Source, position: &str) -> Span {
+    let offset = position.as_ptr() as usize - source.code.as_ptr() as usize;
+    source.span_at(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+
+    use super::*;
+
+    fn is_compile_error(expansion: &TokenStream) -> bool {
+        expansion.to_string().contains("compile_error")
+    }
+
+    #[test]
+    fn valid_program_expands_without_errors() {
+        let expansion = expand_triton_program(quote!(
+            .const N = 3
+            push N call foo halt
+            foo: hint x = stack[0] return
+        ));
+        assert!(!is_compile_error(&expansion));
+    }
+
+    #[test]
+    fn invalid_instruction_is_reported() {
+        let expansion = expand_triton_program(quote!(push 1 pusj 2 halt));
+        assert!(is_compile_error(&expansion));
+    }
+
+    #[test]
+    fn missing_label_is_reported_unless_program_has_interpolations() {
+        let expansion = expand_triton_program(quote!(call foo halt));
+        assert!(is_compile_error(&expansion));
+        assert!(expansion.to_string().contains("missing label"));
+
+        let expansion = expand_triton_program(quote!(call foo halt {label}: return));
+        assert!(!is_compile_error(&expansion));
+    }
+
+    #[test]
+    fn every_duplicate_label_is_reported() {
+        let expansion = expand_triton_program(quote!(foo: halt foo: halt));
+        let num_errors = expansion.to_string().matches("compile_error").count();
+        assert!(2 == num_errors);
+    }
+
+    #[test]
+    fn asm_is_only_checked_for_syntax() {
+        let expansion = expand_triton_asm(quote!(call foo));
+        assert!(!is_compile_error(&expansion));
+
+        let expansion = expand_triton_asm(quote!(dup 16));
+        assert!(is_compile_error(&expansion));
+    }
+
+    #[test]
+    fn structured_control_flow_is_checked() {
+        let expansion = expand_triton_program(quote!(
+            push 1 if { push {a} } else { repeat {n} { nop } } halt
+        ));
+        assert!(!is_compile_error(&expansion));
+
+        let expansion = expand_triton_program(quote!(while { pusj 1 } halt));
+        assert!(is_compile_error(&expansion));
+    }
+
+    #[test]
+    fn only_single_instructions_can_be_repeated() {
+        let expansion = expand_triton_asm(quote!(push 1; 3));
+        assert!(!is_compile_error(&expansion));
+
+        let expansion = expand_triton_asm(quote!(push 1 pop 1; 3));
+        assert!(is_compile_error(&expansion));
+    }
+}
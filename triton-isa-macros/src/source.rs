@@ -0,0 +1,286 @@
+use std::ops::Range;
+
+use proc_macro2::Delimiter;
+use proc_macro2::Group;
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use proc_macro2::TokenTree;
+use quote::quote;
+
+/// Instructions that take a field element as their argument.
+const FIELD_ELEMENT_ARGUMENT: [&str; 2] = ["push", "addi"];
+
+/// Instructions that take a number of words as their argument.
+const NUMBER_OF_WORDS_ARGUMENT: [&str; 6] = [
+    "pop",
+    "divine",
+    "read_mem",
+    "write_mem",
+    "read_io",
+    "write_io",
+];
+
+/// Instructions that take a stack register as their argument.
+const STACK_REGISTER_ARGUMENT: [&str; 4] = ["pick", "place", "dup", "swap"];
+
+/// Triton assembly, reconstructed from the Rust tokens passed to a macro.
+///
+/// Interpolations, _i.e._, expressions in curly braces, are only known at
+/// runtime. For validation at compile time, each of them is replaced by a
+/// placeholder that is syntactically valid in the interpolation's position.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Source {
+    /// The assembly with placeholders in place of interpolations.
+    pub code: String,
+
+    /// The assembly with `{}` in place of interpolations.
+    format_string: String,
+
+    /// The runtime values of the interpolations, in order of appearance.
+    interpolations: Vec<TokenStream>,
+
+    /// The span of every token of the assembly, by the token's byte range in
+    /// [`code`](Self::code).
+    spans: Vec<(Range<usize>, Span)>,
+}
+
+impl Source {
+    pub fn new(tokens: TokenStream) -> Self {
+        let mut source = Self::default();
+        source.extend(tokens, Delimiter::None);
+        source
+    }
+
+    pub fn has_interpolations(&self) -> bool {
+        !self.interpolations.is_empty()
+    }
+
+    fn extend(&mut self, tokens: TokenStream, enclosing_delimiter: Delimiter) {
+        let mut previous_token = None;
+        let mut penultimate_token = None;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match &token {
+                TokenTree::Group(group)
+                    if group.delimiter() == Delimiter::Brace
+                        && is_block(previous_token.as_ref(), penultimate_token.as_ref()) =>
+                {
+                    self.push_block(group);
+                }
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    let context = InterpolationContext {
+                        previous_token: previous_token.as_ref(),
+                        next_token: tokens.peek(),
+                        enclosing_delimiter,
+                    };
+                    self.push_interpolation(group, &context);
+                }
+                TokenTree::Group(group) => self.push_group(group),
+                TokenTree::Ident(ident) => self.push_token(&ident.to_string(), ident.span()),
+                TokenTree::Punct(punct) => self.push_token(&punct.to_string(), punct.span()),
+                TokenTree::Literal(lit) => self.push_token(&lit.to_string(), lit.span()),
+            }
+            penultimate_token = previous_token.replace(token);
+        }
+    }
+
+    fn push_group(&mut self, group: &Group) {
+        let (open, close) = match group.delimiter() {
+            Delimiter::Parenthesis => ("(", ")"),
+            Delimiter::Bracket => ("[", "]"),
+            Delimiter::Brace | Delimiter::None => ("", ""),
+        };
+        self.push_token(open, group.span_open());
+        self.extend(group.stream(), group.delimiter());
+        self.push_token(close, group.span_close());
+    }
+
+    /// A block of structured control flow, like the body of a loop.
+    fn push_block(&mut self, group: &Group) {
+        self.push_token("{", group.span_open());
+        self.extend(group.stream(), Delimiter::None);
+        self.push_token("}", group.span_close());
+    }
+
+    fn push_interpolation(&mut self, group: &Group, context: &InterpolationContext) {
+        let mut tokens = group.stream().into_iter().peekable();
+        let is_list = matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '&');
+        let interpolation = if is_list {
+            let list = tokens.skip(1).collect::<TokenStream>();
+            quote!((#list)
+                .iter()
+                .map(|instruction| instruction.to_string())
+                .collect::<::std::vec::Vec<_>>()
+                .join(" "))
+        } else {
+            let expression = group.stream();
+            quote!((#expression))
+        };
+        let placeholder = if is_list {
+            "nop"
+        } else {
+            context.placeholder()
+        };
+
+        self.push_separator(placeholder);
+        let start = self.code.len();
+        self.code.push_str(placeholder);
+        self.format_string.push_str("{}");
+        self.spans.push((start..self.code.len(), group.span()));
+        self.interpolations.push(interpolation);
+    }
+
+    fn push_token(&mut self, token: &str, span: Span) {
+        if token.is_empty() {
+            return;
+        }
+        self.push_separator(token);
+        let start = self.code.len();
+        self.code.push_str(token);
+        let escaped_token = token.replace('{', "{{").replace('}', "}}");
+        self.format_string.push_str(&escaped_token);
+        self.spans.push((start..self.code.len(), span));
+    }
+
+    /// Separate tokens by whitespace, except for a few cases where the assembly
+    /// does not allow it. Those are directives like `.const` and macro
+    /// invocations like `my_macro!`. This also keeps together the two Rust tokens
+    /// making up the `..` in type hints.
+    fn push_separator(&mut self, next_token: &str) {
+        if self.code.is_empty() || self.code.ends_with('.') || next_token == "!" {
+            return;
+        }
+        self.code.push(' ');
+        self.format_string.push(' ');
+    }
+
+    /// The span of the token at the given byte offset into [`code`](Self::code).
+    /// If the offset points to whitespace, the span of the next token is returned.
+    pub fn span_at(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .find(|(range, _)| offset < range.end)
+            .or(self.spans.last())
+            .map_or_else(Span::call_site, |&(_, span)| span)
+    }
+
+    /// An expression evaluating to the assembly as a `String` at runtime.
+    pub fn to_runtime_source_code(&self) -> TokenStream {
+        if !self.has_interpolations() {
+            let code = &self.code;
+            return quote!(::std::string::String::from(#code));
+        }
+
+        let format_string = &self.format_string;
+        let interpolations = &self.interpolations;
+        quote!(::std::format!(#format_string, #(#interpolations),*))
+    }
+}
+
+/// Whether a group in curly braces, given the two tokens preceding it, is a block
+/// of structured control flow rather than an interpolation. Blocks follow `if`,
+/// `else`, or `while`, or the number of iterations in `repeat N { … }`.
+fn is_block(previous_token: Option<&TokenTree>, penultimate_token: Option<&TokenTree>) -> bool {
+    let is_ident = |token: Option<&TokenTree>, names: &[&str]| matches!(token, Some(TokenTree::Ident(ident)) if names.contains(&ident.to_string().as_str()));
+
+    is_ident(previous_token, &["if", "else", "while"]) || is_ident(penultimate_token, &["repeat"])
+}
+
+/// The surroundings of an interpolation, used to pick a fitting placeholder.
+struct InterpolationContext<'a> {
+    previous_token: Option<&'a TokenTree>,
+    next_token: Option<&'a TokenTree>,
+    enclosing_delimiter: Delimiter,
+}
+
+impl InterpolationContext<'_> {
+    fn placeholder(&self) -> &'static str {
+        let is_punct = |token: Option<&TokenTree>, c| matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == c);
+        if is_punct(self.next_token, ':') {
+            return "interpolated_label";
+        }
+        if is_punct(self.previous_token, '=') || is_punct(self.previous_token, ',') {
+            return "0";
+        }
+        if matches!(
+            self.enclosing_delimiter,
+            Delimiter::Parenthesis | Delimiter::Bracket
+        ) {
+            return "0";
+        }
+
+        let Some(TokenTree::Ident(previous_ident)) = self.previous_token else {
+            return "nop";
+        };
+        let previous_ident = previous_ident.to_string();
+        let previous_ident = previous_ident.as_str();
+        if previous_ident == "call" {
+            "interpolated_label"
+        } else if previous_ident == "repeat" || NUMBER_OF_WORDS_ARGUMENT.contains(&previous_ident) {
+            "1"
+        } else if FIELD_ELEMENT_ARGUMENT.contains(&previous_ident)
+            || STACK_REGISTER_ARGUMENT.contains(&previous_ident)
+        {
+            "0"
+        } else {
+            "nop"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+
+    use super::*;
+
+    #[test]
+    fn tokens_are_separated_by_whitespace() {
+        let source = Source::new(quote!(push 1 call foo foo: pop 1));
+        assert!("push 1 call foo foo : pop 1" == source.code);
+    }
+
+    #[test]
+    fn directives_and_type_hints_are_reconstructed() {
+        let source = Source::new(quote!(
+            .const N = -1
+            hint x: u32 = stack[0..2]
+            my_macro!(N, 2)
+        ));
+        let expected = ".const N = - 1 hint x : u32 = stack [ 0 ..2 ] my_macro! ( N , 2 )";
+        assert!(expected == source.code);
+    }
+
+    #[test]
+    fn interpolations_are_replaced_by_fitting_placeholders() {
+        let source = Source::new(quote!(
+            {label}: push {a} pop {b} dup {c} call {label} {instruction} {&list}
+            my_macro!({d}) .data 0 [{e}]
+        ));
+        let expected = "interpolated_label : push 0 pop 1 dup 0 call interpolated_label \
+            nop nop my_macro! ( 0 ) .data 0 [ 0 ]";
+        assert!(expected == source.code);
+        assert!(source.has_interpolations());
+    }
+
+    #[test]
+    fn blocks_of_structured_control_flow_are_not_interpolations() {
+        let source = Source::new(quote!(
+            if { push {a} } else { pop 1 }
+            repeat {n} { while { nop } }
+        ));
+        let expected = "if { push 0 } else { pop 1 } repeat 1 { while { nop } }";
+        assert!(expected == source.code);
+        assert!(
+            "if {{ push {} }} else {{ pop 1 }} repeat {} {{ while {{ nop }} }}"
+                == source.format_string
+        );
+    }
+
+    #[test]
+    fn interpolations_become_format_arguments() {
+        let source = Source::new(quote!(push {a} {&list} halt));
+        assert!("push {} {} halt" == source.format_string);
+        assert!(2 == source.interpolations.len());
+    }
+}
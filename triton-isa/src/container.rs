@@ -0,0 +1,744 @@
+//! A versioned binary format for storing a [`Program`], for example on disk.
+//!
+//! [Encoding](BFieldCodec::encode) a program discards all debug information, and
+//! the [serde] representation of a program is not stable across versions.
+//! A [`ProgramContainer`] is stable and, optionally, retains the debug information.
+//!
+//! A container starts with the [magic bytes](MAGIC_BYTES), followed by a sequence
+//! of [`BFieldElement`]s, each stored as 8 little-endian bytes. The sequence
+//! consists of
+//! 1. the [`Header`], which identifies the format version, the version of the
+//!    instruction set architecture, and the [digest](Program::hash) of the program,
+//! 1. the program's [`BFieldCodec`] encoding,
+//! 1. the program's [static data](Program::static_data), and
+//! 1. an optional debug section, holding labels, breakpoints, type hints,
+//!    assertion contexts, and source locations.
+//!
+//! Loading a container verifies that the program matches the digest in the header.
+//!
+//! # Examples
+//!
+//! ```
+//! # use triton_isa::container::ProgramContainer;
+//! # use triton_isa::triton_program;
+//! let program = triton_program!(call square halt square: dup 0 mul return);
+//! let bytes = ProgramContainer::new(program.clone()).to_bytes();
+//!
+//! let container = ProgramContainer::from_bytes(&bytes).unwrap();
+//! assert_eq!(program.hash(), container.header().digest);
+//! assert_eq!("square", container.program().label_for_address(3));
+//! ```
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::string::FromUtf8Error;
+
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use twenty_first::prelude::*;
+
+use crate::instruction::AssertionContext;
+use crate::instruction::StaticData;
+use crate::instruction::TypeHint;
+use crate::parser;
+use crate::program::DebugInformation;
+use crate::program::Program;
+use crate::program::SourceLocation;
+
+type Result<T> = std::result::Result<T, ContainerError>;
+
+/// The first bytes of every [`ProgramContainer`].
+pub const MAGIC_BYTES: [u8; 8] = *b"TRITONVM";
+
+/// The version of the container format produced by this version of the crate.
+/// Bumped whenever the format changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The number of bytes used to store a single [`BFieldElement`].
+const BYTES_PER_ELEMENT: usize = 8;
+
+/// The version of the instruction set architecture implemented by this crate.
+///
+/// Independent of the version of this crate, the ISA version changes only if
+/// programs are affected. The major version is bumped whenever the semantics or
+/// the encoding of an existing instruction change, the minor version whenever an
+/// instruction is added, and the patch version for changes that do not affect
+/// any program, like a clarified specification.
+pub const ISA_VERSION: IsaVersion = IsaVersion {
+    major: 1,
+    minor: 0,
+    patch: 0,
+};
+
+/// The version of the instruction set architecture a program was written for.
+/// See also [`ISA_VERSION`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, BFieldCodec)]
+pub struct IsaVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Display for IsaVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl IsaVersion {
+    /// Whether programs written for this version can be run by an implementation
+    /// of the `other` version. This is the case if the major versions agree, and
+    /// the `other` version has all instructions of this one.
+    pub fn is_compatible_with(self, other: Self) -> bool {
+        self.major == other.major && self.minor <= other.minor
+    }
+}
+
+/// The header of a [`ProgramContainer`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, BFieldCodec)]
+pub struct Header {
+    pub format_version: u32,
+    pub isa_version: IsaVersion,
+
+    /// The [digest](Program::hash) of the contained program.
+    pub digest: Digest,
+}
+
+impl Header {
+    fn new(program: &Program) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            isa_version: ISA_VERSION,
+            digest: program.hash(),
+        }
+    }
+
+    fn len() -> usize {
+        Self::static_length().expect("header must have static length")
+    }
+}
+
+/// A [`Program`] together with a [`Header`] identifying it. See the
+/// [module-level documentation](self) for details.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProgramContainer {
+    header: Header,
+    program: Program,
+    include_debug_information: bool,
+}
+
+impl ProgramContainer {
+    /// A container for the given program, including its debug information.
+    pub fn new(program: Program) -> Self {
+        Self {
+            header: Header::new(&program),
+            program,
+            include_debug_information: true,
+        }
+    }
+
+    /// Whether to store the program's debug information, like labels and
+    /// breakpoints. Storing it is the default.
+    #[must_use]
+    pub fn with_debug_information(mut self, include_debug_information: bool) -> Self {
+        self.include_debug_information = include_debug_information;
+        self
+    }
+
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    pub fn has_debug_information(&self) -> bool {
+        self.include_debug_information
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let elements = self.encode_elements();
+        let mut bytes = Vec::with_capacity(MAGIC_BYTES.len() + elements.len() * BYTES_PER_ELEMENT);
+        bytes.extend(MAGIC_BYTES);
+        for element in elements {
+            bytes.extend(element.value().to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Load a container, verifying that the contained program matches the
+    /// [digest](Header::digest) in the header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let elements = decode_bytes(bytes)?;
+        let header = decode_header(&elements)?;
+        if !header.isa_version.is_compatible_with(ISA_VERSION) {
+            return Err(ContainerError::IncompatibleIsaVersion {
+                found: header.isa_version,
+                current: ISA_VERSION,
+            });
+        }
+
+        let body = &elements[Header::len()..];
+        let (program, static_data, debug_section) =
+            *<Body>::decode(body).map_err(|err| ContainerError::DecodingError(err.to_string()))?;
+        let digest = program.hash();
+        if digest != header.digest {
+            return Err(ContainerError::DigestMismatch {
+                expected: header.digest,
+                actual: digest,
+            });
+        }
+
+        let include_debug_information = debug_section.is_some();
+        let mut program = Program {
+            static_data,
+            ..program
+        };
+        if let Some(debug_section) = debug_section {
+            debug_section.apply_to(&mut program)?;
+        }
+
+        Ok(Self {
+            header,
+            program,
+            include_debug_information,
+        })
+    }
+
+    /// Read only the header of a container, without decoding or verifying the
+    /// program.
+    pub fn read_header(bytes: &[u8]) -> Result<Header> {
+        decode_header(&decode_bytes(bytes)?)
+    }
+
+    fn encode_elements(&self) -> Vec<BFieldElement> {
+        let debug_section = self
+            .include_debug_information
+            .then(|| DebugSection::new(&self.program));
+        let body: Body = (
+            self.program.clone(),
+            self.program.static_data.clone(),
+            debug_section,
+        );
+        [self.header.encode(), body.encode()].concat()
+    }
+}
+
+/// Everything in a container except the header.
+type Body = (Program, Vec<StaticData>, Option<DebugSection>);
+
+fn decode_bytes(bytes: &[u8]) -> Result<Vec<BFieldElement>> {
+    let Some(bytes) = bytes.strip_prefix(&MAGIC_BYTES) else {
+        return Err(ContainerError::MissingMagicBytes);
+    };
+    if bytes.len() % BYTES_PER_ELEMENT != 0 {
+        return Err(ContainerError::InvalidLength(bytes.len()));
+    }
+
+    bytes
+        .chunks_exact(BYTES_PER_ELEMENT)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let value = u64::from_le_bytes(chunk.try_into().unwrap());
+            if !BFieldElement::is_canonical(value) {
+                return Err(ContainerError::NonCanonicalElement(index));
+            }
+            Ok(bfe!(value))
+        })
+        .collect()
+}
+
+fn decode_header(elements: &[BFieldElement]) -> Result<Header> {
+    let Some(header) = elements.get(..Header::len()) else {
+        return Err(ContainerError::MissingHeader);
+    };
+    let header = *Header::decode(header)
+        .map_err(|err| ContainerError::HeaderDecodingError(err.to_string()))?;
+    if header.format_version != FORMAT_VERSION {
+        return Err(ContainerError::UnsupportedFormatVersion(
+            header.format_version,
+        ));
+    }
+
+    Ok(header)
+}
+
+/// The debug information of a [`Program`] in a format that can be
+/// [encoded](BFieldCodec). Strings are stored as their UTF-8 bytes.
+#[derive(Debug, Default, Clone, Eq, PartialEq, BFieldCodec)]
+struct DebugSection {
+    labels: Vec<LabelEntry>,
+    breakpoints: Vec<u64>,
+    type_hints: Vec<TypeHintEntry>,
+    assertion_contexts: Vec<AssertionContextEntry>,
+    source_locations: Vec<SourceLocationEntry>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, BFieldCodec)]
+struct LabelEntry {
+    address: u64,
+    label: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, BFieldCodec)]
+struct TypeHintEntry {
+    address: u64,
+    starting_index: u64,
+    length: u64,
+    type_name: Option<Vec<u8>>,
+    variable_name: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, BFieldCodec)]
+struct AssertionContextEntry {
+    address: u64,
+    id: i128,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, BFieldCodec)]
+struct SourceLocationEntry {
+    address: u64,
+    file: Option<Vec<u8>>,
+    line: u64,
+    column: u64,
+}
+
+impl DebugSection {
+    /// Entries are sorted by address to make the encoding deterministic.
+    fn new(program: &Program) -> Self {
+        let labels = program
+            .address_to_label
+            .iter()
+            .sorted()
+            .map(|(&address, label)| LabelEntry {
+                address,
+                label: label.clone().into_bytes(),
+            })
+            .collect();
+
+        let debug_information = &program.debug_information;
+        let breakpoints = (0..)
+            .zip(&debug_information.breakpoints)
+            .filter(|&(_, &is_breakpoint)| is_breakpoint)
+            .map(|(address, _)| address)
+            .collect();
+
+        let type_hints = debug_information
+            .type_hints
+            .iter()
+            .sorted_by_key(|&(&address, _)| address)
+            .flat_map(|(&address, hints)| hints.iter().map(move |hint| (address, hint)))
+            .map(|(address, hint)| TypeHintEntry {
+                address,
+                starting_index: hint.starting_index as u64,
+                length: hint.length as u64,
+                type_name: hint.type_name.clone().map(String::into_bytes),
+                variable_name: hint.variable_name.clone().into_bytes(),
+            })
+            .collect();
+
+        let assertion_contexts = debug_information
+            .assertion_context
+            .iter()
+            .sorted_by_key(|&(&address, _)| address)
+            .map(|(&address, context)| match context {
+                AssertionContext::ID(id) => AssertionContextEntry { address, id: *id },
+            })
+            .collect();
+
+        let source_locations = debug_information
+            .source_map
+            .iter()
+            .sorted_by_key(|&(&address, _)| address)
+            .map(|(&address, location)| SourceLocationEntry {
+                address,
+                file: location.file.clone().map(String::into_bytes),
+                line: location.line as u64,
+                column: location.column as u64,
+            })
+            .collect();
+
+        Self {
+            labels,
+            breakpoints,
+            type_hints,
+            assertion_contexts,
+            source_locations,
+        }
+    }
+
+    fn apply_to(self, program: &mut Program) -> Result<()> {
+        let mut address_to_label = HashMap::new();
+        let mut seen_labels = HashSet::new();
+        for LabelEntry { address, label } in self.labels {
+            let label = String::from_utf8(label)?;
+            if !parser::is_legal_label(&label) {
+                return Err(ContainerError::IllegalLabel(label));
+            }
+            if !seen_labels.insert(label.clone()) {
+                return Err(ContainerError::DuplicateLabel(label));
+            }
+            address_to_label.insert(address, label);
+        }
+
+        let mut breakpoints = vec![false; program.len_bwords()];
+        for address in self.breakpoints {
+            let Some(is_breakpoint) = usize::try_from(address)
+                .ok()
+                .and_then(|address| breakpoints.get_mut(address))
+            else {
+                return Err(ContainerError::BreakpointOutOfBounds(address));
+            };
+            *is_breakpoint = true;
+        }
+
+        let mut type_hints = HashMap::<_, Vec<_>>::new();
+        for entry in self.type_hints {
+            let type_hint = TypeHint {
+                starting_index: usize::try_from(entry.starting_index)?,
+                length: usize::try_from(entry.length)?,
+                type_name: entry.type_name.map(String::from_utf8).transpose()?,
+                variable_name: String::from_utf8(entry.variable_name)?,
+            };
+            type_hints.entry(entry.address).or_default().push(type_hint);
+        }
+
+        let assertion_context = self
+            .assertion_contexts
+            .into_iter()
+            .map(|entry| (entry.address, AssertionContext::ID(entry.id)))
+            .collect();
+
+        let mut source_map = HashMap::new();
+        for entry in self.source_locations {
+            let location = SourceLocation {
+                file: entry.file.map(String::from_utf8).transpose()?,
+                line: usize::try_from(entry.line)?,
+                column: usize::try_from(entry.column)?,
+            };
+            source_map.insert(entry.address, location);
+        }
+
+        program.address_to_label = address_to_label;
+        program.debug_information = DebugInformation {
+            breakpoints,
+            type_hints,
+            assertion_context,
+            source_map,
+        };
+
+        Ok(())
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ContainerError {
+    #[error("missing magic bytes; not a program container")]
+    MissingMagicBytes,
+
+    #[error("length {0} is not a multiple of {BYTES_PER_ELEMENT}")]
+    InvalidLength(usize),
+
+    #[error("element {0} is not a canonical field element")]
+    NonCanonicalElement(usize),
+
+    #[error("container is too short to hold a header")]
+    MissingHeader,
+
+    #[error("container format version {0} is not supported")]
+    UnsupportedFormatVersion(u32),
+
+    #[error("program was written for ISA version {found}, which is incompatible with {current}")]
+    IncompatibleIsaVersion {
+        found: IsaVersion,
+        current: IsaVersion,
+    },
+
+    #[error("expected program digest {expected}, but got {actual}")]
+    DigestMismatch { expected: Digest, actual: Digest },
+
+    #[error("label \"{0}\" cannot be used in Triton assembly")]
+    IllegalLabel(String),
+
+    #[error("label \"{0}\" is used for more than one address")]
+    DuplicateLabel(String),
+
+    #[error("breakpoint at address {0} is outside of the program")]
+    BreakpointOutOfBounds(u64),
+
+    #[error(transparent)]
+    InvalidUtf8(#[from] FromUtf8Error),
+
+    #[error(transparent)]
+    OutOfRange(#[from] std::num::TryFromIntError),
+
+    #[error("failed to decode program container: {0}")]
+    DecodingError(String),
+
+    #[error("failed to decode header of program container: {0}")]
+    HeaderDecodingError(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+    use proptest::prelude::*;
+    use proptest_arbitrary_interop::arb;
+    use test_strategy::proptest;
+
+    use crate::instruction::ALL_INSTRUCTIONS;
+    use crate::triton_program;
+
+    use super::*;
+
+    #[proptest]
+    fn program_with_debug_information_survives_roundtrip(#[strategy(arb())] program: Program) {
+        let bytes = ProgramContainer::new(program.clone()).to_bytes();
+        let container = ProgramContainer::from_bytes(&bytes)?;
+        prop_assert!(container.has_debug_information());
+
+        let loaded_program = container.into_program();
+        prop_assert_eq!(&program, &loaded_program);
+        prop_assert_eq!(&program.address_to_label, &loaded_program.address_to_label);
+        prop_assert_eq!(
+            &program.debug_information,
+            &loaded_program.debug_information
+        );
+        prop_assert_eq!(&program.static_data, &loaded_program.static_data);
+    }
+
+    #[proptest]
+    fn program_without_debug_information_survives_roundtrip(#[strategy(arb())] program: Program) {
+        let container = ProgramContainer::new(program.clone()).with_debug_information(false);
+        let container = ProgramContainer::from_bytes(&container.to_bytes())?;
+        prop_assert!(!container.has_debug_information());
+
+        let loaded_program = container.into_program();
+        prop_assert_eq!(&program, &loaded_program);
+        prop_assert!(loaded_program.address_to_label.is_empty());
+        prop_assert_eq!(&program.static_data, &loaded_program.static_data);
+    }
+
+    #[test]
+    fn header_has_static_length() {
+        assert!(Some(9) == Header::static_length());
+    }
+
+    #[test]
+    fn header_can_be_read_on_its_own() {
+        let program = triton_program!(push 1 halt);
+        let bytes = ProgramContainer::new(program.clone()).to_bytes();
+        let header = ProgramContainer::read_header(&bytes).unwrap();
+
+        assert!(FORMAT_VERSION == header.format_version);
+        assert!(ISA_VERSION == header.isa_version);
+        assert!(program.hash() == header.digest);
+    }
+
+    #[test]
+    fn encoding_is_deterministic() {
+        let code = "
+            call foo call bar break halt
+            foo: hint a = stack[0] hint b = stack[1] assert error_id 1 return
+            bar: hint c = stack[2] assert error_id 2 return
+        ";
+        let program = Program::from_code(code).unwrap();
+        let bytes = ProgramContainer::new(program).to_bytes();
+        for _ in 0..10 {
+            let program = Program::from_code(code).unwrap();
+            assert!(bytes == ProgramContainer::new(program).to_bytes());
+        }
+    }
+
+    #[test]
+    fn source_locations_survive_roundtrip() {
+        let program = Program::from_code("push 1\n  pop 1\nhalt")
+            .unwrap()
+            .with_source_file("main.tasm");
+        let bytes = ProgramContainer::new(program.clone()).to_bytes();
+        let loaded_program = ProgramContainer::from_bytes(&bytes).unwrap().into_program();
+
+        assert!(
+            program.debug_information.source_map == loaded_program.debug_information.source_map
+        );
+        let_assert!(Some(location) = loaded_program.source_location(2));
+        assert!("main.tasm:2:3" == location.to_string());
+    }
+
+    #[test]
+    fn digest_mismatch_is_detected() {
+        let program = triton_program!(push 1 halt);
+        let other_program = triton_program!(push 2 halt);
+        let mut container = ProgramContainer::new(program);
+        container.header.digest = other_program.hash();
+
+        let_assert!(
+            Err(ContainerError::DigestMismatch { .. }) =
+                ProgramContainer::from_bytes(&container.to_bytes())
+        );
+    }
+
+    #[test]
+    fn incompatible_isa_version_is_detected() {
+        let newer_isa_versions = [
+            IsaVersion {
+                major: ISA_VERSION.major + 1,
+                ..ISA_VERSION
+            },
+            IsaVersion {
+                minor: ISA_VERSION.minor + 1,
+                ..ISA_VERSION
+            },
+        ];
+        for isa_version in newer_isa_versions {
+            let mut container = ProgramContainer::new(triton_program!(halt));
+            container.header.isa_version = isa_version;
+            let_assert!(
+                Err(ContainerError::IncompatibleIsaVersion { .. }) =
+                    ProgramContainer::from_bytes(&container.to_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn programs_for_same_isa_version_with_other_patch_level_are_compatible() {
+        let isa_version = IsaVersion {
+            patch: ISA_VERSION.patch + 1,
+            ..ISA_VERSION
+        };
+        assert!(isa_version.is_compatible_with(ISA_VERSION));
+        assert!(ISA_VERSION.is_compatible_with(isa_version));
+    }
+
+    /// If this test fails, the instruction set has changed. Bump [`ISA_VERSION`]
+    /// accordingly, then update the pinned version and fingerprint.
+    #[test]
+    fn isa_version_is_pinned_to_instruction_set() {
+        let instruction_set = ALL_INSTRUCTIONS
+            .iter()
+            .flat_map(|instruction| {
+                let properties = [
+                    instruction.opcode().into(),
+                    (instruction.size() as u64).into(),
+                    instruction.op_stack_size_influence().into(),
+                ];
+                let name = instruction.name().bytes().map(BFieldElement::from);
+                properties.into_iter().chain(name)
+            })
+            .collect_vec();
+        let fingerprint = Tip5::hash_varlen(&instruction_set).to_hex();
+
+        let pinned_isa_version = IsaVersion {
+            major: 1,
+            minor: 0,
+            patch: 0,
+        };
+        let pinned_fingerprint = "5110fceeb353663ee4d7b0a06fd45c02619a8f961ea7b0a2\
+                                  8aa979a445c82a37294bb74e7385c92b";
+        assert!(pinned_isa_version == ISA_VERSION);
+        assert!(pinned_fingerprint == fingerprint);
+    }
+
+    #[test]
+    fn unsupported_format_version_is_detected() {
+        let mut container = ProgramContainer::new(triton_program!(halt));
+        container.header.format_version = FORMAT_VERSION + 1;
+
+        let_assert!(
+            Err(ContainerError::UnsupportedFormatVersion(_)) =
+                ProgramContainer::read_header(&container.to_bytes())
+        );
+    }
+
+    #[test]
+    fn malformed_bytes_are_rejected() {
+        let bytes = ProgramContainer::new(triton_program!(halt)).to_bytes();
+
+        let_assert!(
+            Err(ContainerError::MissingMagicBytes) = ProgramContainer::from_bytes(&bytes[1..])
+        );
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let_assert!(
+            Err(ContainerError::InvalidLength(_)) = ProgramContainer::from_bytes(truncated)
+        );
+
+        let only_magic_bytes = &bytes[..MAGIC_BYTES.len()];
+        let_assert!(
+            Err(ContainerError::MissingHeader) = ProgramContainer::from_bytes(only_magic_bytes)
+        );
+
+        let mut non_canonical = bytes.clone();
+        non_canonical.extend(u64::MAX.to_le_bytes());
+        let_assert!(
+            Err(ContainerError::NonCanonicalElement(_)) =
+                ProgramContainer::from_bytes(&non_canonical)
+        );
+
+        let mut too_long = bytes;
+        too_long.extend(0_u64.to_le_bytes());
+        let_assert!(
+            Err(ContainerError::DecodingError(_)) = ProgramContainer::from_bytes(&too_long)
+        );
+    }
+
+    #[test]
+    fn faulty_debug_section_is_rejected() {
+        let program = triton_program!(push 1 halt);
+        let container = ProgramContainer::new(program.clone());
+        let to_bytes = |debug_section: DebugSection| {
+            let body: Body = (program.clone(), vec![], Some(debug_section));
+            let elements = [container.header.encode(), body.encode()].concat();
+            let element_bytes = elements.iter().flat_map(|e| e.value().to_le_bytes());
+            MAGIC_BYTES.into_iter().chain(element_bytes).collect_vec()
+        };
+        let label = |address, label: &str| LabelEntry {
+            address,
+            label: label.as_bytes().to_vec(),
+        };
+
+        let labels = vec![label(0, "push")];
+        let bytes = to_bytes(DebugSection {
+            labels,
+            ..Default::default()
+        });
+        let_assert!(Err(ContainerError::IllegalLabel(_)) = ProgramContainer::from_bytes(&bytes));
+
+        let labels = vec![label(0, "foo"), label(2, "foo")];
+        let bytes = to_bytes(DebugSection {
+            labels,
+            ..Default::default()
+        });
+        let_assert!(Err(ContainerError::DuplicateLabel(_)) = ProgramContainer::from_bytes(&bytes));
+
+        let labels = vec![LabelEntry {
+            address: 0,
+            label: vec![0xff],
+        }];
+        let bytes = to_bytes(DebugSection {
+            labels,
+            ..Default::default()
+        });
+        let_assert!(Err(ContainerError::InvalidUtf8(_)) = ProgramContainer::from_bytes(&bytes));
+
+        let bytes = to_bytes(DebugSection {
+            breakpoints: vec![3],
+            ..Default::default()
+        });
+        let_assert!(
+            Err(ContainerError::BreakpointOutOfBounds(3)) = ProgramContainer::from_bytes(&bytes)
+        );
+    }
+}
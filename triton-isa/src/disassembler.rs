@@ -0,0 +1,459 @@
+//! Turn a [`Program`] back into readable [Triton assembly][tasm].
+//!
+//! Encoding a program using [`BFieldCodec`] discards all labels and debug
+//! information. The [`Disassembler`] recovers a synthetic label for every
+//! address that is the target of some `call`, and optionally annotates every
+//! instruction with its address. Additionally, the original labels can be
+//! restored from a separately stored [`SymbolMap`].
+//!
+//! Since Triton assembly can only `call` labels, programs that `call` an
+//! address that is neither the start of an instruction nor the end of the
+//! program cannot be disassembled.
+//!
+//! # Examples
+//!
+//! ```
+//! # use triton_isa::disassembler::Disassembler;
+//! # use triton_isa::disassembler::SymbolMap;
+//! # use triton_isa::triton_program;
+//! # use twenty_first::prelude::*;
+//! let program = triton_program!(call square halt square: dup 0 mul return);
+//! let symbols = SymbolMap::from(&program);
+//!
+//! // later, only the encoding and the digest are known
+//! let encoding = program.encode();
+//! let digest = program.hash();
+//! let disassembler = Disassembler::from_encoding(&encoding, digest)
+//!     .unwrap()
+//!     .with_symbol_map(symbols)
+//!     .unwrap();
+//! assert!(disassembler.to_string().contains("call square"));
+//! ```
+//!
+//! [tasm]: https://triton-vm.org/spec/instructions.html
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use twenty_first::prelude::*;
+
+use crate::instruction::Instruction;
+use crate::instruction::LabelledInstruction;
+use crate::parser;
+use crate::program::Program;
+use crate::program::ProgramDecodingError;
+
+type Result<T> = std::result::Result<T, DisassemblerError>;
+
+/// The column at which address annotations start, unless the instruction is
+/// too long.
+const ANNOTATION_COLUMN: usize = 32;
+
+/// Labels of a [`Program`] by address, stored separately from the program itself.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SymbolMap {
+    labels: BTreeMap<u64, String>,
+}
+
+impl From<&Program> for SymbolMap {
+    fn from(program: &Program) -> Self {
+        let labels = program
+            .address_to_label()
+            .iter()
+            .map(|(&address, label)| (address, label.clone()))
+            .collect();
+        Self { labels }
+    }
+}
+
+impl SymbolMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the label of the given address, replacing any previous label.
+    #[must_use]
+    pub fn with_label(mut self, address: u64, label: impl Into<String>) -> Self {
+        self.labels.insert(address, label.into());
+        self
+    }
+
+    pub fn label_for_address(&self, address: u64) -> Option<&str> {
+        self.labels.get(&address).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+/// Produces readable [Triton assembly][tasm] for a [`Program`]. See the
+/// [module-level documentation](self) for details.
+///
+/// [tasm]: https://triton-vm.org/spec/instructions.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Disassembler {
+    program: Program,
+    symbols: SymbolMap,
+    annotate_addresses: bool,
+}
+
+impl Display for Disassembler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut address = 0;
+        for labelled_instruction in self.labelled_instructions() {
+            let LabelledInstruction::Instruction(instruction) = &labelled_instruction else {
+                if let LabelledInstruction::Label(_) = labelled_instruction {
+                    writeln!(f, "{labelled_instruction}")?;
+                } else {
+                    writeln!(f, "    {labelled_instruction}")?;
+                }
+                continue;
+            };
+
+            let instruction = format!("    {instruction}");
+            if self.annotate_addresses {
+                writeln!(f, "{instruction:<ANNOTATION_COLUMN$} // {address}")?;
+            } else {
+                writeln!(f, "{instruction}")?;
+            }
+            address += labelled_instruction_size(&labelled_instruction);
+        }
+
+        Ok(())
+    }
+}
+
+impl Disassembler {
+    /// Fails if some `call` targets an address that cannot be labelled, _i.e._,
+    /// an address that is neither the start of an instruction nor the end of
+    /// the program.
+    pub fn new(program: Program) -> Result<Self> {
+        let disassembler = Self {
+            program,
+            symbols: SymbolMap::default(),
+            annotate_addresses: false,
+        };
+
+        let instruction_addresses = disassembler.instruction_addresses();
+        if let Some(address) = disassembler
+            .call_targets()
+            .find(|address| !instruction_addresses.contains(address))
+        {
+            return Err(DisassemblerError::MisplacedCallTarget(address));
+        }
+
+        Ok(disassembler)
+    }
+
+    /// Decode a [`BFieldCodec`]-encoded program, making sure it has the
+    /// expected [digest](Program::hash).
+    pub fn from_encoding(encoding: &[BFieldElement], expected_digest: Digest) -> Result<Self> {
+        let program = *Program::decode(encoding)?;
+        let digest = program.hash();
+        if digest != expected_digest {
+            return Err(DisassemblerError::DigestMismatch {
+                expected: expected_digest,
+                actual: digest,
+            });
+        }
+
+        Self::new(program)
+    }
+
+    /// Use the labels of the given symbol map. Addresses that are called but
+    /// have no label in the symbol map still get a synthetic label.
+    ///
+    /// Fails if some label cannot be used in Triton assembly, if some label is
+    /// used for more than one address, or if some address is not the start of
+    /// an instruction.
+    pub fn with_symbol_map(mut self, symbols: SymbolMap) -> Result<Self> {
+        let instruction_addresses = self.instruction_addresses();
+        let mut seen_labels = HashSet::new();
+        for (&address, label) in &symbols.labels {
+            if !parser::is_legal_label(label) {
+                return Err(DisassemblerError::IllegalLabel(label.clone()));
+            }
+            if !seen_labels.insert(label) {
+                return Err(DisassemblerError::DuplicateLabel(label.clone()));
+            }
+            if !instruction_addresses.contains(&address) {
+                let label = label.clone();
+                return Err(DisassemblerError::MisplacedLabel { address, label });
+            }
+        }
+
+        self.symbols = symbols;
+        Ok(self)
+    }
+
+    /// Annotate every instruction with its address, in form of a comment.
+    #[must_use]
+    pub fn with_address_annotations(mut self, annotate_addresses: bool) -> Self {
+        self.annotate_addresses = annotate_addresses;
+        self
+    }
+
+    /// The labels of the disassembled program, by address. Every address in the
+    /// symbol map has a label, as does every address that is the target of some
+    /// `call`.
+    pub fn labels(&self) -> HashMap<u64, String> {
+        let mut labels = self
+            .symbols
+            .labels
+            .clone()
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let mut used_labels = labels.values().cloned().collect::<HashSet<_>>();
+
+        for address in self.call_targets() {
+            if labels.contains_key(&address) {
+                continue;
+            }
+            let mut label = self.program.label_for_address(address);
+            while used_labels.contains(&label) {
+                label.push('_');
+            }
+            used_labels.insert(label.clone());
+            labels.insert(address, label);
+        }
+
+        labels
+    }
+
+    /// The disassembled program, including recovered labels.
+    pub fn labelled_instructions(&self) -> Vec<LabelledInstruction> {
+        self.program
+            .labelled_instructions_with_labels(&self.labels())
+    }
+
+    fn call_targets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.program
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Call(address) => Some(address.value()),
+                _ => None,
+            })
+    }
+
+    /// All addresses at which a label can be placed: the start of every
+    /// instruction, and the end of the program.
+    fn instruction_addresses(&self) -> HashSet<u64> {
+        let mut addresses = HashSet::from([0]);
+        let mut address = 0;
+        for instruction in self.program.clone() {
+            address += instruction.size() as u64;
+            addresses.insert(address);
+        }
+        addresses
+    }
+}
+
+fn labelled_instruction_size(labelled_instruction: &LabelledInstruction) -> u64 {
+    match labelled_instruction {
+        LabelledInstruction::Instruction(instruction) => instruction.size() as u64,
+        _ => 0,
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum DisassemblerError {
+    #[error(transparent)]
+    DecodingError(#[from] ProgramDecodingError),
+
+    #[error("expected program digest {expected}, but got {actual}")]
+    DigestMismatch { expected: Digest, actual: Digest },
+
+    #[error("label \"{0}\" cannot be used in Triton assembly")]
+    IllegalLabel(String),
+
+    #[error("label \"{0}\" is used for more than one address")]
+    DuplicateLabel(String),
+
+    #[error("label \"{label}\" at address {address} is not at the start of an instruction")]
+    MisplacedLabel { address: u64, label: String },
+
+    #[error("call target {0} is not the start of an instruction")]
+    MisplacedCallTarget(u64),
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest_arbitrary_interop::arb;
+    use test_strategy::proptest;
+
+    use crate::triton_program;
+
+    use super::*;
+
+    #[proptest]
+    fn disassembled_program_can_be_parsed_into_original_program(
+        #[strategy(arb())] program: Program,
+        annotate_addresses: bool,
+    ) {
+        let encoding = program.encode();
+        let disassembler = Disassembler::from_encoding(&encoding, program.hash())?
+            .with_address_annotations(annotate_addresses);
+        let reparsed_program = Program::from_code(&disassembler.to_string())?;
+        prop_assert_eq!(program, reparsed_program);
+    }
+
+    #[proptest]
+    fn disassembled_program_with_arbitrary_call_targets_can_be_parsed_into_original_program(
+        #[strategy(arb())] program: Program,
+        #[strategy(vec(0_u64..64, 0..8))] call_targets: Vec<u64>,
+    ) {
+        let calls = call_targets.iter().flat_map(|&target| {
+            let call = Instruction::Call(bfe!(target));
+            [bfe!(call.opcode()), bfe!(target)]
+        });
+        let code = program.to_bwords().into_iter().chain(calls).collect_vec();
+        let encoding = [bfe!(code.len() as u64)]
+            .into_iter()
+            .chain(code)
+            .collect_vec();
+        let program = *Program::decode(&encoding)?;
+
+        let instruction_addresses = Disassembler {
+            program: program.clone(),
+            symbols: SymbolMap::default(),
+            annotate_addresses: false,
+        }
+        .instruction_addresses();
+        let all_call_targets_are_labellable = call_targets
+            .iter()
+            .all(|target| instruction_addresses.contains(target));
+
+        let disassembler = match Disassembler::new(program.clone()) {
+            Ok(disassembler) => disassembler,
+            Err(DisassemblerError::MisplacedCallTarget(target)) => {
+                prop_assert!(!all_call_targets_are_labellable);
+                prop_assert!(!instruction_addresses.contains(&target));
+                return Ok(());
+            }
+            Err(err) => return Err(TestCaseError::fail(err.to_string())),
+        };
+        prop_assert!(all_call_targets_are_labellable);
+        let reparsed_program = Program::from_code(&disassembler.to_string())?;
+        prop_assert_eq!(program, reparsed_program);
+    }
+
+    #[test]
+    fn every_call_target_gets_a_synthetic_label() {
+        let program = triton_program!(call foo call bar halt foo: return bar: return);
+        let program = *Program::decode(&program.encode()).unwrap();
+        let disassembler = Disassembler::new(program).unwrap();
+
+        let labels = disassembler.labels();
+        assert!(Some("address_5") == labels.get(&5).map(String::as_str));
+        assert!(Some("address_6") == labels.get(&6).map(String::as_str));
+        assert!(2 == labels.len());
+    }
+
+    #[test]
+    fn symbol_map_restores_original_labels() {
+        let program = triton_program!(call foo halt foo: return bar: return);
+        let symbols = SymbolMap::from(&program);
+        let program = *Program::decode(&program.encode()).unwrap();
+
+        let disassembler = Disassembler::new(program)
+            .unwrap()
+            .with_symbol_map(symbols)
+            .unwrap();
+        let code = disassembler.to_string();
+        assert!(code.contains("call foo"));
+        assert!(code.contains("foo:"));
+        assert!(code.contains("bar:"));
+        assert!(!code.contains("address_"));
+    }
+
+    #[test]
+    fn synthetic_labels_do_not_clash_with_symbols() {
+        let program = triton_program!(call foo call bar halt foo: return bar: return);
+        let program = *Program::decode(&program.encode()).unwrap();
+        let symbols = SymbolMap::new().with_label(5, "address_6");
+        let disassembler = Disassembler::new(program.clone())
+            .unwrap()
+            .with_symbol_map(symbols)
+            .unwrap();
+
+        let labels = disassembler.labels();
+        assert!("address_6" == labels[&5]);
+        assert!("address_6_" == labels[&6]);
+
+        let reparsed_program = Program::from_code(&disassembler.to_string()).unwrap();
+        assert!(program == reparsed_program);
+    }
+
+    #[test]
+    fn call_into_multi_word_instruction_is_rejected() {
+        let program = triton_program!(push 1 call foo halt foo: return);
+        let mut encoding = program.encode();
+        let call_target_index = 4;
+        assert!(bfe!(5) == encoding[call_target_index]);
+        encoding[call_target_index] = bfe!(1);
+        let program = *Program::decode(&encoding).unwrap();
+
+        let_assert!(Err(DisassemblerError::MisplacedCallTarget(1)) = Disassembler::new(program));
+    }
+
+    #[test]
+    fn addresses_are_annotated() {
+        let program = triton_program!(push 1 pop 1 halt);
+        let disassembler = Disassembler::new(program)
+            .unwrap()
+            .with_address_annotations(true);
+        let code = disassembler.to_string();
+        let annotations = code
+            .lines()
+            .map(|line| line.split("// ").last().unwrap())
+            .collect::<Vec<_>>();
+        assert!(vec!["0", "2", "4"] == annotations);
+    }
+
+    #[test]
+    fn digest_mismatch_is_detected() {
+        let program = triton_program!(push 1 halt);
+        let other_program = triton_program!(push 2 halt);
+        let encoding = program.encode();
+        let_assert!(
+            Err(DisassemblerError::DigestMismatch { .. }) =
+                Disassembler::from_encoding(&encoding, other_program.hash())
+        );
+    }
+
+    #[test]
+    fn faulty_symbol_maps_are_rejected() {
+        let program = triton_program!(push 1 halt);
+        let with_symbols = |symbols| Disassembler::new(program.clone())?.with_symbol_map(symbols);
+
+        let symbols = SymbolMap::new().with_label(0, "push");
+        let_assert!(Err(DisassemblerError::IllegalLabel(_)) = with_symbols(symbols));
+
+        let symbols = SymbolMap::new().with_label(0, "not a label");
+        let_assert!(Err(DisassemblerError::IllegalLabel(_)) = with_symbols(symbols));
+
+        let symbols = SymbolMap::new().with_label(0, "foo").with_label(2, "foo");
+        let_assert!(Err(DisassemblerError::DuplicateLabel(_)) = with_symbols(symbols));
+
+        let symbols = SymbolMap::new().with_label(1, "foo");
+        let_assert!(Err(DisassemblerError::MisplacedLabel { .. }) = with_symbols(symbols));
+
+        let symbols = SymbolMap::new().with_label(3, "end");
+        let_assert!(Ok(_) = with_symbols(symbols));
+    }
+}
@@ -1,5 +1,8 @@
+pub use crate::container::ContainerError;
+pub use crate::disassembler::DisassemblerError;
 pub use crate::instruction::AssertionError;
 pub use crate::instruction::InstructionError;
+pub use crate::linker::LinkError;
 pub use crate::op_stack::NumberOfWordsError;
 pub use crate::op_stack::OpStackElementError;
 pub use crate::op_stack::OpStackError;
@@ -0,0 +1,512 @@
+//! Format [Triton assembly][tasm] source code canonically.
+//!
+//! Unlike [`Display` for `Program`](crate::program::Program), the formatter works
+//! on the source code itself and keeps comments, blank lines, named constants,
+//! and directives intact. Every label and every directive is put on a line of
+//! its own, flush with the left margin, while instructions and annotations are
+//! indented. The bodies of structured control flow, like `if { … }`, are indented
+//! one more level. Assertion contexts stay on the line of the assertion they
+//! belong to. Consecutive blank lines are collapsed into one.
+//!
+//! # Examples
+//!
+//! ```
+//! # use triton_isa::formatter;
+//! let code = "main: push 1 // one\n\n\n  assert   error_id 7 halt";
+//! let formatted = formatter::format(code).unwrap();
+//! assert_eq!(
+//!     "main:\n    push 1 // one\n\n    assert error_id 7\n    halt\n",
+//!     formatted,
+//! );
+//! ```
+//!
+//! [tasm]: https://triton-vm.org/spec/instructions.html
+
+use itertools::Itertools;
+use nom::Finish;
+
+use crate::parser;
+use crate::parser::InstructionToken;
+use crate::parser::ParseError;
+
+/// The number of spaces per level of indentation.
+const INDENTATION_WIDTH: usize = 4;
+
+/// Format the given source code. See the [module-level documentation](self) for
+/// details.
+///
+/// Only the syntax of the source code is checked, not its semantics. In
+/// particular, calls to labels that are not defined in the source code are fine.
+/// This allows formatting [modules](crate::linker) in isolation.
+pub fn format(code: &str) -> Result<String, ParseError<'_>> {
+    parser::tokenize(code)
+        .finish()
+        .map_err(|errors| ParseError {
+            input: code,
+            errors,
+        })?;
+
+    let lexemes = lex(code);
+    let lines = LineBuilder::new(code, &lexemes).build();
+    Ok(render(&lines))
+}
+
+/// The smallest meaningful units of source code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Lexeme<'a> {
+    Word(&'a str),
+    Punctuation(char),
+    Comment(&'a str),
+    Newline,
+}
+
+const PUNCTUATION: [char; 9] = ['(', ')', '[', ']', '{', '}', ',', '=', ':'];
+
+fn lex(code: &str) -> Vec<Lexeme<'_>> {
+    let mut lexemes = vec![];
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            lexemes.push(Lexeme::Newline);
+            rest = &rest[1..];
+        } else if c.is_whitespace() {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() && c != '\n');
+        } else if rest.starts_with("//") {
+            let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+            lexemes.push(Lexeme::Comment(rest[..end].trim_end()));
+            rest = &rest[end..];
+        } else if PUNCTUATION.contains(&c) {
+            lexemes.push(Lexeme::Punctuation(c));
+            rest = &rest[c.len_utf8()..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || PUNCTUATION.contains(&c))
+                .unwrap_or(rest.len());
+            let end = rest[..end].find("//").unwrap_or(end);
+            lexemes.push(Lexeme::Word(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    lexemes
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum LineKind {
+    Label,
+    Directive,
+    Instruction,
+    Annotation,
+    Comment,
+}
+
+/// One line of formatted output.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Line {
+    kind: LineKind,
+    indentation: usize,
+    code: String,
+    comment: Option<String>,
+    has_blank_line_before: bool,
+}
+
+impl Line {
+    fn add_comment(&mut self, comment: &str) {
+        match &mut self.comment {
+            Some(existing) => {
+                existing.push(' ');
+                existing.push_str(comment);
+            }
+            None => self.comment = Some(comment.to_string()),
+        }
+    }
+}
+
+/// Groups [`Lexeme`]s into [`Line`]s.
+#[derive(Debug)]
+struct LineBuilder<'a, 'l> {
+    code: &'a str,
+    lexemes: &'l [Lexeme<'a>],
+    position: usize,
+    lines: Vec<Line>,
+
+    /// The number of line breaks since the end of the last line.
+    num_newlines: usize,
+
+    /// The comments encountered in the middle of the current item.
+    inner_comments: Vec<&'a str>,
+    is_in_macro_body: bool,
+
+    /// The number of blocks of structured control flow enclosing the current item.
+    block_depth: usize,
+}
+
+impl<'a, 'l> LineBuilder<'a, 'l> {
+    fn new(code: &'a str, lexemes: &'l [Lexeme<'a>]) -> Self {
+        Self {
+            code,
+            lexemes,
+            position: 0,
+            lines: vec![],
+            num_newlines: 0,
+            inner_comments: vec![],
+            is_in_macro_body: false,
+            block_depth: 0,
+        }
+    }
+
+    fn build(mut self) -> Vec<Line> {
+        while let Some(&lexeme) = self.lexemes.get(self.position) {
+            self.position += 1;
+            match lexeme {
+                Lexeme::Newline => self.num_newlines += 1,
+                Lexeme::Comment(comment) => self.comment(comment),
+                Lexeme::Word(word) => self.item(word),
+                Lexeme::Punctuation('}') => self.close_block(),
+                Lexeme::Punctuation(_) => unreachable!("syntax was checked by the parser"),
+            }
+        }
+        self.indent_comment_lines();
+
+        self.lines
+    }
+
+    fn comment(&mut self, comment: &str) {
+        if self.num_newlines == 0 {
+            if let Some(line) = self.lines.last_mut() {
+                line.add_comment(comment);
+                return;
+            }
+        }
+
+        self.push_line(LineKind::Comment, String::new());
+        self.lines.last_mut().unwrap().comment = Some(comment.to_string());
+    }
+
+    fn item(&mut self, word: &'a str) {
+        let (kind, code) = match word {
+            "hint" => (LineKind::Annotation, self.type_hint(word)),
+            "break" => (LineKind::Annotation, word.to_string()),
+            "error_id" => {
+                let id = self.next_word();
+                self.assertion_context(format!("{word} {id}"));
+                return;
+            }
+            ".const" => {
+                let name = self.next_word();
+                self.next_punctuation();
+                let value = self.next_word();
+                (LineKind::Directive, format!("{word} {name} = {value}"))
+            }
+            ".macro" => {
+                let name = self.next_word();
+                let parameters = self.optional_list('(', ')');
+                self.is_in_macro_body = true;
+                let parameters = parameters.map(|p| format!("({p})")).unwrap_or_default();
+                (LineKind::Directive, format!("{word} {name}{parameters}"))
+            }
+            ".endmacro" => {
+                self.is_in_macro_body = false;
+                (LineKind::Directive, word.to_string())
+            }
+            ".data" => {
+                let address = self.next_word();
+                let values = self.optional_list('[', ']').unwrap_or_default();
+                (LineKind::Directive, format!("{word} {address} [{values}]"))
+            }
+            "if" | "while" if self.peek() == Some(Lexeme::Punctuation('{')) => {
+                self.next_punctuation();
+                self.open_block(format!("{word} {{"));
+                return;
+            }
+            "repeat" if self.peek() != Some(Lexeme::Punctuation(':')) => {
+                let num_iterations = self.next_word();
+                self.next_punctuation();
+                self.open_block(format!("{word} {num_iterations} {{"));
+                return;
+            }
+            _ if word.ends_with('!') => {
+                let arguments = self.optional_list('(', ')');
+                let arguments = arguments.map(|a| format!("({a})")).unwrap_or_default();
+                (LineKind::Instruction, format!("{word}{arguments}"))
+            }
+            _ if self.peek() == Some(Lexeme::Punctuation(':')) => {
+                self.next_punctuation();
+                (LineKind::Label, format!("{word}:"))
+            }
+            _ if parser::instruction_takes_argument(word) => {
+                let argument = self.next_word();
+                (LineKind::Instruction, format!("{word} {argument}"))
+            }
+            _ => (LineKind::Instruction, word.to_string()),
+        };
+
+        self.push_line(kind, code);
+    }
+
+    fn open_block(&mut self, code: String) {
+        self.push_line(LineKind::Instruction, code);
+        self.block_depth += 1;
+    }
+
+    /// A closing brace, possibly followed by an `else` branch.
+    fn close_block(&mut self) {
+        self.block_depth -= 1;
+        if self.peek() != Some(Lexeme::Word("else")) {
+            self.push_line(LineKind::Instruction, "}".to_string());
+            return;
+        }
+
+        self.next_word();
+        self.next_punctuation();
+        self.open_block("} else {".to_string());
+    }
+
+    /// Type hints are parsed again to bring the involved ranges into canonical
+    /// form.
+    fn type_hint(&mut self, hint: &'a str) -> String {
+        while self.next_lexeme() != Some(Lexeme::Punctuation(']')) {}
+        let start = hint.as_ptr() as usize - self.code.as_ptr() as usize;
+        let source = &self.code[start..];
+        let end = source.find(']').unwrap() + 1;
+
+        let Ok((_, InstructionToken::TypeHint(type_hint, _))) = parser::type_hint(&source[..end])
+        else {
+            unreachable!("syntax was checked by the parser");
+        };
+        type_hint.to_string()
+    }
+
+    /// Assertion contexts are put on the same line as the assertion.
+    fn assertion_context(&mut self, context: String) {
+        let Some(line) = self
+            .lines
+            .last_mut()
+            .filter(|line| line.kind == LineKind::Instruction)
+        else {
+            self.push_line(LineKind::Annotation, context);
+            return;
+        };
+        line.code.push(' ');
+        line.code.push_str(&context);
+        for comment in std::mem::take(&mut self.inner_comments) {
+            line.add_comment(comment);
+        }
+        self.num_newlines = 0;
+    }
+
+    fn push_line(&mut self, kind: LineKind, code: String) {
+        let code_depth = usize::from(self.is_in_macro_body) + self.block_depth;
+        let depth = match kind {
+            LineKind::Directive | LineKind::Comment => 0,
+            LineKind::Label => code_depth,
+            LineKind::Instruction | LineKind::Annotation => code_depth + 1,
+        };
+        let has_blank_line_before = self.num_newlines > 1 && !self.lines.is_empty();
+        let mut line = Line {
+            kind,
+            indentation: depth * INDENTATION_WIDTH,
+            code,
+            comment: None,
+            has_blank_line_before,
+        };
+        for comment in std::mem::take(&mut self.inner_comments) {
+            line.add_comment(comment);
+        }
+
+        self.lines.push(line);
+        self.num_newlines = 0;
+    }
+
+    /// A comment on a line of its own is indented like the next line of code.
+    fn indent_comment_lines(&mut self) {
+        let mut indentation = 0;
+        for line in self.lines.iter_mut().rev() {
+            if line.kind == LineKind::Comment {
+                line.indentation = indentation;
+            } else {
+                indentation = line.indentation;
+            }
+        }
+    }
+
+    /// The next lexeme that is neither a line break nor a comment, without
+    /// consuming it.
+    fn peek(&self) -> Option<Lexeme<'a>> {
+        self.lexemes[self.position..]
+            .iter()
+            .copied()
+            .find(|lexeme| !matches!(lexeme, Lexeme::Newline | Lexeme::Comment(_)))
+    }
+
+    /// Consume the next lexeme that is neither a line break nor a comment.
+    /// Comments are remembered, to be attached to the current line.
+    fn next_lexeme(&mut self) -> Option<Lexeme<'a>> {
+        while let Some(&lexeme) = self.lexemes.get(self.position) {
+            self.position += 1;
+            match lexeme {
+                Lexeme::Newline => (),
+                Lexeme::Comment(comment) => self.inner_comments.push(comment),
+                _ => return Some(lexeme),
+            }
+        }
+        None
+    }
+
+    fn next_word(&mut self) -> &'a str {
+        let Some(Lexeme::Word(word)) = self.next_lexeme() else {
+            unreachable!("syntax was checked by the parser");
+        };
+        word
+    }
+
+    fn next_punctuation(&mut self) {
+        let Some(Lexeme::Punctuation(_)) = self.next_lexeme() else {
+            unreachable!("syntax was checked by the parser");
+        };
+    }
+
+    /// Consume a delimited, comma-separated list of words, if there is one. The
+    /// list is returned in canonical form, without the delimiters.
+    fn optional_list(&mut self, open: char, close: char) -> Option<String> {
+        if self.peek() != Some(Lexeme::Punctuation(open)) {
+            return None;
+        }
+        self.next_punctuation();
+
+        let mut words = vec![];
+        loop {
+            match self.next_lexeme() {
+                Some(Lexeme::Word(word)) => words.push(word),
+                Some(Lexeme::Punctuation(c)) if c == close => break,
+                Some(Lexeme::Punctuation(_)) => (),
+                _ => unreachable!("syntax was checked by the parser"),
+            }
+        }
+
+        Some(words.join(", "))
+    }
+}
+
+fn render(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let blank_line = if line.has_blank_line_before { "\n" } else { "" };
+            let indentation = " ".repeat(line.indentation);
+            let code_and_comment = match &line.comment {
+                Some(comment) if line.code.is_empty() => comment.clone(),
+                Some(comment) => format!("{} {comment}", line.code),
+                None => line.code.clone(),
+            };
+            format!("{blank_line}{indentation}{code_and_comment}\n")
+        })
+        .join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use proptest::prelude::*;
+    use proptest_arbitrary_interop::arb;
+    use test_strategy::proptest;
+
+    use crate::program::Program;
+
+    use super::*;
+
+    #[proptest]
+    fn formatted_program_is_equivalent_to_original(#[strategy(arb())] program: Program) {
+        let formatted = format(&program.to_string())?;
+        prop_assert_eq!(program, Program::from_code(&formatted)?);
+    }
+
+    #[proptest]
+    fn formatting_is_idempotent(#[strategy(arb())] program: Program) {
+        let formatted = format(&program.to_string())?;
+        prop_assert_eq!(&formatted, &format(&formatted)?);
+    }
+
+    #[test]
+    fn bodies_of_structured_control_flow_are_indented() {
+        let code =
+            ".const N = 3\npush 1 if { push 2 } else { repeat N { nop } }\nwhile{ nop } halt";
+        let expected = ".const N = 3
+    push 1
+    if {
+        push 2
+    } else {
+        repeat N {
+            nop
+        }
+    }
+    while {
+        nop
+    }
+    halt
+";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn labels_are_flush_and_instructions_are_indented() {
+        let code = "  main: push 1 call foo halt\nfoo:return";
+        let expected = "main:\n    push 1\n    call foo\n    halt\nfoo:\n    return\n";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn comments_are_kept() {
+        let code = "// entry point\nmain: // the main function\n    push 1 // one\n// done\nhalt";
+        let expected = "// entry point\nmain: // the main function\n    push 1 // one\n    \
+            // done\n    halt\n";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn consecutive_blank_lines_are_collapsed() {
+        let code = "\n\npush 1\n\n\n\npush 2\n  \npush 3\n\n";
+        let expected = "    push 1\n\n    push 2\n\n    push 3\n";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn annotations_are_formatted_canonically() {
+        let code = "hint  foo :Bar=stack[ 0 .. 2 ] break push 0 assert\nerror_id -3 halt";
+        let expected = "    hint foo: Bar = stack[0..2]\n    break\n    push 0\n    \
+            assert error_id -3\n    halt\n";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn comment_between_assertion_and_context_moves_to_end_of_line() {
+        let code = "assert // must hold\nerror_id 0";
+        assert!("    assert error_id 0 // must hold\n" == format(code).unwrap());
+    }
+
+    #[test]
+    fn directives_and_named_constants_are_kept() {
+        let code = ".const  N=3 .macro add_n( x ,y ) push x addi N pop 1\n.endmacro\n\
+            .data N [ 1,2 , 3 ] add_n!(1,N) halt";
+        let expected = ".const N = 3\n.macro add_n(x, y)\n        push x\n        addi N\n        \
+            pop 1\n.endmacro\n.data N [1, 2, 3]\n    add_n!(1, N)\n    halt\n";
+        assert!(expected == format(code).unwrap());
+    }
+
+    #[test]
+    fn calls_to_undefined_labels_can_be_formatted() {
+        assert!("    call foo\n" == format("call foo").unwrap());
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert!(format("push").is_err());
+        assert!(format("foo: bar").is_err());
+    }
+
+    #[test]
+    fn empty_code_formats_to_empty_string() {
+        assert!(format("").unwrap().is_empty());
+        assert!(format("\n \n").unwrap().is_empty());
+    }
+}
@@ -113,6 +113,10 @@ pub enum LabelledInstruction {
     TypeHint(TypeHint),
 
     AssertionContext(AssertionContext),
+
+    /// Data that is placed in RAM before the program starts, declared with directive
+    /// `.data`.
+    StaticData(StaticData),
 }
 
 /// A hint about a range of stack elements. Helps debugging programs written for Triton VM.
@@ -144,6 +148,32 @@ pub enum AssertionContext {
     // Message(String),
 }
 
+/// A block of consecutive memory cells with a fixed initial value. Does not influence
+/// the program itself, but the initial state of RAM, which is non-deterministic.
+///
+/// Usually constructed by parsing the directive `.data`, for example:
+/// ```tasm
+/// .data 1000 [1, 2, 3]
+/// ```
+#[derive(
+    Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, GetSize, BFieldCodec, Arbitrary,
+)]
+pub struct StaticData {
+    /// The RAM address of the first value.
+    pub address: BFieldElement,
+
+    /// The values, placed at consecutive RAM addresses.
+    pub values: Vec<BFieldElement>,
+}
+
+impl StaticData {
+    /// The `(address, value)` pairs making up this block of static data.
+    pub fn ram_entries(&self) -> impl Iterator<Item = (BFieldElement, BFieldElement)> + '_ {
+        let addresses = (0..).map(|offset: u64| self.address + bfe!(offset));
+        addresses.zip(self.values.iter().copied())
+    }
+}
+
 impl LabelledInstruction {
     pub const fn op_stack_size_influence(&self) -> i32 {
         match self {
@@ -161,6 +191,7 @@ impl Display for LabelledInstruction {
             LabelledInstruction::Breakpoint => write!(f, "break"),
             LabelledInstruction::TypeHint(type_hint) => write!(f, "{type_hint}"),
             LabelledInstruction::AssertionContext(ctx) => write!(f, "{ctx}"),
+            LabelledInstruction::StaticData(data) => write!(f, "{data}"),
         }
     }
 }
@@ -190,6 +221,14 @@ impl Display for AssertionContext {
     }
 }
 
+impl Display for StaticData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let address = self.address;
+        let values = self.values.iter().join(", ");
+        write!(f, ".data {address} [{values}]")
+    }
+}
+
 impl<'a> Arbitrary<'a> for TypeHint {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let starting_index = u.arbitrary()?;
@@ -718,6 +757,7 @@ impl<'a> Arbitrary<'a> for LabelledInstruction {
             2 => return Ok(Self::Breakpoint),
             3 => return Ok(Self::TypeHint(u.arbitrary()?)),
             4 => return Ok(Self::AssertionContext(u.arbitrary()?)),
+            5 => return Ok(Self::StaticData(u.arbitrary()?)),
             _ => unreachable!(),
         };
         let legal_label = String::from(u.arbitrary::<InstructionLabel>()?);
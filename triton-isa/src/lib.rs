@@ -1,10 +1,17 @@
+#![recursion_limit = "4096"]
+
 pub use twenty_first;
 
+pub mod container;
+pub mod disassembler;
 pub mod error;
+pub mod formatter;
 pub mod instruction;
+pub mod linker;
 pub mod op_stack;
 pub mod parser;
 pub mod program;
+pub mod stdlib;
 
 /// Compile an entire program written in [Triton assembly][tasm].
 /// Triton VM can run the resulting [`Program`](program::Program); see there for
@@ -142,6 +149,33 @@ macro_rules! triton_program {
 /// # assert_eq!(LabelledInstruction::Instruction(Push(one)), surrounding_code[4]);
 ///```
 ///
+/// Structured control flow, which is lowered to plain instructions. The branches
+/// and loop bodies become subroutines with generated labels, placed at the end of
+/// the returned instructions. Make sure execution cannot fall through to them,
+/// for example by ending the snippet in `return`. See
+/// [`tokenize`](parser::tokenize) for details on the supported constructs.
+///
+/// ```
+/// # use triton_isa::triton_asm;
+/// # use triton_isa::instruction::LabelledInstruction;
+/// let num_words = 3;
+/// let instructions = triton_asm!(
+///     absolute_difference:
+///         dup 1 dup 1 lt
+///         if { swap 1 } // ensure the larger element is on top
+///         pick 1 push -1 mul add
+///         return
+///     read_and_discard:
+///         repeat {num_words} { read_io 1 pop 1 }
+///         return
+/// );
+/// let num_labels = instructions
+///     .iter()
+///     .filter(|instruction| matches!(instruction, LabelledInstruction::Label(_)))
+///     .count();
+/// assert_eq!(4, num_labels);
+/// ```
+///
 /// # Panics
 ///
 /// **Panics** if the instructions cannot be parsed.
@@ -186,6 +220,46 @@ macro_rules! triton_asm {
             $($tail)*
         )
     };
+    (@fmt $fmt:expr, $($args:expr,)*;
+        if { $($then:tt)* } else { $($else:tt)* } $($tail:tt)*) => {
+        $crate::triton_asm!(@fmt
+            concat!($fmt, " if {{ {} }} else {{ {} }} "),
+            $($args,)*
+            $crate::triton_asm!(@fmt "",; $($then)*),
+            $crate::triton_asm!(@fmt "",; $($else)*),;
+            $($tail)*
+        )
+    };
+    (@fmt $fmt:expr, $($args:expr,)*; if { $($then:tt)* } $($tail:tt)*) => {
+        $crate::triton_asm!(@fmt
+            concat!($fmt, " if {{ {} }} "),
+            $($args,)* $crate::triton_asm!(@fmt "",; $($then)*),;
+            $($tail)*
+        )
+    };
+    (@fmt $fmt:expr, $($args:expr,)*;
+        repeat {$num_iterations:expr} { $($body:tt)* } $($tail:tt)*) => {
+        $crate::triton_asm!(@fmt
+            concat!($fmt, " repeat {} {{ {} }} "),
+            $($args,)* $num_iterations, $crate::triton_asm!(@fmt "",; $($body)*),;
+            $($tail)*
+        )
+    };
+    (@fmt $fmt:expr, $($args:expr,)*;
+        repeat $num_iterations:tt { $($body:tt)* } $($tail:tt)*) => {
+        $crate::triton_asm!(@fmt
+            concat!($fmt, " repeat ", stringify!($num_iterations), " {{ {} }} "),
+            $($args,)* $crate::triton_asm!(@fmt "",; $($body)*),;
+            $($tail)*
+        )
+    };
+    (@fmt $fmt:expr, $($args:expr,)*; while { $($body:tt)* } $($tail:tt)*) => {
+        $crate::triton_asm!(@fmt
+            concat!($fmt, " while {{ {} }} "),
+            $($args,)* $crate::triton_asm!(@fmt "",; $($body)*),;
+            $($tail)*
+        )
+    };
     (@fmt $fmt:expr, $($args:expr,)*; $label_declaration:ident: $($tail:tt)*) => {
         $crate::triton_asm!(@fmt
             concat!($fmt, " ", stringify!($label_declaration), ": "), $($args,)*; $($tail)*
@@ -333,8 +407,18 @@ mod tests {
     fn public_types_implement_usual_auto_traits() {
         fn implements_auto_traits<T: Sized + Send + Sync + Unpin>() {}
 
+        implements_auto_traits::<container::Header>();
+        implements_auto_traits::<container::IsaVersion>();
+        implements_auto_traits::<container::ProgramContainer>();
+
+        implements_auto_traits::<disassembler::Disassembler>();
+        implements_auto_traits::<disassembler::SymbolMap>();
+
         implements_auto_traits::<error::AssertionError>();
+        implements_auto_traits::<error::ContainerError>();
+        implements_auto_traits::<error::DisassemblerError>();
         implements_auto_traits::<error::InstructionError>();
+        implements_auto_traits::<error::LinkError>();
         implements_auto_traits::<error::NumberOfWordsError>();
         implements_auto_traits::<error::OpStackElementError>();
         implements_auto_traits::<error::OpStackError>();
@@ -344,8 +428,12 @@ mod tests {
         implements_auto_traits::<instruction::Instruction>();
         implements_auto_traits::<instruction::AnInstruction<usize>>();
         implements_auto_traits::<instruction::InstructionBit>();
+        implements_auto_traits::<instruction::StaticData>();
         implements_auto_traits::<instruction::TypeHint>();
 
+        implements_auto_traits::<linker::Linker>();
+        implements_auto_traits::<linker::Module>();
+
         implements_auto_traits::<op_stack::NumberOfWords>();
         implements_auto_traits::<op_stack::OpStack>();
         implements_auto_traits::<op_stack::OpStackElement>();
@@ -355,5 +443,6 @@ mod tests {
 
         implements_auto_traits::<program::InstructionIter>();
         implements_auto_traits::<program::Program>();
+        implements_auto_traits::<program::SourceLocation>();
     }
 }
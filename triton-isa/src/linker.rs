@@ -0,0 +1,629 @@
+//! Combine several modules of [Triton assembly][tasm] into one
+//! [`Program`].
+//!
+//! Every [`Module`] has its own label namespace. Labels are private to the
+//! module defining them, unless they are explicitly exported. A `call` to some
+//! label is resolved to the calling module's own label, if such a label
+//! exists, and to the label exported by some other module otherwise.
+//!
+//! When linking, only the functions reachable from the entrypoint are kept.
+//! Here, a function is a label together with all instructions up to the next
+//! label.
+//!
+//! # Examples
+//!
+//! ```
+//! # use triton_isa::linker::Linker;
+//! # use triton_isa::linker::Module;
+//! # use triton_isa::triton_asm;
+//! let main = Module::new("main", triton_asm!(read_io 1 call square write_io 1 halt));
+//! let math = Module::new(
+//!     "math",
+//!     triton_asm!(
+//!         square: dup 0 call mul_helper return
+//!         mul_helper: mul return
+//!         unused: push 0 return
+//!     ),
+//! )
+//! .with_export("square");
+//!
+//! let program = Linker::new()
+//!     .with_module(main)
+//!     .with_module(math)
+//!     .link_program("main")
+//!     .unwrap();
+//! assert!(program.label_for_address(7) == "square");
+//! assert!(program.label_for_address(12) == "math__mul_helper");
+//! assert!(!program.to_string().contains("unused"));
+//! ```
+//!
+//! [tasm]: https://triton-vm.org/spec/instructions.html
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::instruction::AnInstruction;
+use crate::instruction::LabelledInstruction;
+use crate::program::Program;
+
+type Result<T> = std::result::Result<T, LinkError>;
+
+/// The separator between the name of a module and the name of one of its
+/// private labels in the linked program.
+const MANGLING_SEPARATOR: &str = "__";
+
+/// One unit of [Triton assembly][tasm] with its own label namespace.
+///
+/// [tasm]: https://triton-vm.org/spec/instructions.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Module {
+    name: String,
+    instructions: Vec<LabelledInstruction>,
+    exports: Vec<String>,
+}
+
+impl Module {
+    /// A module without any exported labels. See also [`Module::with_export`].
+    pub fn new(name: impl Into<String>, instructions: Vec<LabelledInstruction>) -> Self {
+        Self {
+            name: name.into(),
+            instructions,
+            exports: vec![],
+        }
+    }
+
+    /// Make the given label callable from other modules.
+    #[must_use]
+    pub fn with_export(mut self, label: impl Into<String>) -> Self {
+        self.exports.push(label.into());
+        self
+    }
+
+    /// Make all given labels callable from other modules.
+    #[must_use]
+    pub fn with_exports<L: Into<String>>(mut self, labels: impl IntoIterator<Item = L>) -> Self {
+        self.exports
+            .extend(labels.into_iter().map(|label| label.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn exports(&self) -> &[String] {
+        &self.exports
+    }
+
+    fn labels(&self) -> impl Iterator<Item = &String> {
+        self.instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::Label(label) => Some(label),
+                _ => None,
+            })
+    }
+
+    fn is_exported(&self, label: &str) -> bool {
+        self.exports.iter().any(|export| export == label)
+    }
+
+    /// Split the module into [`Function`]s. The first function is the code
+    /// preceding the first label. It is unnamed and might be empty.
+    fn functions(&self) -> Vec<Function> {
+        let mut functions = vec![Function::default()];
+        for instruction in &self.instructions {
+            if let LabelledInstruction::Label(label) = instruction {
+                let label = Some(label.clone());
+                functions.push(Function {
+                    label,
+                    body: vec![],
+                });
+            } else {
+                functions.last_mut().unwrap().body.push(instruction.clone());
+            }
+        }
+        functions
+    }
+}
+
+/// A label and all instructions up to the next label.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+struct Function {
+    label: Option<String>,
+    body: Vec<LabelledInstruction>,
+}
+
+impl Function {
+    fn call_targets(&self) -> impl Iterator<Item = &String> {
+        self.body
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::Instruction(AnInstruction::Call(target)) => Some(target),
+                _ => None,
+            })
+    }
+
+    /// Whether execution can continue with the function following this one.
+    fn might_fall_through(&self) -> bool {
+        let mut instructions = self.body.iter().rev().filter_map(|instr| match instr {
+            LabelledInstruction::Instruction(instruction) => Some(instruction),
+            _ => None,
+        });
+        let Some(last) = instructions.next() else {
+            return true;
+        };
+        let is_terminal = matches!(
+            last,
+            AnInstruction::Halt
+                | AnInstruction::Return
+                | AnInstruction::Recurse
+                | AnInstruction::RecurseOrReturn
+        );
+        let is_conditional = matches!(instructions.next(), Some(AnInstruction::Skiz));
+
+        !is_terminal || is_conditional
+    }
+}
+
+/// Combines several [`Module`]s into one program. See the
+/// [module-level documentation](self) for details.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Linker {
+    modules: Vec<Module>,
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_module(mut self, module: Module) -> Self {
+        self.modules.push(module);
+        self
+    }
+
+    /// Link all modules into one [`Program`]. See also [`Linker::link`].
+    pub fn link_program(&self, entrypoint: &str) -> Result<Program> {
+        Ok(Program::new(&self.link(entrypoint)?))
+    }
+
+    /// Link all modules into one list of [`LabelledInstruction`]s.
+    ///
+    /// The code of the `entrypoint` module preceding its first label is where
+    /// execution starts. Exported labels keep their name, while private labels
+    /// are prefixed with the name of their module. Functions that cannot be
+    /// reached from the entrypoint are removed.
+    pub fn link(&self, entrypoint: &str) -> Result<Vec<LabelledInstruction>> {
+        self.ensure_unique_module_names()?;
+        let entrypoint_index = self
+            .modules
+            .iter()
+            .position(|module| module.name == entrypoint)
+            .ok_or_else(|| LinkError::UnknownEntrypoint(entrypoint.to_string()))?;
+        let exports = self.exported_labels()?;
+        for module in &self.modules {
+            Self::ensure_no_duplicate_labels(module)?;
+        }
+
+        let mut ordered_modules = self.modules.iter().collect_vec();
+        let entrypoint_module = ordered_modules.remove(entrypoint_index);
+        ordered_modules.insert(0, entrypoint_module);
+
+        let mut symbols = HashMap::new();
+        let mut linked_names = HashMap::new();
+        for module in &ordered_modules {
+            let symbol_table = self.symbol_table(module, &exports)?;
+            for label in module.labels() {
+                let linked_name = symbol_table[label].clone();
+                let definition = (module.name.clone(), label.clone());
+                if let Some(first) = linked_names.insert(linked_name.clone(), definition) {
+                    let (first_module, first_label) = first;
+                    return Err(LinkError::LinkedNameClash {
+                        linked_name,
+                        first_module,
+                        first_label,
+                        second_module: module.name.clone(),
+                        second_label: label.clone(),
+                    });
+                }
+            }
+            symbols.insert(module.name.clone(), symbol_table);
+        }
+
+        let functions = ordered_modules
+            .iter()
+            .map(|module| (module.name.as_str(), module.functions()))
+            .collect_vec();
+        let reachable = Self::reachable_functions(&functions, &symbols);
+
+        let mut linked = vec![];
+        for (module_index, (module_name, module_functions)) in functions.iter().enumerate() {
+            let symbol_table = &symbols[*module_name];
+            for (function_index, function) in module_functions.iter().enumerate() {
+                if !reachable.contains(&(module_index, function_index)) {
+                    continue;
+                }
+                if let Some(label) = &function.label {
+                    linked.push(LabelledInstruction::Label(symbol_table[label].clone()));
+                }
+                for instruction in &function.body {
+                    let instruction = match instruction {
+                        LabelledInstruction::Instruction(instruction) => {
+                            let instruction =
+                                instruction.map_call_address(|target| symbol_table[target].clone());
+                            LabelledInstruction::Instruction(instruction)
+                        }
+                        _ => instruction.clone(),
+                    };
+                    linked.push(instruction);
+                }
+            }
+        }
+
+        Ok(linked)
+    }
+
+    fn ensure_unique_module_names(&self) -> Result<()> {
+        let mut seen_names = HashSet::new();
+        for module in &self.modules {
+            if !seen_names.insert(module.name.as_str()) {
+                return Err(LinkError::DuplicateModule(module.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn ensure_no_duplicate_labels(module: &Module) -> Result<()> {
+        let mut seen_labels = HashSet::new();
+        for label in module.labels() {
+            if !seen_labels.insert(label) {
+                return Err(LinkError::DuplicateLabel {
+                    module: module.name.clone(),
+                    label: label.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Map every exported label to the name of the module exporting it.
+    fn exported_labels(&self) -> Result<HashMap<&str, &str>> {
+        let mut exports = HashMap::<&str, &str>::new();
+        for module in &self.modules {
+            for export in &module.exports {
+                if !module.labels().contains(export) {
+                    return Err(LinkError::UndefinedExport {
+                        module: module.name.clone(),
+                        label: export.clone(),
+                    });
+                }
+                if let Some(&first) = exports.get(export.as_str()) {
+                    if first == module.name {
+                        continue;
+                    }
+                    return Err(LinkError::ExportClash {
+                        label: export.clone(),
+                        first_module: first.to_string(),
+                        second_module: module.name.clone(),
+                    });
+                }
+                exports.insert(export, &module.name);
+            }
+        }
+        Ok(exports)
+    }
+
+    /// Map every label that is defined or called in the given module to its
+    /// name in the linked program.
+    fn symbol_table(
+        &self,
+        module: &Module,
+        exports: &HashMap<&str, &str>,
+    ) -> Result<HashMap<String, String>> {
+        let mangle = |label: &str| format!("{}{MANGLING_SEPARATOR}{label}", module.name);
+
+        let mut symbol_table = HashMap::new();
+        for label in module.labels() {
+            let linked_name = if module.is_exported(label) {
+                label.clone()
+            } else {
+                mangle(label)
+            };
+            symbol_table.insert(label.clone(), linked_name);
+        }
+
+        let all_call_targets = module
+            .functions()
+            .iter()
+            .flat_map(|function| function.call_targets().cloned().collect_vec())
+            .collect_vec();
+        for target in all_call_targets {
+            if symbol_table.contains_key(&target) {
+                continue;
+            }
+            if exports.contains_key(target.as_str()) {
+                symbol_table.insert(target.clone(), target);
+                continue;
+            }
+
+            let private_definition = self
+                .modules
+                .iter()
+                .find(|other| other.labels().contains(&target));
+            let error = match private_definition {
+                Some(owner) => LinkError::PrivateLabel {
+                    module: module.name.clone(),
+                    label: target,
+                    owner: owner.name.clone(),
+                },
+                None => LinkError::UnresolvedCall {
+                    module: module.name.clone(),
+                    label: target,
+                },
+            };
+            return Err(error);
+        }
+
+        Ok(symbol_table)
+    }
+
+    /// The indices `(module_index, function_index)` of all functions reachable
+    /// from the entrypoint, which is the first function of the first module.
+    fn reachable_functions(
+        functions: &[(&str, Vec<Function>)],
+        symbols: &HashMap<String, HashMap<String, String>>,
+    ) -> HashSet<(usize, usize)> {
+        let mut function_for_linked_label = HashMap::new();
+        for (module_index, (module_name, module_functions)) in functions.iter().enumerate() {
+            for (function_index, function) in module_functions.iter().enumerate() {
+                if let Some(label) = &function.label {
+                    let linked_label = symbols[*module_name][label].as_str();
+                    function_for_linked_label.insert(linked_label, (module_index, function_index));
+                }
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut to_visit = vec![(0, 0)];
+        while let Some((module_index, function_index)) = to_visit.pop() {
+            if !reachable.insert((module_index, function_index)) {
+                continue;
+            }
+            let (module_name, module_functions) = &functions[module_index];
+            let function = &module_functions[function_index];
+            for target in function.call_targets() {
+                let linked_target = symbols[*module_name][target].as_str();
+                to_visit.push(function_for_linked_label[linked_target]);
+            }
+            if function.might_fall_through() && function_index + 1 < module_functions.len() {
+                to_visit.push((module_index, function_index + 1));
+            }
+        }
+
+        reachable
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum LinkError {
+    #[error("module `{0}` is defined more than once")]
+    DuplicateModule(String),
+
+    #[error("entrypoint module `{0}` does not exist")]
+    UnknownEntrypoint(String),
+
+    #[error("label `{label}` is defined more than once in module `{module}`")]
+    DuplicateLabel { module: String, label: String },
+
+    #[error("module `{module}` exports label `{label}` but does not define it")]
+    UndefinedExport { module: String, label: String },
+
+    #[error(
+        "label `{label}` is exported by both module `{first_module}` and module `{second_module}`"
+    )]
+    ExportClash {
+        label: String,
+        first_module: String,
+        second_module: String,
+    },
+
+    #[error(
+        "module `{module}` calls label `{label}`, which is private to module `{owner}`; \
+         consider exporting it"
+    )]
+    PrivateLabel {
+        module: String,
+        label: String,
+        owner: String,
+    },
+
+    #[error("module `{module}` calls label `{label}`, which no module defines")]
+    UnresolvedCall { module: String, label: String },
+
+    #[error(
+        "label `{first_label}` of module `{first_module}` and label `{second_label}` of module \
+         `{second_module}` would both be called `{linked_name}` in the linked program"
+    )]
+    LinkedNameClash {
+        linked_name: String,
+        first_module: String,
+        first_label: String,
+        second_module: String,
+        second_label: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+
+    use crate::triton_asm;
+
+    use super::*;
+
+    fn main_module() -> Module {
+        Module::new("main", triton_asm!(push 2 call square call helper halt))
+    }
+
+    fn math_module() -> Module {
+        let code = triton_asm!(
+            square: dup 0 call helper return
+            helper: mul return
+            unused: push 0 return
+        );
+        Module::new("math", code).with_export("square")
+    }
+
+    #[test]
+    fn private_labels_of_different_modules_do_not_clash() {
+        let main = Module::new(
+            "main",
+            triton_asm!(call helper call square halt helper: return),
+        );
+        let linked = Linker::new()
+            .with_module(main)
+            .with_module(math_module())
+            .link_program("main")
+            .unwrap();
+
+        let labels = linked
+            .labelled_instructions()
+            .into_iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::Label(label) => Some(label),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        assert!(labels.contains("main__helper"));
+        assert!(labels.contains("math__helper"));
+        assert!(labels.contains("square"));
+    }
+
+    #[test]
+    fn unreachable_functions_are_removed() {
+        let linked = Linker::new()
+            .with_module(main_module())
+            .with_module(math_module())
+            .link("main");
+
+        // `main` calls the private `helper` of module `math`
+        let_assert!(Err(LinkError::PrivateLabel { label, owner, .. }) = linked);
+        assert!("helper" == label);
+        assert!("math" == owner);
+
+        let main = Module::new("main", triton_asm!(push 2 call square halt));
+        let linked = Linker::new()
+            .with_module(math_module())
+            .with_module(main)
+            .link("main")
+            .unwrap();
+        let expected = triton_asm!(
+            push 2 call square halt
+            square: dup 0 call math__helper return
+            math__helper: mul return
+        );
+        assert!(expected == linked);
+    }
+
+    #[test]
+    fn functions_reached_through_fall_through_are_kept() {
+        let main = Module::new("main", triton_asm!(call foo halt));
+        let lib = Module::new(
+            "lib",
+            triton_asm!(
+                foo: push 1 skiz return
+                bar: push 2 pop 1 return
+                baz: return
+            ),
+        )
+        .with_export("foo");
+
+        let linked = Linker::new()
+            .with_module(main)
+            .with_module(lib)
+            .link("main")
+            .unwrap();
+        let expected = triton_asm!(
+            call foo halt
+            foo: push 1 skiz return
+            lib__bar: push 2 pop 1 return
+        );
+        assert!(expected == linked);
+    }
+
+    #[test]
+    fn clashing_exports_are_reported() {
+        let other_math =
+            Module::new("other_math", triton_asm!(square: return)).with_export("square");
+        let linked = Linker::new()
+            .with_module(main_module())
+            .with_module(math_module())
+            .with_module(other_math)
+            .link("main");
+        let_assert!(Err(LinkError::ExportClash { label, .. }) = linked);
+        assert!("square" == label);
+    }
+
+    #[test]
+    fn duplicate_module_names_are_reported() {
+        let linked = Linker::new()
+            .with_module(math_module())
+            .with_module(math_module())
+            .link("math");
+        let_assert!(Err(LinkError::DuplicateModule(name)) = linked);
+        assert!("math" == name);
+    }
+
+    #[test]
+    fn duplicate_labels_within_one_module_are_reported() {
+        let module = Module::new("main", triton_asm!(foo: halt foo: halt));
+        let_assert!(Err(err) = Linker::new().with_module(module).link("main"));
+        let_assert!(LinkError::DuplicateLabel { .. } = err);
+    }
+
+    #[test]
+    fn unknown_entrypoint_is_reported() {
+        let_assert!(Err(err) = Linker::new().with_module(math_module()).link("main"));
+        let_assert!(LinkError::UnknownEntrypoint(_) = err);
+    }
+
+    #[test]
+    fn exporting_undefined_label_is_reported() {
+        let module = Module::new("main", triton_asm!(halt)).with_export("foo");
+        let_assert!(Err(err) = Linker::new().with_module(module).link("main"));
+        let_assert!(LinkError::UndefinedExport { .. } = err);
+    }
+
+    #[test]
+    fn unresolved_call_is_reported() {
+        let module = Module::new("main", triton_asm!(call foo halt));
+        let_assert!(Err(err) = Linker::new().with_module(module).link("main"));
+        let_assert!(LinkError::UnresolvedCall { label, .. } = err);
+        assert!("foo" == label);
+    }
+
+    #[test]
+    fn clash_between_mangled_and_exported_name_is_reported() {
+        let main = Module::new(
+            "main",
+            triton_asm!(call helper call lib__foo halt helper: return),
+        );
+        let lib =
+            Module::new("lib", triton_asm!(foo: return lib__foo: return)).with_export("lib__foo");
+        let_assert!(
+            Err(err) = Linker::new()
+                .with_module(main)
+                .with_module(lib)
+                .link("main")
+        );
+        let_assert!(LinkError::LinkedNameClash { linked_name, .. } = err);
+        assert!("lib__foo" == linked_name);
+    }
+}
@@ -6,6 +6,7 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
 
+use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::bytes::complete::take_while;
@@ -20,8 +21,11 @@ use nom::error::VerboseError;
 use nom::error::VerboseErrorKind;
 use nom::multi::many0;
 use nom::multi::many1;
+use nom::multi::separated_list0;
 use nom::Finish;
 use nom::IResult;
+use num_traits::ConstOne;
+use num_traits::ConstZero;
 use twenty_first::bfe;
 use twenty_first::prelude::BFieldElement;
 
@@ -29,7 +33,9 @@ use crate::instruction::AnInstruction;
 use crate::instruction::AssertionContext;
 use crate::instruction::Instruction;
 use crate::instruction::LabelledInstruction;
+use crate::instruction::StaticData;
 use crate::instruction::TypeHint;
+use crate::instruction::ALL_INSTRUCTIONS;
 use crate::instruction::ALL_INSTRUCTION_NAMES;
 use crate::op_stack::NumberOfWords;
 use crate::op_stack::OpStackElement;
@@ -40,6 +46,20 @@ const KEYWORDS: [&str; 3] = [
     "error_message", // reserved for future use
 ];
 
+/// The maximal nesting depth of macro invocations. Since a macro can only invoke
+/// macros that were defined before it, recursion is impossible; this limit only
+/// guards against pathologically deep chains of macros.
+const MAX_MACRO_INVOCATION_DEPTH: usize = 64;
+
+/// The message for failures that the parser cannot describe more specifically.
+const UNEXPECTED_TOKEN: &str = "unexpected token";
+
+/// The message for code that would fall through into the subroutines generated
+/// for structured control flow.
+const FALL_THROUGH_INTO_SUBROUTINES: &str =
+    "code with structured control flow must end in `halt`, `return`, `recurse`, \
+    or `recurse_or_return`";
+
 #[derive(Debug, PartialEq)]
 pub struct ParseError<'a> {
     pub input: &'a str,
@@ -55,10 +75,84 @@ pub enum InstructionToken<'a> {
     Breakpoint(&'a str),
     TypeHint(TypeHint, &'a str),
     AssertionContext(AssertionContext, &'a str),
+    StaticData(StaticData, &'a str),
+}
+
+/// The named constants and macros that are in scope at some point of the input,
+/// as well as the subroutines generated for structured control flow so far.
+#[derive(Debug, Default, Clone)]
+struct Directives<'a> {
+    constants: HashMap<String, BFieldElement>,
+    macros: HashMap<String, Macro<'a>>,
+    subroutines: Subroutines<'a>,
+}
+
+impl<'a> Directives<'a> {
+    /// The directives at the start of the given input.
+    fn new(input: &'a str) -> Self {
+        let subroutines = Subroutines {
+            input,
+            ..Subroutines::default()
+        };
+        Self {
+            subroutines,
+            ..Self::default()
+        }
+    }
+}
+
+/// The subroutines that structured control flow, like `if { … }`, is lowered to.
+/// They are placed after all other code.
+#[derive(Debug, Default, Clone)]
+struct Subroutines<'a> {
+    /// The entire input being parsed. Generated labels never occur in it.
+    input: &'a str,
+    num_generated_labels: usize,
+    tokens: Vec<InstructionToken<'a>>,
+}
+
+impl<'a> Subroutines<'a> {
+    /// A fresh label for a subroutine of the given kind. Labels are numbered in
+    /// order of generation, starting from 0 for every input, which makes parsing
+    /// deterministic. Names that occur in the input are skipped, so that generated
+    /// labels cannot clash with labels defined by the input.
+    fn new_label(&mut self, kind: &str) -> String {
+        loop {
+            let label = format!("_{kind}_{}", self.num_generated_labels);
+            self.num_generated_labels += 1;
+            if !mentions_label(self.input, &label) {
+                return label;
+            }
+        }
+    }
+
+    /// Add a subroutine consisting of the given tokens, labelled with the given
+    /// label. The subroutine's final instruction must be part of the tokens.
+    fn add(&mut self, label: InstructionToken<'a>, body: Vec<InstructionToken<'a>>) {
+        self.tokens.push(label);
+        self.tokens.extend(body);
+    }
+}
+
+/// A macro, defined by directive `.macro`.
+///
+/// The body is kept as (unparsed) source code, and is parsed anew every time the
+/// macro is invoked, with the parameters bound to the invocation's arguments. The
+/// named constants in scope are those at the macro's definition.
+#[derive(Debug, Clone)]
+struct Macro<'a> {
+    parameters: Vec<String>,
+    body: &'a str,
+    constants: HashMap<String, BFieldElement>,
 }
 
 impl Display for ParseError<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let diagnostics = self.diagnostics();
+        if !diagnostics.is_empty() {
+            return write!(f, "{}", diagnostics.iter().join("\n\n"));
+        }
+
         let mut errors = self.errors.clone();
         if matches!(
             errors.errors.first(),
@@ -73,6 +167,95 @@ impl Display for ParseError<'_> {
 
 impl Error for ParseError<'_> {}
 
+impl ParseError<'_> {
+    /// All problems in the input, not only the first one. See [`diagnose`].
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        diagnose(self.input)
+    }
+}
+
+/// A problem in source code, located by line and column. Unlike a [`ParseError`],
+/// which describes the first problem the parser encounters, [`diagnose`] reports
+/// all problems it can find.
+///
+/// The [`Display`] implementation shows the offending line of source code, with
+/// carets pointing at the problem.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Diagnostic {
+    pub message: String,
+
+    /// The line of the problem, starting at 1.
+    pub line: usize,
+
+    /// The column of the problem, starting at 1 and counted in characters.
+    pub column: usize,
+
+    /// The number of characters affected by the problem, at least 1.
+    pub len: usize,
+
+    /// The line of source code containing the problem.
+    pub source_line: String,
+
+    /// A likely fix, like the correct spelling of a misspelled instruction.
+    pub suggestion: Option<String>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let caret_indentation = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(self.len);
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{gutter}--> {}:{}", self.line, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line_number} | {}", self.source_line)?;
+        write!(f, "{gutter} | {caret_indentation}{carets}")?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n{gutter} = help: did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+impl Diagnostic {
+    /// A diagnostic for the word starting at `position`, which must be a slice of
+    /// `input`.
+    fn new(input: &str, position: &str, message: impl Into<String>) -> Self {
+        let offset = position.as_ptr() as usize - input.as_ptr() as usize;
+        let line_start = input[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = input[offset..]
+            .find(is_linebreak)
+            .map_or(input.len(), |index| offset + index);
+        let len = position
+            .chars()
+            .take_while(|c| !c.is_whitespace())
+            .count()
+            .max(1);
+
+        Self {
+            message: message.into(),
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            len,
+            source_line: input[line_start..line_end].to_string(),
+            suggestion: None,
+        }
+    }
+
+    #[must_use]
+    fn with_suggestion(mut self, suggestion: Option<impl Into<String>>) -> Self {
+        self.suggestion = suggestion.map(Into::into);
+        self
+    }
+}
+
 impl<'a> InstructionToken<'a> {
     pub fn token_str(&self) -> &'a str {
         match self {
@@ -81,6 +264,7 @@ impl<'a> InstructionToken<'a> {
             Self::Breakpoint(token_str) => token_str,
             Self::TypeHint(_, token_str) => token_str,
             Self::AssertionContext(_, token_str) => token_str,
+            Self::StaticData(_, token_str) => token_str,
         }
     }
 
@@ -91,6 +275,7 @@ impl<'a> InstructionToken<'a> {
             Self::Breakpoint(_) => LabelledInstruction::Breakpoint,
             Self::TypeHint(type_hint, _) => LabelledInstruction::TypeHint(type_hint.to_owned()),
             Self::AssertionContext(ctx, _) => LabelledInstruction::AssertionContext(ctx.to_owned()),
+            Self::StaticData(data, _) => LabelledInstruction::StaticData(data.to_owned()),
         }
     }
 }
@@ -110,6 +295,7 @@ pub(crate) fn parse(input: &str) -> Result<Vec<InstructionToken>, ParseError> {
 
     ensure_no_missing_or_duplicate_labels(input, &instructions)?;
     ensure_assertion_context_is_matched_with_assertion(input, &instructions)?;
+    ensure_no_overlapping_static_data(input, &instructions)?;
 
     Ok(instructions)
 }
@@ -220,22 +406,780 @@ fn ensure_assertion_context_is_matched_with_assertion<'a>(
     }
 }
 
+fn ensure_no_overlapping_static_data<'a>(
+    input: &'a str,
+    instructions: &[InstructionToken<'a>],
+) -> Result<(), ParseError<'a>> {
+    let mut initializing_data = HashMap::new();
+    let mut overlapping_data = vec![];
+    for instruction in instructions {
+        let InstructionToken::StaticData(data, token_str) = instruction else {
+            continue;
+        };
+        for (address, _) in data.ram_entries() {
+            if let Some(first_occurrence) = initializing_data.insert(address, *token_str) {
+                overlapping_data.extend([first_occurrence, *token_str]);
+            }
+        }
+    }
+    overlapping_data.sort_unstable_by_key(|token_str| token_str.as_ptr());
+    overlapping_data.dedup();
+
+    if overlapping_data.is_empty() {
+        return Ok(());
+    }
+
+    let context = VerboseErrorKind::Context("overlapping static data");
+    let errors = overlapping_data
+        .into_iter()
+        .map(|token_str| (token_str, context.clone()))
+        .collect();
+    let errors = VerboseError { errors };
+    Err(ParseError { input, errors })
+}
+
+/// Find all problems in the given source code, as far as possible.
+///
+/// After encountering an invalid instruction or directive, parsing continues
+/// with the next instruction. Since the parser has to guess where that is, some
+/// reported problems might be consequences of previous ones.
+///
+/// ```
+/// # use triton_isa::parser::diagnose;
+/// let diagnostics = diagnose("push 1 pusj 2\npop 7 call fooo halt\nfoo: return");
+/// assert_eq!(3, diagnostics.len());
+/// assert_eq!((1, 8), (diagnostics[0].line, diagnostics[0].column));
+/// assert_eq!(Some("push"), diagnostics[0].suggestion.as_deref());
+/// assert_eq!(Some("foo"), diagnostics[2].suggestion.as_deref());
+/// ```
+pub fn diagnose(input: &str) -> Vec<Diagnostic> {
+    let (tokens, mut diagnostics) = tokenize_with_recovery(input);
+
+    let labels = tokens
+        .iter()
+        .filter_map(|token| match token {
+            InstructionToken::Label(label, _) => Some(label.as_str()),
+            _ => None,
+        })
+        .collect_vec();
+    let missing_label_suggestion = |position| {
+        let (_, AnInstruction::Call(label)) = call_instruction()(position).ok()? else {
+            return None;
+        };
+        closest_match(&label, labels.iter().copied())
+    };
+
+    let semantic_checks = [
+        ensure_no_missing_or_duplicate_labels,
+        ensure_assertion_context_is_matched_with_assertion,
+        ensure_no_overlapping_static_data,
+    ];
+    for check in semantic_checks {
+        let Err(err) = check(input, &tokens) else {
+            continue;
+        };
+        for (position, kind) in err.errors.errors {
+            let VerboseErrorKind::Context(context) = kind else {
+                continue;
+            };
+            let suggestion = match context {
+                "missing label" => missing_label_suggestion(position),
+                _ => None,
+            };
+            let diagnostic = Diagnostic::new(input, position, context).with_suggestion(suggestion);
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    diagnostics.dedup();
+    diagnostics
+}
+
+/// Like [`tokenize`], but instead of stopping at the first problem, record it and
+/// skip the offending item. Useful for tools that need to make sense of
+/// incomplete or invalid code, like editors.
+pub fn tokenize_with_recovery(input: &str) -> (Vec<InstructionToken<'_>>, Vec<Diagnostic>) {
+    let mut tokens = vec![];
+    let mut diagnostics = vec![];
+    let mut directives = Directives::new(input);
+
+    let mut s = input;
+    loop {
+        (s, _) = comment_or_whitespace0(s).unwrap_or((s, ""));
+        if s.is_empty() {
+            break;
+        }
+
+        match item(s, &mut directives, 0) {
+            Ok((rest, Some(item_tokens))) => {
+                tokens.extend(item_tokens);
+                s = rest;
+                continue;
+            }
+            Ok((_, None)) => diagnostics.push(unexpected_item(input, s, &directives)),
+            Err(nom::Err::Error(errors) | nom::Err::Failure(errors)) => {
+                diagnostics.push(failed_item(input, &errors));
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!("all parsers are complete"),
+        }
+        s = skip_item(s);
+    }
+    if let Some(position) = fall_through_into_subroutines(&tokens, &directives.subroutines) {
+        diagnostics.push(Diagnostic::new(
+            input,
+            position,
+            FALL_THROUGH_INTO_SUBROUTINES,
+        ));
+    }
+    tokens.extend(directives.subroutines.tokens);
+
+    (tokens, diagnostics)
+}
+
+/// Explain why the input at position `s` is neither an instruction, nor a label,
+/// nor anything else the parser accepts.
+fn unexpected_item<'a>(input: &'a str, s: &'a str, directives: &Directives) -> Diagnostic {
+    let Ok((_, word)) = label_addr(s) else {
+        return Diagnostic::new(input, s, "expecting label, instruction or eof");
+    };
+
+    if let Some(instruction) = ALL_INSTRUCTIONS.iter().find(|i| i.name() == word) {
+        let argument = s[word.len()..].trim_start();
+        let argument_error = match instruction {
+            AnInstruction::Pop(_)
+            | AnInstruction::Divine(_)
+            | AnInstruction::ReadMem(_)
+            | AnInstruction::WriteMem(_)
+            | AnInstruction::ReadIo(_)
+            | AnInstruction::WriteIo(_) => number_of_words(argument).err(),
+            AnInstruction::Pick(_)
+            | AnInstruction::Place(_)
+            | AnInstruction::Dup(_)
+            | AnInstruction::Swap(_) => stack_register(argument).err(),
+            _ => None,
+        };
+        if let Some(nom::Err::Error(errors) | nom::Err::Failure(errors)) = argument_error {
+            let diagnostic = failed_item(input, &errors);
+            if diagnostic.message != UNEXPECTED_TOKEN {
+                return diagnostic;
+            }
+        }
+        if instruction.arg().is_none() {
+            return Diagnostic::new(input, argument, "expecting whitespace after instruction");
+        }
+        let message = format!("invalid argument for instruction `{word}`");
+        return Diagnostic::new(input, argument, message);
+    }
+
+    let malformed_annotation = match word.as_str() {
+        "hint" => Some("invalid type hint"),
+        "error_id" => Some("invalid assertion context"),
+        _ => None,
+    };
+    if let Some(message) = malformed_annotation {
+        return Diagnostic::new(input, s, message);
+    }
+
+    if directives.macros.contains_key(&word) {
+        let message = format!("macro `{word}` must be invoked with `{word}!`");
+        return Diagnostic::new(input, s, message).with_suggestion(Some(format!("{word}!")));
+    }
+
+    let candidates = ALL_INSTRUCTION_NAMES.into_iter().chain(["break", "hint"]);
+    let suggestion = closest_match(&word, candidates);
+    Diagnostic::new(input, s, format!("unknown instruction `{word}`")).with_suggestion(suggestion)
+}
+
+/// The most specific explanation in the given errors, which describe one failure.
+fn failed_item<'a>(input: &'a str, errors: &VerboseError<&'a str>) -> Diagnostic {
+    let innermost_context = errors
+        .errors
+        .iter()
+        .find_map(|(position, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some((*position, (*context).to_string())),
+            _ => None,
+        });
+    let (position, message) = innermost_context.unwrap_or_else(|| {
+        let (position, kind) = errors.errors.first().unwrap();
+        let message = match kind {
+            VerboseErrorKind::Char(c) => format!("expected '{c}'"),
+            _ => UNEXPECTED_TOKEN.to_string(),
+        };
+        (*position, message)
+    });
+
+    Diagnostic::new(input, position, message)
+}
+
+/// Guess where the item starting at `s` ends. For most items, this is after the
+/// first word and its arguments. Macro definitions, static data declarations,
+/// and structured control flow can span multiple lines and are skipped entirely.
+fn skip_item(s: &str) -> &str {
+    fn split_first_word(s: &str) -> (&str, &str) {
+        let s = s.trim_start();
+        let word_end = s.find(char::is_whitespace).unwrap_or(s.len());
+        s.split_at(word_end)
+    }
+
+    fn skip_block(s: &str) -> &str {
+        let mut depth = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth <= 1 => return &s[i + 1..],
+                '}' => depth -= 1,
+                _ => (),
+            }
+        }
+        ""
+    }
+
+    let skip_past = |delimiter: &str| s.find(delimiter).map_or("", |i| &s[i + delimiter.len()..]);
+    if s.starts_with(".macro") {
+        return skip_past(".endmacro");
+    }
+    if s.starts_with(".data") {
+        return skip_past("]");
+    }
+    let line_end = s.find(is_linebreak).unwrap_or(s.len());
+    if s.starts_with(".const") {
+        return &s[line_end..];
+    }
+    if s.starts_with("hint") {
+        return s[..line_end]
+            .find(']')
+            .map_or(&s[line_end..], |i| &s[i + 1..]);
+    }
+
+    let (word, mut s) = split_first_word(s);
+    if ["if", "repeat", "while"].contains(&word) {
+        s = skip_block(s);
+        if let Some(else_block) = s.trim_start().strip_prefix("else") {
+            s = skip_block(else_block);
+        }
+        return s;
+    }
+    if instruction_takes_argument(word) {
+        (_, s) = split_first_word(s);
+    }
+
+    // skip stray arguments, like the `1` in `pusj 1`
+    while s
+        .trim_start()
+        .starts_with(|c: char| c.is_ascii_digit() || c == '-')
+    {
+        (_, s) = split_first_word(s);
+    }
+
+    s
+}
+
+/// Whether the instruction with the given name takes an argument.
+pub(crate) fn instruction_takes_argument(name: &str) -> bool {
+    ALL_INSTRUCTIONS
+        .iter()
+        .any(|instruction| instruction.name() == name && instruction.arg().is_some())
+}
+
+/// Among the candidates, the one most similar to the given word, if any is
+/// similar enough to likely be meant instead.
+fn closest_match<'c>(word: &str, candidates: impl IntoIterator<Item = &'c str>) -> Option<&'c str> {
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between the two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect_vec();
+    let mut previous_row = (0..=b.len()).collect_vec();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(a_char != b_char);
+            let deletion = previous_row[j + 1] + 1;
+            let insertion = row[j] + 1;
+            row.push(substitution.min(deletion).min(insertion));
+        }
+        previous_row = row;
+    }
+
+    previous_row[b.len()]
+}
+
 /// Auxiliary type alias: `IResult` defaults to `nom::error::Error` as concrete
 /// error type, but we want `nom::error::VerboseError` as it allows `context()`.
 type ParseResult<'input, Out> = IResult<&'input str, Out, VerboseError<&'input str>>;
 
+/// Turn the input into [`InstructionToken`]s.
+///
+/// Besides instructions, labels, and debugging annotations, the following
+/// directives are supported:
+///
+/// - `.const NAME = VALUE` defines a named constant. From then on, the name can be
+///   used in place of a literal in the arguments of `push` and `addi`, and in
+///   directives.
+/// - `.macro NAME(PARAMETER, …)` starts the definition of a macro, which extends
+///   up to the next `.endmacro`. A macro without parameters can omit the
+///   parentheses. Within the macro's body, the parameters can be used like named
+///   constants. Macros are expanded by invoking them with `NAME!(ARGUMENT, …)`.
+///   Macros can invoke macros that are defined before them, but cannot define
+///   other macros.
+/// - `.data ADDRESS [VALUE, …]` declares static data: the values are placed at
+///   consecutive RAM addresses, starting at `ADDRESS`, before the program starts.
+///   See also [`Program::static_data`](crate::program::Program::static_data).
+///
+/// Structured control flow is lowered to plain instructions. Every branch and
+/// loop body becomes a subroutine with a generated label; these subroutines are
+/// placed after all other code. Generated labels are numbered per input and
+/// never clash with labels of the input. Code that is parsed separately and
+/// then combined may, however, contain the same generated label twice. Consequently, `return` in a branch or loop body
+/// only returns from that subroutine, and code using structured control flow
+/// must end in `halt`, `return`, `recurse`, or `recurse_or_return`, lest
+/// execution fall through into the subroutines. The following constructs are
+/// supported:
+///
+/// - `if { THEN } else { ELSE }` pops the top of the stack. If it is not 0, `THEN`
+///   is executed, else `ELSE`. The `else` branch is optional.
+/// - `repeat N { BODY }` executes `BODY` `N` times. While `BODY` is executed, the
+///   number of remaining iterations, including the current one, is on top of the
+///   stack. `BODY` must leave it there.
+/// - `while { BODY }` executes `BODY` as long as stack elements `st5` and `st6`
+///   differ, checking before every iteration. This is the loop that instruction
+///   `recurse_or_return` is built for.
+///
+/// ```
+/// # use triton_isa::program::Program;
+/// let program = Program::from_code(
+///     "
+///     .const BASE = 100
+///     .macro read_twice(address)
+///         push address read_mem 1 pop 1
+///         push address read_mem 1 pop 1
+///     .endmacro
+///
+///     .data BASE [42, 43]
+///     read_twice!(BASE)
+///     halt
+///     ",
+/// )
+/// .unwrap();
+/// assert_eq!(13, program.len_bwords());
+/// assert_eq!(2, program.static_data().len());
+/// ```
 pub fn tokenize(s: &str) -> ParseResult<Vec<InstructionToken>> {
+    let mut directives = Directives::new(s);
     let (s, _) = comment_or_whitespace0(s)?;
-    let (s, instructions) = many0(alt((
+    let (s, mut instructions) = tokens(s, &mut directives, 0)?;
+    let (s, _) = nom::error::context("expecting label, instruction or eof", eof)(s)?;
+    if let Some(position) = fall_through_into_subroutines(&instructions, &directives.subroutines) {
+        return nom::error::context(FALL_THROUGH_INTO_SUBROUTINES, fail)(position);
+    }
+    instructions.extend(directives.subroutines.tokens);
+
+    Ok((s, instructions))
+}
+
+/// If execution would fall through from the given code into the given
+/// subroutines, the position of the offending token. Execution falls through
+/// unless the last instruction ends control flow and is not followed by a label.
+fn fall_through_into_subroutines<'a>(
+    tokens: &[InstructionToken<'a>],
+    subroutines: &Subroutines,
+) -> Option<&'a str> {
+    if subroutines.tokens.is_empty() {
+        return None;
+    }
+
+    // Lowering structured control flow always results in some instructions.
+    let last_token = tokens.iter().rev().find(|token| {
+        matches!(
+            token,
+            InstructionToken::Instruction(..) | InstructionToken::Label(..)
+        )
+    })?;
+    match last_token {
+        InstructionToken::Instruction(
+            AnInstruction::Halt
+            | AnInstruction::Return
+            | AnInstruction::Recurse
+            | AnInstruction::RecurseOrReturn,
+            _,
+        ) => None,
+        _ => Some(last_token.token_str()),
+    }
+}
+
+/// Whether the given label occurs in the given code as a whole word, in any role.
+fn mentions_label(code: &str, label: &str) -> bool {
+    code.match_indices(label).any(|(start, _)| {
+        let end = start + label.len();
+        let is_label_char_at_border = |border: Option<char>| border.is_some_and(is_label_char);
+        !is_label_char_at_border(code[..start].chars().next_back())
+            && !is_label_char_at_border(code[end..].chars().next())
+    })
+}
+
+/// Parse as many tokens as possible, expanding macros and recording definitions.
+///
+/// The macro depth is 0 for top-level code and increases by 1 for every level of
+/// macro definition or invocation.
+fn tokens<'a>(
+    mut s: &'a str,
+    directives: &mut Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, Vec<InstructionToken<'a>>> {
+    let mut instructions = vec![];
+    loop {
+        let (rest, maybe_tokens) = item(s, directives, macro_depth)?;
+        let Some(tokens) = maybe_tokens else {
+            return Ok((s, instructions));
+        };
+        instructions.extend(tokens);
+        s = rest;
+    }
+}
+
+/// Parse one item: a directive, a macro invocation, structured control flow, or
+/// a single token. Returns `None` if the input does not start with any of these.
+fn item<'a>(
+    s: &'a str,
+    directives: &mut Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, Option<Vec<InstructionToken<'a>>>> {
+    let (rest, maybe_constant) = opt(|s| constant_definition(s, &directives.constants))(s)?;
+    if let Some((name, value)) = maybe_constant {
+        directives.constants.insert(name, value);
+        return Ok((rest, Some(vec![])));
+    }
+
+    let (rest, maybe_macro) = opt(|s| macro_definition(s, directives, macro_depth))(s)?;
+    if let Some((name, macro_)) = maybe_macro {
+        directives.macros.insert(name, macro_);
+        return Ok((rest, Some(vec![])));
+    }
+
+    let (rest, maybe_expansion) = opt(|s| macro_invocation(s, directives, macro_depth))(s)?;
+    if let Some(expansion) = maybe_expansion {
+        return Ok((rest, Some(expansion)));
+    }
+
+    let (rest, maybe_lowering) = opt(|s| control_flow(s, directives, macro_depth))(s)?;
+    if let Some(lowering) = maybe_lowering {
+        return Ok((rest, Some(lowering)));
+    }
+
+    let constants = &directives.constants;
+    let (rest, maybe_token) = opt(alt((
         label,
-        labelled_instruction,
+        |s| labelled_instruction(s, constants),
         breakpoint,
         type_hint,
         assertion_context,
+        |s| static_data(s, constants),
     )))(s)?;
-    let (s, _) = nom::error::context("expecting label, instruction or eof", eof)(s)?;
 
-    Ok((s, instructions))
+    Ok((rest, maybe_token.map(|token| vec![token])))
+}
+
+/// Parse a constant definition of the form `.const NAME = VALUE`.
+fn constant_definition<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, (String, BFieldElement)> {
+    let (s_name, _) = token1(".const")(s)?;
+    let (s, name) = directive_name(s_name)?;
+    if constants.contains_key(&name) {
+        return cut(nom::error::context("constant is already defined", fail))(s_name);
+    }
+    let (s, _) = whitespace0(s)?;
+    let (s, _) = cut(token0("="))(s)?;
+    let (s, value) = cut(|s| field_element(s, constants))(s)?;
+
+    Ok((s, (name, value)))
+}
+
+/// Parse a macro definition of the form `.macro NAME(PARAMETER, …) BODY .endmacro`.
+///
+/// The body is parsed to ensure it is well-formed, using placeholder values for
+/// the parameters.
+fn macro_definition<'a>(
+    s_macro: &'a str,
+    directives: &Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, (String, Macro<'a>)> {
+    let (s_name, _) = token1(".macro")(s_macro)?;
+    if macro_depth > 0 {
+        let failure_reason = "macros cannot be defined in macros";
+        return cut(nom::error::context(failure_reason, fail))(s_macro);
+    }
+    let (s, name) = directive_name(s_name)?;
+    if directives.macros.contains_key(&name) {
+        return cut(nom::error::context("macro is already defined", fail))(s_name);
+    }
+    let (body_start, parameters) = cut(macro_parameters)(s)?;
+    if !parameters.iter().all_unique() {
+        return cut(nom::error::context("macro parameters must be unique", fail))(s);
+    }
+
+    let placeholders = parameters.iter().map(|p| (p.clone(), BFieldElement::ZERO));
+    let mut scope = directives.clone();
+    scope.constants.extend(placeholders);
+    let (body_end, _) = tokens(body_start, &mut scope, macro_depth + 1)?;
+    let (s, _) = nom::error::context("expecting `.endmacro`", cut(token1(".endmacro")))(body_end)?;
+
+    let body = &body_start[..body_start.len() - body_end.len()];
+    let constants = directives.constants.clone();
+    let macro_ = Macro {
+        parameters,
+        body,
+        constants,
+    };
+    Ok((s, (name, macro_)))
+}
+
+fn macro_parameters(s: &str) -> ParseResult<Vec<String>> {
+    let (s_paren, _) = comment_or_whitespace0(s)?;
+    let Ok((s, _)) = token0("(")(s_paren) else {
+        let (s, _) = comment_or_whitespace1(s)?;
+        return Ok((s, vec![]));
+    };
+    let (s, parameters) = separated_list0(token0(","), |s| {
+        let (s, parameter) = directive_name(s)?;
+        let (s, _) = comment_or_whitespace0(s)?;
+        Ok((s, parameter))
+    })(s)?;
+    let (s, _) = opt(token0(","))(s)?;
+    let (s, _) = token0(")")(s)?;
+
+    Ok((s, parameters))
+}
+
+/// Parse a macro invocation of the form `NAME!(ARGUMENT, …)` and expand it.
+fn macro_invocation<'a>(
+    s_invocation: &'a str,
+    directives: &mut Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, Vec<InstructionToken<'a>>> {
+    let (s, name) = label_addr(s_invocation)?;
+    let (s, _) = tag("!")(s)?;
+    let Some(macro_) = directives.macros.get(&name) else {
+        return cut(nom::error::context("unknown macro", fail))(s_invocation);
+    };
+    if macro_depth >= MAX_MACRO_INVOCATION_DEPTH {
+        let failure_reason = "macro invocations are nested too deeply";
+        return cut(nom::error::context(failure_reason, fail))(s_invocation);
+    }
+
+    let (s, arguments) = cut(|s| macro_arguments(s, &directives.constants))(s)?;
+    if arguments.len() != macro_.parameters.len() {
+        let failure_reason = "wrong number of macro arguments";
+        return cut(nom::error::context(failure_reason, fail))(s_invocation);
+    }
+
+    let mut constants = macro_.constants.clone();
+    constants.extend(macro_.parameters.iter().cloned().zip(arguments));
+    let macros = directives.macros.clone();
+    let subroutines = std::mem::take(&mut directives.subroutines);
+    let mut scope = Directives {
+        constants,
+        macros,
+        subroutines,
+    };
+    let (_, expansion) = tokens(macro_.body, &mut scope, macro_depth + 1)?;
+    directives.subroutines = scope.subroutines;
+
+    Ok((s, expansion))
+}
+
+fn macro_arguments<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, Vec<BFieldElement>> {
+    let (s_paren, _) = comment_or_whitespace0(s)?;
+    let Ok((s, _)) = token0("(")(s_paren) else {
+        let (s, _) = comment_or_whitespace1(s)?;
+        return Ok((s, vec![]));
+    };
+    let (s, arguments) = separated_list0(token0(","), |s| {
+        let (s, argument) = value(s, constants)?;
+        let (s, _) = comment_or_whitespace0(s)?;
+        Ok((s, argument))
+    })(s)?;
+    let (s, _) = opt(token0(","))(s)?;
+    let (s, _) = token1(")")(s)?;
+
+    Ok((s, arguments))
+}
+
+/// Parse structured control flow, like `if { … } else { … }`, and lower it to
+/// plain instructions. The branches and loop bodies become subroutines. See
+/// [`tokenize`] for the supported constructs.
+fn control_flow<'a>(
+    s_keyword: &'a str,
+    directives: &mut Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, Vec<InstructionToken<'a>>> {
+    let (s, keyword) = alt((tag("if"), tag("repeat"), tag("while")))(s_keyword)?;
+    let (s, num_iterations) = match keyword {
+        "repeat" => {
+            let (s, _) = comment_or_whitespace1(s)?;
+            let num_iterations = cut(|s| field_element(s, &directives.constants));
+            let (s, num_iterations) =
+                nom::error::context("expecting number of iterations", num_iterations)(s)?;
+            (s, Some(num_iterations))
+        }
+        _ => (comment_or_whitespace0(s)?.0, None),
+    };
+    let (s, body) = block(s, directives, macro_depth)?;
+
+    let instruction = |instruction| InstructionToken::Instruction(instruction, s_keyword);
+    let call = |label: &String| instruction(AnInstruction::Call(label.clone()));
+    match keyword {
+        "if" => {
+            let else_block = |s| {
+                let (s, _) = token1("else")(s)?;
+                block(s, directives, macro_depth)
+            };
+            let (s, maybe_else_body) = opt(else_block)(s)?;
+
+            let subroutines = &mut directives.subroutines;
+            let then_label = subroutines.new_label("then");
+            let Some(else_body) = maybe_else_body else {
+                let then_body = [body, vec![instruction(AnInstruction::Return)]].concat();
+                subroutines.add(
+                    InstructionToken::Label(then_label.clone(), s_keyword),
+                    then_body,
+                );
+                return Ok((s, vec![instruction(AnInstruction::Skiz), call(&then_label)]));
+            };
+
+            // The flag left on the stack decides whether the else branch is taken.
+            let else_label = subroutines.new_label("else");
+            let then_body = [
+                vec![instruction(AnInstruction::Pop(NumberOfWords::N1))],
+                body,
+                vec![
+                    instruction(AnInstruction::Push(BFieldElement::ZERO)),
+                    instruction(AnInstruction::Return),
+                ],
+            ]
+            .concat();
+            let else_body = [else_body, vec![instruction(AnInstruction::Return)]].concat();
+            subroutines.add(
+                InstructionToken::Label(then_label.clone(), s_keyword),
+                then_body,
+            );
+            subroutines.add(
+                InstructionToken::Label(else_label.clone(), s_keyword),
+                else_body,
+            );
+
+            let lowering = vec![
+                instruction(AnInstruction::Push(BFieldElement::ONE)),
+                instruction(AnInstruction::Swap(OpStackElement::ST1)),
+                instruction(AnInstruction::Skiz),
+                call(&then_label),
+                instruction(AnInstruction::Skiz),
+                call(&else_label),
+            ];
+            Ok((s, lowering))
+        }
+        "repeat" => {
+            let subroutines = &mut directives.subroutines;
+            let label = subroutines.new_label("repeat");
+            let loop_body = [
+                vec![
+                    instruction(AnInstruction::Dup(OpStackElement::ST0)),
+                    instruction(AnInstruction::Push(BFieldElement::ZERO)),
+                    instruction(AnInstruction::Eq),
+                    instruction(AnInstruction::Skiz),
+                    instruction(AnInstruction::Return),
+                ],
+                body,
+                vec![
+                    instruction(AnInstruction::AddI(-BFieldElement::ONE)),
+                    instruction(AnInstruction::Recurse),
+                ],
+            ]
+            .concat();
+            subroutines.add(InstructionToken::Label(label.clone(), s_keyword), loop_body);
+
+            let lowering = vec![
+                instruction(AnInstruction::Push(num_iterations.unwrap())),
+                call(&label),
+                instruction(AnInstruction::Pop(NumberOfWords::N1)),
+            ];
+            Ok((s, lowering))
+        }
+        _ => {
+            let subroutines = &mut directives.subroutines;
+            let label = subroutines.new_label("while");
+            let loop_body = [body, vec![instruction(AnInstruction::RecurseOrReturn)]].concat();
+            subroutines.add(InstructionToken::Label(label.clone(), s_keyword), loop_body);
+
+            // enter the loop only if st5 and st6 differ
+            let lowering = vec![
+                instruction(AnInstruction::Dup(OpStackElement::ST5)),
+                instruction(AnInstruction::Dup(OpStackElement::ST7)),
+                instruction(AnInstruction::Eq),
+                instruction(AnInstruction::Push(BFieldElement::ZERO)),
+                instruction(AnInstruction::Eq),
+                instruction(AnInstruction::Skiz),
+                call(&label),
+            ];
+            Ok((s, lowering))
+        }
+    }
+}
+
+/// Parse a block of the form `{ … }`, like the body of a loop.
+fn block<'a>(
+    s: &'a str,
+    directives: &mut Directives<'a>,
+    macro_depth: usize,
+) -> ParseResult<'a, Vec<InstructionToken<'a>>> {
+    let (s, _) = token0("{")(s)?;
+    let (s, body) = tokens(s, directives, macro_depth)?;
+    let (s, _) = nom::error::context("expecting `}`", cut(token1("}")))(s)?;
+
+    Ok((s, body))
+}
+
+/// Parse a static data declaration of the form `.data ADDRESS [VALUE, …]`.
+fn static_data<'a>(
+    s_data: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, InstructionToken<'a>> {
+    let (s, _) = token1(".data")(s_data)?;
+    let (s, address) = cut(|s| field_element(s, constants))(s)?;
+    let (s, _) = cut(token0("["))(s)?;
+    let (s, values) = separated_list0(token0(","), |s| {
+        let (s, value) = value(s, constants)?;
+        let (s, _) = comment_or_whitespace0(s)?;
+        Ok((s, value))
+    })(s)?;
+    let (s, _) = opt(token0(","))(s)?;
+    let (s, _) = nom::error::context("expecting `]`", cut(token1("]")))(s)?;
+
+    let data = StaticData { address, values };
+    Ok((s, InstructionToken::StaticData(data, s_data)))
+}
+
+/// Parse the name of a constant, a macro, or a macro parameter.
+fn directive_name(s: &str) -> ParseResult<String> {
+    let (s_rest, name) = label_addr(s)?;
+    if is_illegal_label(&name) {
+        let failure_reason = "name must be neither instruction nor keyword";
+        return cut(nom::error::context(failure_reason, fail))(s);
+    }
+
+    Ok((s_rest, name))
 }
 
 fn label(label_s: &str) -> ParseResult<InstructionToken> {
@@ -259,15 +1203,21 @@ fn breakpoint(breakpoint_s: &str) -> ParseResult<InstructionToken> {
     Ok((s, InstructionToken::Breakpoint(breakpoint_s)))
 }
 
-fn labelled_instruction(s_instr: &str) -> ParseResult<InstructionToken> {
-    let (s, instr) = an_instruction(s_instr)?;
+fn labelled_instruction<'a>(
+    s_instr: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, InstructionToken<'a>> {
+    let (s, instr) = an_instruction(s_instr, constants)?;
     Ok((s, InstructionToken::Instruction(instr, s_instr)))
 }
 
-fn an_instruction(s: &str) -> ParseResult<AnInstruction<String>> {
+fn an_instruction<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, AnInstruction<String>> {
     // OpStack manipulation
     let pop = pop_instruction();
-    let push = push_instruction();
+    let push = push_instruction(constants);
     let divine = divine_instruction();
     let pick = pick_instruction();
     let place = place_instruction();
@@ -306,7 +1256,7 @@ fn an_instruction(s: &str) -> ParseResult<AnInstruction<String>> {
 
     // Arithmetic on stack instructions
     let add = instruction("add", AnInstruction::Add);
-    let addi = addi_instruction();
+    let addi = addi_instruction(constants);
     let mul = instruction("mul", AnInstruction::Mul);
     let invert = instruction("invert", AnInstruction::Invert);
     let eq = instruction("eq", AnInstruction::Eq);
@@ -376,6 +1326,15 @@ fn an_instruction(s: &str) -> ParseResult<AnInstruction<String>> {
     ))(s)
 }
 
+/// Whether the given string can be used as a label, _i.e._, declared with
+/// `<label>:` and called with `call <label>`.
+pub(crate) fn is_legal_label(label: &str) -> bool {
+    let Ok((rest, label)) = label_addr(label) else {
+        return false;
+    };
+    rest.is_empty() && !is_illegal_label(&label)
+}
+
 fn is_illegal_label(s: &str) -> bool {
     ALL_INSTRUCTION_NAMES.contains(&s) || KEYWORDS.contains(&s)
 }
@@ -398,18 +1357,22 @@ fn pop_instruction() -> impl Fn(&str) -> ParseResult<AnInstruction<String>> {
     }
 }
 
-fn push_instruction() -> impl Fn(&str) -> ParseResult<AnInstruction<String>> {
+fn push_instruction(
+    constants: &HashMap<String, BFieldElement>,
+) -> impl Fn(&str) -> ParseResult<AnInstruction<String>> + '_ {
     move |s: &str| {
         let (s, _) = token1("push")(s)?;
-        let (s, elem) = field_element(s)?;
+        let (s, elem) = field_element(s, constants)?;
         Ok((s, AnInstruction::Push(elem)))
     }
 }
 
-fn addi_instruction() -> impl Fn(&str) -> ParseResult<AnInstruction<String>> {
+fn addi_instruction(
+    constants: &HashMap<String, BFieldElement>,
+) -> impl Fn(&str) -> ParseResult<AnInstruction<String>> + '_ {
     move |s: &str| {
         let (s, _) = token1("addi")(s)?;
-        let (s, elem) = field_element(s)?;
+        let (s, elem) = field_element(s, constants)?;
         Ok((s, AnInstruction::AddI(elem)))
     }
 }
@@ -456,8 +1419,8 @@ fn swap_instruction() -> impl Fn(&str) -> ParseResult<AnInstruction<String>> {
 
 fn call_instruction<'a>() -> impl Fn(&'a str) -> ParseResult<'a, AnInstruction<String>> {
     move |s: &'a str| {
-        let (s, _) = token1("call")(s)?;
-        let (s, label) = label_addr(s)?;
+        let (s_label, _) = token1("call")(s)?;
+        let (s, label) = label_addr(s_label)?;
         let (s, _) = comment_or_whitespace1(s)?;
 
         // This check cannot be moved into `label_addr`, since `label_addr` is shared
@@ -465,7 +1428,7 @@ fn call_instruction<'a>() -> impl Fn(&'a str) -> ParseResult<'a, AnInstruction<S
         // parsing the `:` before rejecting a possible instruction name in the label.
         if is_illegal_label(&label) {
             let failure_reason = "label must be neither instruction nor keyword";
-            return cut(nom::error::context(failure_reason, fail))(s);
+            return cut(nom::error::context(failure_reason, fail))(s_label);
         }
 
         Ok((s, AnInstruction::Call(label)))
@@ -504,10 +1467,33 @@ fn write_io_instruction() -> impl Fn(&str) -> ParseResult<AnInstruction<String>>
     }
 }
 
-fn field_element(s_orig: &str) -> ParseResult<BFieldElement> {
+/// Parse a field element – either a literal or a named constant – followed by at
+/// least one comment and/or whitespace, or eof.
+fn field_element<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, BFieldElement> {
+    let (s, element) = value(s, constants)?;
+    let (s, _) = comment_or_whitespace1(s)?;
+
+    Ok((s, element))
+}
+
+/// Parse a field element – either a literal or a named constant.
+fn value<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, BFieldElement> {
+    if s.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        field_element_literal(s)
+    } else {
+        named_constant(s, constants)
+    }
+}
+
+fn field_element_literal(s_orig: &str) -> ParseResult<BFieldElement> {
     let (s, negative) = opt(token0("-"))(s_orig)?;
     let (s, n) = digit1(s)?;
-    let (s, _) = comment_or_whitespace1(s)?;
 
     let Ok(mut n): Result<i128, _> = n.parse() else {
         return nom::error::context("out-of-bounds constant", fail)(s);
@@ -526,8 +1512,20 @@ fn field_element(s_orig: &str) -> ParseResult<BFieldElement> {
     Ok((s, BFieldElement::new(n as u64)))
 }
 
-fn stack_register(s: &str) -> ParseResult<OpStackElement> {
-    let (s, n) = digit1(s)?;
+fn named_constant<'a>(
+    s: &'a str,
+    constants: &HashMap<String, BFieldElement>,
+) -> ParseResult<'a, BFieldElement> {
+    let (s_rest, name) = label_addr(s)?;
+    let Some(&value) = constants.get(&name) else {
+        return cut(nom::error::context("unknown constant", fail))(s);
+    };
+
+    Ok((s_rest, value))
+}
+
+fn stack_register(s_orig: &str) -> ParseResult<OpStackElement> {
+    let (s, n) = digit1(s_orig)?;
     let stack_register = match n {
         "0" => OpStackElement::ST0,
         "1" => OpStackElement::ST1,
@@ -547,7 +1545,7 @@ fn stack_register(s: &str) -> ParseResult<OpStackElement> {
         "15" => OpStackElement::ST15,
         _ => {
             let failure_reason = "using an out-of-bounds stack register (0-15 exist)";
-            return nom::error::context(failure_reason, fail)(s);
+            return nom::error::context(failure_reason, fail)(s_orig);
         }
     };
     let (s, _) = comment_or_whitespace1(s)?;
@@ -555,15 +1553,18 @@ fn stack_register(s: &str) -> ParseResult<OpStackElement> {
     Ok((s, stack_register))
 }
 
-fn number_of_words(s: &str) -> ParseResult<NumberOfWords> {
-    let (s, n) = digit1(s)?;
+fn number_of_words(s_orig: &str) -> ParseResult<NumberOfWords> {
+    let (s, n) = digit1(s_orig)?;
     let arg = match n {
         "1" => NumberOfWords::N1,
         "2" => NumberOfWords::N2,
         "3" => NumberOfWords::N3,
         "4" => NumberOfWords::N4,
         "5" => NumberOfWords::N5,
-        _ => return nom::error::context("using an out-of-bounds argument (1-5 allowed)", fail)(s),
+        _ => {
+            let failure_reason = "using an out-of-bounds argument (1-5 allowed)";
+            return nom::error::context(failure_reason, fail)(s_orig);
+        }
     };
     let (s, _) = comment_or_whitespace1(s)?; // require space after element
 
@@ -661,7 +1662,7 @@ fn token1<'a>(token: &'a str) -> impl Fn(&'a str) -> ParseResult<'a, ()> {
 /// ```text
 /// hint <variable_name>[: <type_name>] = stack\[<range_start>[..<range_end>]\]
 /// ```
-fn type_hint(s_type_hint: &str) -> ParseResult<InstructionToken> {
+pub(crate) fn type_hint(s_type_hint: &str) -> ParseResult<InstructionToken> {
     let (s, _) = token1("hint")(s_type_hint)?;
     let (s, variable_name) = type_hint_variable_name(s)?;
     let (s, type_name) = type_hint_type_name(s)?;
@@ -723,7 +1724,8 @@ fn type_hint_ending_index(s: &str) -> ParseResult<Option<usize>> {
 
 fn type_hint_starting_index(s: &str) -> ParseResult<usize> {
     let (s, _) = token0("=")(s)?;
-    let (s, _) = token0("stack[")(s)?;
+    let (s, _) = token0("stack")(s)?;
+    let (s, _) = token0("[")(s)?;
     let (s, range_start) = take_while(|c: char| c.is_numeric())(s)?;
     let (_, range_start) = parse_str_to_usize(range_start)?;
     let (s, _) = whitespace0(s)?;
@@ -835,6 +1837,7 @@ pub(crate) mod tests {
     use twenty_first::bfe;
     use twenty_first::prelude::Digest;
 
+    use crate::program::Program;
     use crate::triton_asm;
     use crate::triton_instr;
     use crate::triton_program;
@@ -1095,7 +2098,7 @@ pub(crate) mod tests {
         NegativeTestCase {
             input: "pop: call pop",
             expected_error: "label must be neither instruction nor keyword",
-            expected_error_count: 1,
+            expected_error_count: 2,
             message: "label names may not overlap with instruction names",
         }
         .run();
@@ -1119,7 +2122,7 @@ pub(crate) mod tests {
     fn parse_program_nonexistent_instructions() {
         NegativeTestCase {
             input: "pop 0",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "using an out-of-bounds argument (1-5 allowed)",
             expected_error_count: 1,
             message: "instruction `pop` cannot take argument `0`",
         }
@@ -1127,7 +2130,7 @@ pub(crate) mod tests {
 
         NegativeTestCase {
             input: "swap 16",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "using an out-of-bounds stack register (0-15 exist)",
             expected_error_count: 1,
             message: "there is no swap 16 instruction",
         }
@@ -1135,7 +2138,7 @@ pub(crate) mod tests {
 
         NegativeTestCase {
             input: "dup 16",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "using an out-of-bounds stack register (0-15 exist)",
             expected_error_count: 1,
             message: "there is no dup 16 instruction",
         }
@@ -1202,7 +2205,7 @@ pub(crate) mod tests {
     fn parse_type_hint_with_range_and_offset_and_missing_closing_bracket() {
         NegativeTestCase {
             input: "hint foo: Type = stack[2..5",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid type hint",
             expected_error_count: 1,
             message: "parse type hint with range and offset and missing closing bracket",
         }
@@ -1213,7 +2216,7 @@ pub(crate) mod tests {
     fn parse_type_hint_with_range_and_offset_and_missing_opening_bracket() {
         NegativeTestCase {
             input: "hint foo: Type = stack2..5]",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid type hint",
             expected_error_count: 1,
             message: "parse type hint with range and offset and missing opening bracket",
         }
@@ -1235,7 +2238,7 @@ pub(crate) mod tests {
     fn parse_type_hint_with_range_and_offset_and_missing_type_name() {
         NegativeTestCase {
             input: "hint foo: = stack[2..5]",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid type hint",
             expected_error_count: 1,
             message: "parse type hint with range and offset and missing type name",
         }
@@ -1257,7 +2260,7 @@ pub(crate) mod tests {
     fn parse_type_hint_with_range_and_offset_and_missing_colon() {
         NegativeTestCase {
             input: "hint foo Type = stack[2..5]",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid type hint",
             expected_error_count: 1,
             message: "parse type hint with range and offset and missing colon",
         }
@@ -1353,14 +2356,14 @@ pub(crate) mod tests {
     fn assertion_context_error_id_fails_on_too_large_error_ids() {
         NegativeTestCase {
             input: "assert error_id -170141183460469231731687303715884105729",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid assertion context",
             expected_error_count: 1,
             message: "error id smaller than i128::MIN",
         }
         .run();
         NegativeTestCase {
             input: "assert error_id 170141183460469231731687303715884105728",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid assertion context",
             expected_error_count: 1,
             message: "error id larger than i128::MAX",
         }
@@ -1381,15 +2384,15 @@ pub(crate) mod tests {
     fn parse_erroneous_assertion_contexts_for_assert() {
         NegativeTestCase {
             input: "assert error_id",
-            expected_error: "expecting label, instruction or eof",
+            expected_error: "invalid assertion context",
             expected_error_count: 1,
             message: "missing id after keyword `error_id`",
         }
         .run();
         NegativeTestCase {
             input: "assert error id 42",
-            expected_error: "expecting label, instruction or eof",
-            expected_error_count: 1,
+            expected_error: "unknown instruction",
+            expected_error_count: 2,
             message: "incorrect keyword `error id`",
         }
         .run();
@@ -1650,4 +2653,432 @@ pub(crate) mod tests {
         let program = triton_program! { break halt break };
         assert_eq!(1, program.len_bwords());
     }
+
+    #[test]
+    fn named_constants_can_be_used_as_arguments() {
+        TestCase {
+            input: ".const N = 42 .const MINUS_ONE = -1 push N addi MINUS_ONE push 7",
+            expected: vec![
+                Instruction::Push(bfe!(42)),
+                Instruction::Push(bfe!(42)),
+                Instruction::AddI(bfe!(-1)),
+                Instruction::AddI(bfe!(-1)),
+                Instruction::Push(bfe!(7)),
+                Instruction::Push(bfe!(7)),
+            ],
+            message: "named constants should be replaced by their values",
+        }
+        .run();
+
+        TestCase {
+            input: ".const N = 42\n.const M = N\npush M",
+            expected: vec![Instruction::Push(bfe!(42)), Instruction::Push(bfe!(42))],
+            message: "constants should be definable in terms of other constants",
+        }
+        .run();
+    }
+
+    #[test]
+    fn faulty_constant_definitions_and_usages_are_rejected() {
+        NegativeTestCase {
+            input: "push N",
+            expected_error: "unknown constant",
+            expected_error_count: 1,
+            message: "constants must be defined before use",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: "push N .const N = 3",
+            expected_error: "unknown constant",
+            expected_error_count: 1,
+            message: "constants must be defined before use, not after",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".const N = 3 .const N = 4",
+            expected_error: "constant is already defined",
+            expected_error_count: 1,
+            message: "constants cannot be redefined",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".const push = 3",
+            expected_error: "name must be neither instruction nor keyword",
+            expected_error_count: 1,
+            message: "constants cannot be named like instructions",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".const N = 18446744069414584321",
+            expected_error: "out-of-bounds constant",
+            expected_error_count: 1,
+            message: "constants must be field elements",
+        }
+        .run();
+    }
+
+    #[test]
+    fn macros_are_expanded_with_arguments_bound_to_parameters() {
+        let code = "
+            .const ADDRESS = 5
+            .macro push_twice(value)
+                push value push value
+            .endmacro
+            .macro write_word_at(address, word)
+                push_twice!(word) // macros can invoke previously defined macros
+                pop 1
+                push address write_mem 1 pop 1
+            .endmacro
+            .macro stop
+                halt
+            .endmacro
+
+            write_word_at!(ADDRESS, -1)
+            stop!
+        ";
+        let program = Program::from_code(code).unwrap();
+        let expected = triton_program!(
+            push -1 push -1 pop 1 push 5 write_mem 1 pop 1 halt
+        );
+        assert!(expected == program);
+    }
+
+    #[test]
+    fn macros_capture_constants_in_scope_at_their_definition() {
+        let code = "
+            .const N = 1
+            .macro push_n push N .endmacro
+            .const M = 2
+            push_n!
+            push M
+        ";
+        let program = Program::from_code(code).unwrap();
+        assert!(triton_program!(push 1 push 2) == program);
+    }
+
+    #[test]
+    fn faulty_macro_definitions_and_invocations_are_rejected() {
+        NegativeTestCase {
+            input: "my_macro!",
+            expected_error: "unknown macro",
+            expected_error_count: 1,
+            message: "macros must be defined before invocation",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo(a) push a .endmacro foo!(1, 2)",
+            expected_error: "wrong number of macro arguments",
+            expected_error_count: 1,
+            message: "number of arguments must match number of parameters",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo(a, a) push a .endmacro",
+            expected_error: "macro parameters must be unique",
+            expected_error_count: 1,
+            message: "parameters must have unique names",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo push 1 .endmacro .macro foo push 2 .endmacro",
+            expected_error: "macro is already defined",
+            expected_error_count: 1,
+            message: "macros cannot be redefined",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo .macro bar push 2 .endmacro .endmacro",
+            expected_error: "macros cannot be defined in macros",
+            expected_error_count: 1,
+            message: "macro definitions cannot be nested",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo push 1",
+            expected_error: "expecting `.endmacro`",
+            expected_error_count: 1,
+            message: "macro definitions must be terminated",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo foo! .endmacro",
+            expected_error: "unknown macro",
+            expected_error_count: 1,
+            message: "macros cannot invoke themselves",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".macro foo(a) push b .endmacro",
+            expected_error: "unknown constant",
+            expected_error_count: 1,
+            message: "macro bodies are checked at definition",
+        }
+        .run();
+    }
+
+    #[test]
+    fn labels_in_macros_that_are_invoked_repeatedly_are_duplicate() {
+        NegativeTestCase {
+            input: ".macro foo my_label: nop .endmacro foo! foo! call my_label",
+            expected_error: "duplicate label",
+            expected_error_count: 1,
+            message: "invoking a macro with labels twice duplicates the labels",
+        }
+        .run();
+    }
+
+    fn generated_labels(instructions: &[LabelledInstruction]) -> Vec<String> {
+        instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::Label(label) => Some(label.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn if_else_is_lowered_to_subroutines() {
+        let instructions = triton_asm!(push 1 if { push 2 } else { push 3 } halt);
+        let labels = generated_labels(&instructions);
+        let_assert!([then_label, else_label] = labels.as_slice());
+
+        let expected = triton_asm!(
+            push 1 push 1 swap 1 skiz call {then_label} skiz call {else_label} halt
+            {then_label}: pop 1 push 2 push 0 return
+            {else_label}: push 3 return
+        );
+        assert!(expected == instructions);
+
+        let instructions = triton_asm!(if { push 2 } halt);
+        let labels = generated_labels(&instructions);
+        let_assert!([then_label] = labels.as_slice());
+        let expected = triton_asm!(skiz call {then_label} halt {then_label}: push 2 return);
+        assert!(expected == instructions);
+    }
+
+    #[test]
+    fn loops_are_lowered_to_subroutines() {
+        let instructions = triton_asm!(repeat 3 { write_io 1 } halt);
+        let labels = generated_labels(&instructions);
+        let_assert!([label] = labels.as_slice());
+        let expected = triton_asm!(
+            push 3 call {label} pop 1 halt
+            {label}: dup 0 push 0 eq skiz return write_io 1 addi -1 recurse
+        );
+        assert!(expected == instructions);
+
+        let instructions = triton_asm!(while { addi 1 } halt);
+        let labels = generated_labels(&instructions);
+        let_assert!([label] = labels.as_slice());
+        let expected = triton_asm!(
+            dup 5 dup 7 eq push 0 eq skiz call {label} halt
+            {label}: addi 1 recurse_or_return
+        );
+        assert!(expected == instructions);
+    }
+
+    #[test]
+    fn generated_labels_are_unique() {
+        let code = "
+            .const N = 2
+            .macro maybe_nop if { nop } .endmacro
+            push 1 maybe_nop!
+            push 1 maybe_nop!
+            repeat N { while { if { nop } else { nop } } }
+            call if halt
+            if: return // control flow keywords remain valid labels
+        ";
+        let program = Program::from_code(code).unwrap();
+        let labels = generated_labels(&program.labelled_instructions());
+        assert!(7 == labels.len());
+        assert!(labels.iter().all_unique());
+    }
+
+    #[test]
+    fn parsing_same_code_twice_gives_identical_output() {
+        let code = "push 1 if { nop } else { repeat 2 { nop } } while { nop } halt";
+        let_assert!(Ok(tokens) = parse(code));
+        let_assert!(Ok(tokens_again) = parse(code));
+        let instructions = to_labelled_instructions(&tokens);
+        let instructions_again = to_labelled_instructions(&tokens_again);
+        assert!(instructions == instructions_again);
+
+        let display = |instructions: &[LabelledInstruction]| instructions.iter().join("\n");
+        assert!(display(&instructions) == display(&instructions_again));
+    }
+
+    #[test]
+    fn generated_labels_do_not_clash_with_labels_of_input() {
+        let code = "call _then_0 if { nop } halt _then_0: call _then_1x return _then_1x: return";
+        let_assert!(Ok(tokens) = parse(code));
+        let labels = generated_labels(&to_labelled_instructions(&tokens));
+        assert!(labels.iter().all_unique());
+        assert!(labels.contains(&"_then_1".to_string()));
+    }
+
+    #[test]
+    fn code_falling_through_into_generated_subroutines_is_rejected() {
+        for input in [
+            "push 1 if { push 2 }",
+            "push 1 if { push 2 } push 3",
+            "repeat 2 { nop } halt label:",
+            "while { nop } halt label: nop // comment",
+        ] {
+            NegativeTestCase {
+                input,
+                expected_error: FALL_THROUGH_INTO_SUBROUTINES,
+                expected_error_count: 1,
+                message: "code must not fall through into generated subroutines",
+            }
+            .run();
+        }
+    }
+
+    #[test]
+    fn faulty_structured_control_flow_is_rejected() {
+        NegativeTestCase {
+            input: "push 1 if { push 1 halt",
+            expected_error: "expecting `}`",
+            expected_error_count: 1,
+            message: "blocks must be closed",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: "push 1 if { push 1 } }",
+            expected_error: "expecting label, instruction or eof",
+            expected_error_count: 1,
+            message: "blocks must be opened",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: "repeat { nop }",
+            expected_error: "expecting number of iterations",
+            expected_error_count: 1,
+            message: "bounded loops need a number of iterations",
+        }
+        .run();
+    }
+
+    #[test]
+    fn problems_in_structured_control_flow_do_not_cascade() {
+        let diagnostics = diagnose("push 1 if { nop } else { pusj 2 } halt\npusj 3");
+        assert!(2 == diagnostics.len());
+    }
+
+    #[test]
+    fn static_data_is_parsed() {
+        let code = ".const BASE = 100 .data BASE [1, -1, BASE,] .data 7 [] push 0";
+        let (_, tokens) = tokenize(code).unwrap();
+        let instructions = to_labelled_instructions(&tokens);
+
+        let_assert!(LabelledInstruction::StaticData(data) = &instructions[0]);
+        assert!(bfe!(100) == data.address);
+        assert!(vec![bfe!(1), bfe!(-1), bfe!(100)] == data.values);
+
+        let_assert!(LabelledInstruction::StaticData(data) = &instructions[1]);
+        assert!(bfe!(7) == data.address);
+        assert!(data.values.is_empty());
+    }
+
+    #[test]
+    fn faulty_static_data_is_rejected() {
+        NegativeTestCase {
+            input: ".data 10 [1, 2, 3] .data 12 [4]",
+            expected_error: "overlapping static data",
+            expected_error_count: 2,
+            message: "static data must not overlap",
+        }
+        .run();
+
+        NegativeTestCase {
+            input: ".data 10 [1, 2",
+            expected_error: "expecting `]`",
+            expected_error_count: 1,
+            message: "static data must be terminated",
+        }
+        .run();
+    }
+
+    #[proptest]
+    fn valid_programs_have_no_diagnostics(#[strategy(arb())] program: Program) {
+        prop_assert!(diagnose(&program.to_string()).is_empty());
+    }
+
+    #[test]
+    fn parsing_continues_after_errors() {
+        let code = "push 1 pusj 2 dup 16\nfoo: pop 0\n  halt lt\n\thalt bar";
+        let diagnostics = diagnose(code);
+        let locations = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column))
+            .collect_vec();
+        assert!(vec![(1, 8), (1, 19), (2, 10), (4, 7)] == locations);
+    }
+
+    #[test]
+    fn misspelled_instructions_and_labels_come_with_suggestions() {
+        let diagnostics = diagnose("call my_labl sponge_absrob halt my_label: return");
+        let_assert!([label, instruction] = diagnostics.as_slice());
+        assert!("missing label" == label.message);
+        assert!(Some("my_label") == label.suggestion.as_deref());
+        assert!("unknown instruction `sponge_absrob`" == instruction.message);
+        assert!(Some("sponge_absorb") == instruction.suggestion.as_deref());
+    }
+
+    #[test]
+    fn dissimilar_words_come_without_suggestion() {
+        let diagnostics = diagnose("frobnicate");
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(diagnostic.suggestion.is_none());
+    }
+
+    #[test]
+    fn macros_invoked_without_exclamation_mark_come_with_suggestion() {
+        let diagnostics = diagnose(".macro twice push 2 mul .endmacro twice halt");
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(Some("twice!") == diagnostic.suggestion.as_deref());
+    }
+
+    #[test]
+    fn parsing_continues_after_faulty_directives() {
+        let code = ".macro foo pusj 1 .endmacro\n.data 0 [1, x]\n.const 7 = 8\nhalt haltt";
+        let diagnostics = diagnose(code);
+        let lines = diagnostics.iter().map(|d| d.line).collect_vec();
+        assert!(vec![1, 2, 3, 4] == lines);
+    }
+
+    #[test]
+    fn diagnostic_points_at_problem_with_carets() {
+        let diagnostics = diagnose("push 1\n\tpusj 2 halt");
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        let expected = "error: unknown instruction `pusj`\n \
+            --> 2:2\n  \
+            |\n\
+            2 | \tpusj 2 halt\n  \
+            | \t^^^^\n  \
+            = help: did you mean `push`?";
+        assert!(expected == diagnostic.to_string());
+    }
+
+    #[test]
+    fn parse_error_displays_all_diagnostics() {
+        let_assert!(Err(err) = parse("pusj 1 popp 1 halt"));
+        let message = err.to_string();
+        assert!(message.contains("did you mean `push`?"));
+        assert!(message.contains("did you mean `pop`?"));
+    }
 }
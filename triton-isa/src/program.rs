@@ -20,27 +20,34 @@ use crate::instruction::AssertionContext;
 use crate::instruction::Instruction;
 use crate::instruction::InstructionError;
 use crate::instruction::LabelledInstruction;
+use crate::instruction::StaticData;
 use crate::instruction::TypeHint;
 use crate::parser;
+use crate::parser::InstructionToken;
 use crate::parser::ParseError;
 
 /// A program for Triton VM. Triton VM can run and profile such programs,
 /// and trace its execution in order to generate a proof of correct execution.
 /// See there for details.
 ///
-/// A program may contain debug information, such as label names and breakpoints.
-/// Access this information through methods [`label_for_address()`][label_for_address] and
-/// [`is_breakpoint()`][is_breakpoint]. Some operations, most notably
-/// [BField-encoding](BFieldCodec::encode), discard this debug information.
+/// A program may contain debug information, such as label names, breakpoints, and
+/// the location of each instruction in the source code. Access this information
+/// through methods [`label_for_address()`][label_for_address],
+/// [`is_breakpoint()`][is_breakpoint], and [`source_location()`][source_location].
+/// Some operations, most notably [BField-encoding](BFieldCodec::encode), discard
+/// this debug information, as well as the program's
+/// [static data](Program::static_data).
 ///
 /// [program attestation]: https://triton-vm.org/spec/program-attestation.html
 /// [label_for_address]: Program::label_for_address
 /// [is_breakpoint]: Program::is_breakpoint
+/// [source_location]: Program::source_location
 #[derive(Debug, Clone, Eq, Serialize, Deserialize, GetSize)]
 pub struct Program {
     pub instructions: Vec<Instruction>,
-    address_to_label: HashMap<u64, String>,
-    debug_information: DebugInformation,
+    pub(crate) address_to_label: HashMap<u64, String>,
+    pub(crate) debug_information: DebugInformation,
+    pub(crate) static_data: Vec<StaticData>,
 }
 
 impl Display for Program {
@@ -107,6 +114,7 @@ impl BFieldCodec for Program {
             instructions,
             address_to_label: HashMap::default(),
             debug_information: DebugInformation::default(),
+            static_data: vec![],
         }))
     }
 
@@ -145,11 +153,22 @@ impl<'a> Arbitrary<'a> for Program {
         };
 
         let mut labelled_instructions = vec![];
+        let mut initialized_addresses = HashSet::new();
         for _ in 0..u.arbitrary_len::<LabelledInstruction>()? {
             let labelled_instruction = u.arbitrary()?;
             if contains_label(&labelled_instructions, &labelled_instruction) {
                 continue;
             }
+            if let LabelledInstruction::StaticData(data) = &labelled_instruction {
+                // static data must not overlap
+                let addresses = data.ram_entries().map(|(address, _)| address).collect_vec();
+                if addresses.iter().any(|a| initialized_addresses.contains(a))
+                    || !addresses.iter().all_unique()
+                {
+                    continue;
+                }
+                initialized_addresses.extend(addresses);
+            }
             if let LabelledInstruction::AssertionContext(_) = labelled_instruction {
                 // assertion context must come after an assertion
                 continue;
@@ -216,10 +235,33 @@ impl IntoIterator for Program {
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, Arbitrary, GetSize)]
-struct DebugInformation {
-    breakpoints: Vec<bool>,
-    type_hints: HashMap<u64, Vec<TypeHint>>,
-    assertion_context: HashMap<u64, AssertionContext>,
+pub(crate) struct DebugInformation {
+    pub(crate) breakpoints: Vec<bool>,
+    pub(crate) type_hints: HashMap<u64, Vec<TypeHint>>,
+    pub(crate) assertion_context: HashMap<u64, AssertionContext>,
+    pub(crate) source_map: HashMap<u64, SourceLocation>,
+}
+
+/// A position in the source code a [`Program`] was parsed from.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, GetSize, Arbitrary)]
+pub struct SourceLocation {
+    /// The name of the file containing the source code, if known.
+    pub file: Option<String>,
+
+    /// The line, starting at 1.
+    pub line: usize,
+
+    /// The column, starting at 1 and counted in characters.
+    pub column: usize,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 impl Program {
@@ -229,12 +271,20 @@ impl Program {
             parser::turn_labels_into_addresses(labelled_instructions, &label_to_address);
         let address_to_label = Self::flip_map(label_to_address);
         let debug_information = Self::extract_debug_information(labelled_instructions);
+        let static_data = labelled_instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::StaticData(data) => Some(data.clone()),
+                _ => None,
+            })
+            .collect();
 
         debug_assert_eq!(instructions.len(), debug_information.breakpoints.len());
         Program {
             instructions,
             address_to_label,
             debug_information,
+            static_data,
         }
     }
 
@@ -256,7 +306,7 @@ impl Program {
                     break_before_next_instruction = false;
                     address += instruction.size() as u64;
                 }
-                LabelledInstruction::Label(_) => (),
+                LabelledInstruction::Label(_) | LabelledInstruction::StaticData(_) => (),
                 LabelledInstruction::Breakpoint => break_before_next_instruction = true,
                 LabelledInstruction::TypeHint(hint) => match debug_info.type_hints.entry(address) {
                     Entry::Occupied(mut entry) => entry.get_mut().push(hint.clone()),
@@ -275,26 +325,103 @@ impl Program {
     }
 
     /// Create a `Program` by parsing source code.
+    ///
+    /// The program remembers the [location](Self::source_location) in the source
+    /// code of each of its instructions. Instructions resulting from a macro
+    /// invocation are located in the macro's definition.
     pub fn from_code(code: &str) -> Result<Self, ParseError> {
-        parser::parse(code)
-            .map(|tokens| parser::to_labelled_instructions(&tokens))
-            .map(|instructions| Program::new(&instructions))
+        let tokens = parser::parse(code)?;
+        let mut program = Program::new(&parser::to_labelled_instructions(&tokens));
+        program.debug_information.source_map = Self::build_source_map(code, &tokens);
+        Ok(program)
+    }
+
+    fn build_source_map(code: &str, tokens: &[InstructionToken]) -> HashMap<u64, SourceLocation> {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(index, _)| index + 1))
+            .collect_vec();
+
+        let mut source_map = HashMap::new();
+        let mut address = 0;
+        for token in tokens {
+            let InstructionToken::Instruction(instruction, token_str) = token else {
+                continue;
+            };
+            let offset = (token_str.as_ptr() as usize).checked_sub(code.as_ptr() as usize);
+            if let Some(offset) = offset.filter(|&offset| offset <= code.len()) {
+                let line = line_starts.partition_point(|&start| start <= offset);
+                let column = code[line_starts[line - 1]..offset].chars().count() + 1;
+                let location = SourceLocation {
+                    file: None,
+                    line,
+                    column,
+                };
+                source_map.insert(address, location);
+            }
+            address += instruction.size() as u64;
+        }
+
+        source_map
+    }
+
+    /// Set the name of the file the program's source code was read from. The
+    /// name becomes part of every [source location](Self::source_location).
+    #[must_use]
+    pub fn with_source_file(mut self, file: impl Into<String>) -> Self {
+        let file = file.into();
+        for location in self.debug_information.source_map.values_mut() {
+            location.file = Some(file.clone());
+        }
+        self
     }
 
     pub fn labelled_instructions(&self) -> Vec<LabelledInstruction> {
-        let call_targets = self.call_targets();
+        let end_address = self.len_bwords() as u64;
+        let leftover_label_addresses = self
+            .address_to_label
+            .keys()
+            .copied()
+            .filter(|&address| address >= end_address);
+        let labels = self
+            .call_targets()
+            .into_iter()
+            .chain(leftover_label_addresses)
+            .map(|address| (address, self.label_for_address(address)))
+            .collect();
+
+        self.labelled_instructions_with_labels(&labels)
+    }
+
+    /// Like [`labelled_instructions()`](Self::labelled_instructions), but with the
+    /// given labels instead of the program's own. Calls to addresses without a
+    /// given label fall back to [`label_for_address()`](Self::label_for_address).
+    /// Labels for addresses beyond the end of the program are put at the end.
+    pub(crate) fn labelled_instructions_with_labels(
+        &self,
+        labels: &HashMap<u64, String>,
+    ) -> Vec<LabelledInstruction> {
+        let label_for_address = |address| {
+            labels
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| self.label_for_address(address))
+        };
         let instructions_with_labels = self.instructions.iter().map(|instruction| {
-            instruction.map_call_address(|&address| self.label_for_address(address.value()))
+            instruction.map_call_address(|&address| label_for_address(address.value()))
         });
 
-        let mut labelled_instructions = vec![];
+        let mut labelled_instructions = self
+            .static_data
+            .iter()
+            .cloned()
+            .map(LabelledInstruction::StaticData)
+            .collect_vec();
         let mut address = 0;
         let mut instruction_stream = instructions_with_labels.into_iter();
         while let Some(instruction) = instruction_stream.next() {
             let instruction_size = instruction.size() as u64;
-            if call_targets.contains(&address) {
-                let label = self.label_for_address(address);
-                let label = LabelledInstruction::Label(label);
+            if let Some(label) = labels.get(&address) {
+                let label = LabelledInstruction::Label(label.clone());
                 labelled_instructions.push(label);
             }
             for type_hint in self.type_hints_at(address) {
@@ -314,8 +441,7 @@ impl Program {
             address += instruction_size;
         }
 
-        let leftover_labels = self
-            .address_to_label
+        let leftover_labels = labels
             .iter()
             .filter(|(&labels_address, _)| labels_address >= address)
             .sorted();
@@ -326,6 +452,28 @@ impl Program {
         labelled_instructions
     }
 
+    /// The initial state of RAM as declared by the program's static data, _i.e._,
+    /// through directive `.data`. Since RAM is non-deterministic, the static data
+    /// is only available to the program if it is part of the initial RAM, for
+    /// example via Triton VM's `NonDeterminism::with_ram`.
+    ///
+    /// If several blocks of static data initialize the same address, the later one
+    /// takes precedence. Programs parsed from source code never contain such overlaps.
+    ///
+    /// The static data is neither part of the program's [digest](Self::hash) nor of
+    /// its [encoding](BFieldCodec::encode), and does not influence
+    /// [equality](PartialEq). The digest is what a proof attests to, and it must
+    /// agree with the digest Triton VM computes from the instructions alone while
+    /// proving. Because the static data only reaches the program as non-determinism,
+    /// a proof does not attest to it either: a program must not rely on the static
+    /// data being present without checking it, just like any other secret input.
+    pub fn static_data(&self) -> HashMap<BFieldElement, BFieldElement> {
+        self.static_data
+            .iter()
+            .flat_map(StaticData::ram_entries)
+            .collect()
+    }
+
     fn call_targets(&self) -> HashSet<u64> {
         self.instructions
             .iter()
@@ -360,6 +508,12 @@ impl Program {
             .cloned()
     }
 
+    /// The location in the source code of the instruction at the given address.
+    /// Only known for programs [parsed from source code](Self::from_code).
+    pub fn source_location(&self, address: u64) -> Option<SourceLocation> {
+        self.debug_information.source_map.get(&address).cloned()
+    }
+
     /// Turn the program into a sequence of `BFieldElement`s. Each instruction is encoded as its
     /// opcode, followed by its argument (if any).
     ///
@@ -391,11 +545,19 @@ impl Program {
 
     /// Produces the program's canonical hash digest. Uses [`Tip5`], the
     /// canonical hash function for Triton VM.
+    ///
+    /// Only the instructions are hashed; the digest does not cover debug
+    /// information or [static data](Self::static_data).
     pub fn hash(&self) -> Digest {
         // not encoded using `BFieldCodec` because that would prepend the length
         Tip5::hash_varlen(&self.to_bwords())
     }
 
+    /// The labels of the program, by address.
+    pub(crate) fn address_to_label(&self) -> &HashMap<u64, String> {
+        &self.address_to_label
+    }
+
     /// The label for the given address, or a deterministic, unique substitute if no label is found.
     pub fn label_for_address(&self, address: u64) -> String {
         // Uniqueness of the label is relevant for printing and subsequent parsing:
@@ -583,6 +745,27 @@ mod tests {
         println!("{program}");
     }
 
+    #[test]
+    fn static_data_survives_printing_and_parsing() {
+        let program = Program::from_code(".data 10 [4, 5] push 0 .data 3 [-1] halt").unwrap();
+        let expected_ram = [(10, 4), (11, 5), (3, -1)].map(|(a, v)| (bfe!(a), bfe!(v)));
+        assert!(HashMap::from(expected_ram) == program.static_data());
+
+        let reparsed_program = Program::from_code(&program.to_string()).unwrap();
+        assert!(program.static_data() == reparsed_program.static_data());
+    }
+
+    #[test]
+    fn static_data_is_neither_hashed_nor_encoded() {
+        let program = Program::from_code("push 0 halt").unwrap();
+        let program_with_data = Program::from_code(".data 10 [4, 5] push 0 halt").unwrap();
+        assert!(program.hash() == program_with_data.hash());
+        assert!(program.encode() == program_with_data.encode());
+
+        let decoded_program = Program::decode(&program_with_data.encode()).unwrap();
+        assert!(decoded_program.static_data().is_empty());
+    }
+
     #[proptest]
     fn printed_program_can_be_parsed_again(#[strategy(arb())] program: Program) {
         parser::parse(&program.to_string())?;
@@ -744,4 +927,48 @@ mod tests {
         let printed_program = format!("{program}");
         assert_eq!(source_code, &printed_program);
     }
+
+    #[test]
+    fn parsed_program_knows_source_location_of_each_instruction() {
+        let source_code = "push 1 // the first instruction\n\
+            \tpop 1\n\
+            \n\
+            foo:  halt";
+        let program = Program::from_code(source_code).unwrap();
+
+        let location = |line, column| SourceLocation {
+            file: None,
+            line,
+            column,
+        };
+        assert!(Some(location(1, 1)) == program.source_location(0));
+        assert!(None == program.source_location(1));
+        assert!(Some(location(2, 2)) == program.source_location(2));
+        assert!(Some(location(4, 7)) == program.source_location(4));
+        assert!(None == program.source_location(5));
+    }
+
+    #[test]
+    fn source_location_includes_file_name_if_known() {
+        let program = Program::from_code("push 1\nhalt")
+            .unwrap()
+            .with_source_file("main.tasm");
+        let_assert!(Some(location) = program.source_location(2));
+        assert!("main.tasm:2:1" == location.to_string());
+    }
+
+    #[test]
+    fn instructions_from_macros_are_located_in_macro_definition() {
+        let source_code = ".macro double\n    push 2 mul\n.endmacro\ndouble! double! halt";
+        let program = Program::from_code(source_code).unwrap();
+        let_assert!(Some(location) = program.source_location(3));
+        assert!(2 == location.line);
+        assert!(5 == location.column);
+    }
+
+    #[test]
+    fn program_from_labelled_instructions_has_no_source_locations() {
+        let program = triton_program!(push 1 halt);
+        assert!(None == program.source_location(0));
+    }
 }
@@ -0,0 +1,138 @@
+//! A standard library of reusable functions written in [Triton assembly][tasm].
+//!
+//! Every function is returned by a builder as a list of
+//! [`LabelledInstruction`]s. The list starts with the function's label, which
+//! is prefixed with `std_`. Include the instructions in a
+//! program and `call` the label, or link the [`module`] of the entire library
+//! using the [`Linker`](crate::linker::Linker).
+//!
+//! ```
+//! # use triton_isa::stdlib::arithmetic;
+//! # use triton_isa::triton_program;
+//! let program = triton_program!(
+//!     read_io 4           // _ [a; 2] [b; 2]
+//!     call std_u64_add    // _ [a + b; 2]
+//!     write_io 2
+//!     halt
+//!     {&arithmetic::u64_add()}
+//! );
+//! ```
+//!
+//! All functions follow the same calling convention:
+//!
+//! - Arguments are consumed from the stack and results are left in their place.
+//! - Operands are listed in reading order: for stack `_ [a] [b]`, function
+//!   `std_u64_sub` computes `a - b` and function `std_u64_lt` computes `a < b`.
+//!   Note that this is the opposite of instruction `lt`.
+//! - Integers wider than 32 bits are stored in u32 limbs, most significant limb
+//!   deepest. For example, a `u64` takes up two stack elements `_ hi lo`, like
+//!   the result of instruction `split`.
+//! - Inputs outside of a function's documented domain make the VM crash, unless
+//!   documented otherwise.
+//!
+//! The documented stack effects list the stack before and after the call. The
+//! documented costs are the number of clock cycles from the function's first
+//! instruction up to and including its `return`. The caller's `call` takes one
+//! more cycle.
+//!
+//! [tasm]: https://triton-vm.org/spec/instructions.html
+
+use crate::instruction::LabelledInstruction;
+use crate::linker::Module;
+
+pub mod arithmetic;
+pub mod list;
+pub mod memory;
+pub mod merkle;
+pub mod polynomial;
+
+/// The name of the standard library's [`Module`].
+pub const MODULE_NAME: &str = "std";
+
+/// All functions of the standard library as one [`Module`], exporting the label
+/// of every function.
+pub fn module() -> Module {
+    let functions = functions();
+    let exports = functions.iter().map(|function| entry_label(function));
+    let exports = exports.map(str::to_string).collect::<Vec<_>>();
+
+    Module::new(MODULE_NAME, functions.concat()).with_exports(exports)
+}
+
+fn functions() -> Vec<Vec<LabelledInstruction>> {
+    vec![
+        arithmetic::u64_add(),
+        arithmetic::u64_sub(),
+        arithmetic::u64_lt(),
+        arithmetic::u64_eq(),
+        arithmetic::u64_mul(),
+        arithmetic::u64_shr(),
+        arithmetic::u128_add(),
+        arithmetic::u128_sub(),
+        arithmetic::u128_lt(),
+        arithmetic::u128_eq(),
+        list::length(),
+        list::get(),
+        list::set(),
+        list::push(),
+        list::pop(),
+        memory::memcpy(),
+        merkle::root(),
+        polynomial::xfe_evaluate(),
+    ]
+}
+
+fn entry_label(function: &[LabelledInstruction]) -> &str {
+    let Some(LabelledInstruction::Label(label)) = function.first() else {
+        panic!("every function of the standard library starts with its label");
+    };
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use assert2::assert;
+
+    use super::*;
+    use crate::linker::Linker;
+    use crate::triton_asm;
+
+    #[test]
+    fn all_labels_are_prefixed() {
+        for instruction in functions().concat() {
+            if let LabelledInstruction::Label(label) = instruction {
+                assert!(label.starts_with("std_"));
+            }
+        }
+    }
+
+    #[test]
+    fn labels_are_unique() {
+        let instructions = functions().concat();
+        let labels = instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LabelledInstruction::Label(label) => Some(label),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let unique_labels = labels.iter().collect::<HashSet<_>>();
+        assert!(labels.len() == unique_labels.len());
+    }
+
+    #[test]
+    fn standard_library_can_be_linked() {
+        let main = Module::new("main", triton_asm!(call std_u64_add call std_memcpy halt));
+        let program = Linker::new()
+            .with_module(main)
+            .with_module(module())
+            .link_program("main")
+            .unwrap();
+
+        let code = program.to_string();
+        assert!(code.contains("std_memcpy"));
+        assert!(!code.contains("std_u128_add"));
+    }
+}
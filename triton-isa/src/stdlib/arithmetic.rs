@@ -0,0 +1,208 @@
+//! Arithmetic on unsigned integers wider than 32 bits. A `u64` is stored as two
+//! u32 limbs `_ hi lo`, a `u128` as four u32 limbs, most significant limb
+//! deepest.
+//!
+//! All limbs must be u32s. Not every function checks this; for example, adding
+//! limbs that are not u32s gives a meaningless result instead of a crash.
+
+use crate::instruction::LabelledInstruction;
+use crate::triton_asm;
+
+/// The number of u32 limbs of a `u64`.
+const U64_LIMBS: usize = 2;
+
+/// The number of u32 limbs of a `u128`.
+const U128_LIMBS: usize = 4;
+
+/// Add two `u64`s. Crashes if the sum overflows.
+///
+/// Stack: `_ [a; 2] [b; 2]` → `_ [a + b; 2]`
+///
+/// Cost: 13 clock cycles.
+pub fn u64_add() -> Vec<LabelledInstruction> {
+    add("std_u64_add", U64_LIMBS)
+}
+
+/// Subtract two `u64`s. Crashes if the difference underflows, _i.e._, if
+/// `a < b`.
+///
+/// Stack: `_ [a; 2] [b; 2]` → `_ [a - b; 2]`
+///
+/// Cost: 18 clock cycles.
+pub fn u64_sub() -> Vec<LabelledInstruction> {
+    sub("std_u64_sub", U64_LIMBS)
+}
+
+/// Compare two `u64`s.
+///
+/// Stack: `_ [a; 2] [b; 2]` → `_ (a < b)`
+///
+/// Cost: 14 clock cycles.
+pub fn u64_lt() -> Vec<LabelledInstruction> {
+    lt("std_u64_lt", U64_LIMBS)
+}
+
+/// Test two `u64`s for equality. Only checks the limbs for equality, meaning
+/// that it does not crash if some limb is not a u32.
+///
+/// Stack: `_ [a; 2] [b; 2]` → `_ (a == b)`
+///
+/// Cost: 7 clock cycles.
+pub fn u64_eq() -> Vec<LabelledInstruction> {
+    eq("std_u64_eq", U64_LIMBS)
+}
+
+/// Multiply two `u64`s. The product is a `u128` and never overflows.
+///
+/// Stack: `_ [a; 2] [b; 2]` → `_ [a · b; 4]`
+///
+/// Cost: 33 clock cycles.
+pub fn u64_mul() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ a1 a0 b1 b0
+        std_u64_mul:
+            dup 0 dup 3 mul split   // _ a1 a0 b1 b0 hi(a0·b0) c0
+            place 5                 // _ c0 a1 a0 b1 b0 hi(a0·b0)
+            dup 3 dup 3 mul split   // _ c0 a1 a0 b1 b0 hi(a0·b0) hi(a0·b1) lo(a0·b1)
+            pick 2 add              // _ c0 a1 a0 b1 b0 hi(a0·b1) s
+            dup 5 dup 3 mul split   // _ c0 a1 a0 b1 b0 hi(a0·b1) s hi(a1·b0) lo(a1·b0)
+            pick 2 add split        // _ c0 a1 a0 b1 b0 hi(a0·b1) hi(a1·b0) carry c1
+            place 8                 // _ c1 c0 a1 a0 b1 b0 hi(a0·b1) hi(a1·b0) carry
+            add add                 // _ c1 c0 a1 a0 b1 b0 t
+            pick 4 pick 3 mul split // _ c1 c0 a0 b0 t hi(a1·b1) lo(a1·b1)
+            pick 2 add split        // _ c1 c0 a0 b0 hi(a1·b1) carry c2
+            place 6                 // _ c2 c1 c0 a0 b0 hi(a1·b1) carry
+            add                     // _ c2 c1 c0 a0 b0 c3
+            place 5 pop 2           // _ c3 c2 c1 c0
+            return
+    )
+}
+
+/// Shift a `u64` to the right by `k` bits. Crashes unless `k < 32`.
+///
+/// Stack: `_ [a; 2] k` → `_ [a >> k; 2]`
+///
+/// Cost: 23 clock cycles.
+pub fn u64_shr() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ a1 a0 k
+        std_u64_shr:
+            push 32 dup 1 lt    // _ a1 a0 k (k < 32)
+            assert
+            push 2 pow          // _ a1 a0 2^k
+            dup 0 pick 3        // _ a0 2^k 2^k a1
+            div_mod             // _ a0 2^k (a1 >> k) (a1 mod 2^k)
+            place 3 place 3     // _ (a1 >> k) (a1 mod 2^k) a0 2^k
+            dup 0 invert        // _ (a1 >> k) (a1 mod 2^k) a0 2^k 2^-k
+            push 4294967296 mul // _ (a1 >> k) (a1 mod 2^k) a0 2^k 2^(32-k)
+            pick 3 mul place 2  // _ (a1 >> k) ((a1 mod 2^k) << (32-k)) a0 2^k
+            swap 1 div_mod      // _ (a1 >> k) ((a1 mod 2^k) << (32-k)) (a0 >> k) (a0 mod 2^k)
+            pop 1 add           // _ (a1 >> k) ((a1 mod 2^k) << (32-k) + (a0 >> k))
+            return
+    )
+}
+
+/// Add two `u128`s. Crashes if the sum overflows.
+///
+/// Stack: `_ [a; 4] [b; 4]` → `_ [a + b; 4]`
+///
+/// Cost: 23 clock cycles.
+pub fn u128_add() -> Vec<LabelledInstruction> {
+    add("std_u128_add", U128_LIMBS)
+}
+
+/// Subtract two `u128`s. Crashes if the difference underflows, _i.e._, if
+/// `a < b`.
+///
+/// Stack: `_ [a; 4] [b; 4]` → `_ [a - b; 4]`
+///
+/// Cost: 36 clock cycles.
+pub fn u128_sub() -> Vec<LabelledInstruction> {
+    sub("std_u128_sub", U128_LIMBS)
+}
+
+/// Compare two `u128`s.
+///
+/// Stack: `_ [a; 4] [b; 4]` → `_ (a < b)`
+///
+/// Cost: 36 clock cycles.
+pub fn u128_lt() -> Vec<LabelledInstruction> {
+    lt("std_u128_lt", U128_LIMBS)
+}
+
+/// Test two `u128`s for equality. Only checks the limbs for equality, meaning
+/// that it does not crash if some limb is not a u32.
+///
+/// Stack: `_ [a; 4] [b; 4]` → `_ (a == b)`
+///
+/// Cost: 15 clock cycles.
+pub fn u128_eq() -> Vec<LabelledInstruction> {
+    eq("std_u128_eq", U128_LIMBS)
+}
+
+/// Limb-wise addition with carry, least significant limb first. The sum of the
+/// `i`th limbs is split into the carry and the `i`th limb of the result, which is
+/// stashed below the remaining operands.
+fn add(label: &str, num_limbs: usize) -> Vec<LabelledInstruction> {
+    let n = num_limbs;
+    let mut code = triton_asm!({label}:);
+    for i in 0..n {
+        if i > 0 {
+            code.extend(triton_asm!(add)); // carry
+        }
+        code.extend(triton_asm!(pick {n - i} add split place {2 * n - 1 - i}));
+    }
+    code.extend(triton_asm!(push 0 eq assert return));
+    code
+}
+
+/// Limb-wise subtraction with borrow, least significant limb first. Adding 2^32
+/// to the difference of the `i`th limbs makes it non-negative. Splitting it
+/// gives the `i`th limb of the result and a flag that is 1 if there is no
+/// borrow, and 0 if there is.
+fn sub(label: &str, num_limbs: usize) -> Vec<LabelledInstruction> {
+    let n = num_limbs;
+    let mut code = triton_asm!({label}:);
+    for i in 0..n {
+        let offset = if i == 0 { 1_u64 << 32 } else { (1 << 32) - 1 };
+        if i > 0 {
+            code.extend(triton_asm!(swap 1)); // no-borrow flag
+        }
+        code.extend(triton_asm!(push -1 mul));
+        if i > 0 {
+            code.extend(triton_asm!(add));
+        }
+        code.extend(triton_asm!(
+            pick {n - i} add addi {offset} split place {2 * n - 1 - i}
+        ));
+    }
+    code.extend(triton_asm!(assert return));
+    code
+}
+
+/// Limb-wise comparison, least significant limb first. The accumulator `acc` is
+/// updated to `(a_i < b_i) + (a_i == b_i)·acc`.
+fn lt(label: &str, num_limbs: usize) -> Vec<LabelledInstruction> {
+    let n = num_limbs;
+    let mut code = triton_asm!({label}: pick {n} lt);
+    for i in 1..n {
+        code.extend(triton_asm!(
+            pick {n - i + 1} pick 2 // _ acc a_i b_i
+            dup 1 dup 1 eq place 3  // _ (a_i == b_i) acc a_i b_i
+            swap 1 lt place 2       // _ (a_i < b_i) (a_i == b_i) acc
+            mul add
+        ));
+    }
+    code.extend(triton_asm!(return));
+    code
+}
+
+fn eq(label: &str, num_limbs: usize) -> Vec<LabelledInstruction> {
+    let n = num_limbs;
+    let mut code = triton_asm!({label}: pick {n} eq);
+    for i in 1..n {
+        code.extend(triton_asm!(pick {n - i + 1} pick 2 eq mul));
+    }
+    code.extend(triton_asm!(return));
+    code
+}
@@ -0,0 +1,97 @@
+//! Lists of field elements in RAM. A list at address `*list` stores its length
+//! at `*list`, followed by its elements at `*list + 1`, `*list + 2`, and so on.
+//! The length and all indices must be u32s.
+//!
+//! Lists do not track their capacity. Pushing to a list writes past its last
+//! element, whether or not that memory is in use otherwise.
+
+use crate::instruction::LabelledInstruction;
+use crate::triton_asm;
+
+/// The number of elements in a list.
+///
+/// Stack: `_ *list` → `_ len`
+///
+/// Cost: 3 clock cycles.
+pub fn length() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        std_list_length:
+            read_mem 1 pop 1
+            return
+    )
+}
+
+/// The element at index `i` of a list. Crashes if `i` is out of bounds.
+///
+/// Stack: `_ *list i` → `_ element`
+///
+/// Cost: 11 clock cycles.
+pub fn get() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ *list i
+        std_list_get:
+            dup 1 read_mem 1 pop 1  // _ *list i len
+            dup 1 lt assert         // _ *list i
+            add addi 1              // _ *element
+            read_mem 1 pop 1        // _ element
+            return
+    )
+}
+
+/// Overwrite the element at index `i` of a list. Crashes if `i` is out of
+/// bounds.
+///
+/// Stack: `_ element *list i` → `_`
+///
+/// Cost: 11 clock cycles.
+pub fn set() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ element *list i
+        std_list_set:
+            dup 1 read_mem 1 pop 1  // _ element *list i len
+            dup 1 lt assert         // _ element *list i
+            add addi 1              // _ element *element
+            write_mem 1 pop 1       // _
+            return
+    )
+}
+
+/// Append an element to a list.
+///
+/// Stack: `_ *list element` → `_`
+///
+/// Cost: 11 clock cycles.
+pub fn push() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ *list element
+        std_list_push:
+            dup 1 read_mem 1 pop 1  // _ *list element len
+            dup 0 addi 1            // _ *list element len (len + 1)
+            dup 3 write_mem 1       // _ *list element len (*list + 1)
+            add write_mem 1         // _ *list (*list + len + 2)
+            pop 2                   // _
+            return
+    )
+}
+
+/// Remove the last element from a list and return it. Crashes if the list is
+/// empty.
+///
+/// Stack: `_ *list` → `_ element`
+///
+/// Cost: 19 clock cycles.
+pub fn pop() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ *list
+        std_list_pop:
+            dup 0 read_mem 1 pop 1  // _ *list len
+            dup 0 push 0 eq         // _ *list len (len == 0)
+            push 0 eq assert        // _ *list len
+            addi -1                 // _ *list (len - 1)
+            dup 0 dup 2             // _ *list (len - 1) (len - 1) *list
+            write_mem 1 pop 1       // _ *list (len - 1)
+            add addi 1              // _ *element
+            read_mem 1 pop 1        // _ element
+            return
+    )
+}
@@ -0,0 +1,31 @@
+//! Operations on contiguous regions of RAM.
+
+use crate::instruction::LabelledInstruction;
+use crate::triton_asm;
+
+/// Copy `n` words from `*src`, `*src + 1`, … to `*dst`, `*dst + 1`, ….
+/// Words are copied in ascending order of their addresses. Overlapping regions
+/// are handled correctly if `*dst` is less than or equal to `*src`.
+///
+/// Stack: `_ *dst *src n` → `_`
+///
+/// Cost: 8 + 13·n clock cycles.
+pub fn memcpy() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ *dst *src n
+        std_memcpy:
+            call std_memcpy_loop    // _ *dst' *src' 0
+            pop 3
+            return
+
+        // _ *dst *src n
+        std_memcpy_loop:
+            dup 0 push 0 eq skiz return
+            addi -1                 // _ *dst *src (n - 1)
+            pick 1 read_mem 1       // _ *dst (n - 1) word (*src - 1)
+            addi 2 place 3          // _ (*src + 1) *dst (n - 1) word
+            pick 2 write_mem 1      // _ (*src + 1) (n - 1) (*dst + 1)
+            place 2                 // _ (*dst + 1) (*src + 1) (n - 1)
+            recurse
+    )
+}
@@ -0,0 +1,40 @@
+//! Polynomials over the extension field.
+
+use crate::instruction::LabelledInstruction;
+use crate::triton_asm;
+
+/// Evaluate a polynomial with extension-field coefficients in an
+/// extension-field element `x`, using Horner's method. The polynomial's `n`
+/// coefficients are stored in RAM starting at address `*coefficients`, constant
+/// term first. Each coefficient takes up three consecutive words, its own
+/// constant term first.
+///
+/// Stack: `_ *coefficients n [x; 3]` → `_ [p(x); 3]`
+///
+/// Cost: 24 + 16·n clock cycles.
+pub fn xfe_evaluate() -> Vec<LabelledInstruction> {
+    triton_asm!(
+        // _ *coefficients n [x; 3]
+        std_polynomial_xfe_evaluate:
+            dup 3 push 3 mul        // _ *coefficients n [x; 3] 3n
+            pick 5 add addi -1      // _ n [x; 3] *last_word
+            place 4                 // _ *last_word n [x; 3]
+            push 0 push 0 push 0    // _ *last_word n [x; 3] [acc; 3]
+            call std_polynomial_xfe_evaluate_loop
+                                    // _ *word 0 [x; 3] [p(x); 3]
+            pick 7 pick 7 pop 2     // _ [x; 3] [p(x); 3]
+            pick 5 pick 5 pick 5    // _ [p(x); 3] [x; 3]
+            pop 3                   // _ [p(x); 3]
+            return
+
+        // _ *word k [x; 3] [acc; 3]
+        std_polynomial_xfe_evaluate_loop:
+            dup 6 push 0 eq skiz return
+            dup 5 dup 5 dup 5 xx_mul    // _ *word k [x; 3] [acc·x; 3]
+            pick 7 read_mem 3           // _ k [x; 3] [acc·x; 3] [coefficient; 3] (*word - 3)
+            place 10                    // _ (*word - 3) k [x; 3] [acc·x; 3] [coefficient; 3]
+            xx_add                      // _ (*word - 3) k [x; 3] [acc'; 3]
+            pick 6 addi -1 place 6      // _ (*word - 3) (k - 1) [x; 3] [acc'; 3]
+            recurse
+    )
+}
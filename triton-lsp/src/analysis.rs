@@ -0,0 +1,440 @@
+//! Static analysis of a single Triton assembly document.
+
+use std::ops::Range as Span;
+
+use isa::instruction::AnInstruction;
+use isa::instruction::ALL_INSTRUCTIONS;
+use isa::op_stack::NUM_OP_STACK_REGISTERS;
+use isa::parser;
+use isa::parser::InstructionToken;
+use itertools::Itertools;
+use lsp_types::CompletionItem;
+use lsp_types::CompletionItemKind;
+use lsp_types::Diagnostic;
+use lsp_types::DiagnosticSeverity;
+use lsp_types::Documentation;
+use lsp_types::Hover;
+use lsp_types::HoverContents;
+use lsp_types::MarkupContent;
+use lsp_types::MarkupKind;
+use lsp_types::Position;
+use lsp_types::Range;
+
+use crate::docs::documentation;
+
+/// A named thing in the source code, like a label or an instruction's mnemonic.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Symbol {
+    name: String,
+    span: Span<usize>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Problem {
+    message: String,
+    span: Span<usize>,
+}
+
+/// Everything the language server knows about one document. Spans are byte
+/// offsets into the document; they are converted to [LSP positions][Position],
+/// which count UTF-16 code units, only when answering requests.
+#[derive(Debug, Clone)]
+pub(crate) struct Analysis {
+    text: String,
+    line_starts: Vec<usize>,
+    problems: Vec<Problem>,
+    mnemonics: Vec<Symbol>,
+    label_definitions: Vec<Symbol>,
+    label_references: Vec<Symbol>,
+}
+
+impl Analysis {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect_vec();
+
+        let (tokens, _) = parser::tokenize_with_recovery(&text);
+        let offset = |s: &str| s.as_ptr() as usize - text.as_ptr() as usize;
+        let symbol = |name: &str, start| Symbol {
+            name: name.to_string(),
+            span: start..start + name.len(),
+        };
+
+        let mut mnemonics = vec![];
+        let mut label_definitions = vec![];
+        let mut label_references = vec![];
+        for token in &tokens {
+            // Tokens generated for structured control flow, like `if { … }`, point
+            // to the keyword that they are generated from; they are skipped.
+            match token {
+                InstructionToken::Label(label, token_str) if token_str.starts_with(label) => {
+                    label_definitions.push(symbol(label, offset(token_str)));
+                }
+                InstructionToken::Instruction(instruction, token_str)
+                    if token_str.starts_with(instruction.name()) =>
+                {
+                    mnemonics.push(symbol(instruction.name(), offset(token_str)));
+                    if let AnInstruction::Call(label) = instruction {
+                        let label_str = skip_comments_and_whitespace(&token_str["call".len()..]);
+                        label_references.push(symbol(label, offset(label_str)));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // Instructions and labels from macro expansions share their source code.
+        for symbols in [
+            &mut mnemonics,
+            &mut label_definitions,
+            &mut label_references,
+        ] {
+            symbols.sort_by_key(|symbol| symbol.span.start);
+            symbols.dedup();
+        }
+
+        let mut problems = parser::diagnose(&text)
+            .into_iter()
+            .map(|diagnostic| {
+                let line_start = line_starts[diagnostic.line - 1];
+                let byte_index = |column| {
+                    let mut char_indices = diagnostic.source_line.char_indices();
+                    let index = char_indices.nth(column).map(|(index, _)| index);
+                    line_start + index.unwrap_or(diagnostic.source_line.len())
+                };
+                let start = byte_index(diagnostic.column - 1);
+                let end = byte_index(diagnostic.column - 1 + diagnostic.len);
+                let mut message = diagnostic.message;
+                if let Some(suggestion) = diagnostic.suggestion {
+                    message = format!("{message}; did you mean `{suggestion}`?");
+                }
+                Problem {
+                    message,
+                    span: start..end,
+                }
+            })
+            .collect_vec();
+
+        // The stack depth is meaningless if some instructions could not be parsed.
+        if problems.is_empty() {
+            problems = stack_underflows(&text, &tokens);
+        }
+
+        Self {
+            text,
+            line_starts,
+            problems,
+            mnemonics,
+            label_definitions,
+            label_references,
+        }
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.problems
+            .iter()
+            .map(|problem| Diagnostic {
+                range: self.range(&problem.span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("triton-lsp".to_string()),
+                message: problem.message.clone(),
+                ..Diagnostic::default()
+            })
+            .collect()
+    }
+
+    /// The location where the label at the given position is defined.
+    pub fn definition(&self, position: Position) -> Option<Range> {
+        let label = self.label_at(position)?;
+        self.label_definitions
+            .iter()
+            .find(|definition| definition.name == label)
+            .map(|definition| self.range(&definition.span))
+    }
+
+    /// All locations where the label at the given position is called.
+    pub fn references(&self, position: Position, include_declaration: bool) -> Vec<Range> {
+        let Some(label) = self.label_at(position) else {
+            return vec![];
+        };
+        let declarations = self
+            .label_definitions
+            .iter()
+            .filter(|_| include_declaration);
+
+        declarations
+            .chain(&self.label_references)
+            .filter(|symbol| symbol.name == label)
+            .map(|symbol| self.range(&symbol.span))
+            .collect()
+    }
+
+    /// Documentation for the instruction at the given position.
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let offset = self.offset(position);
+        let mnemonic = self
+            .mnemonics
+            .iter()
+            .find(|mnemonic| mnemonic.span.contains(&offset))?;
+        let instruction = ALL_INSTRUCTIONS
+            .into_iter()
+            .find(|instruction| instruction.name() == mnemonic.name)?;
+
+        let contents = HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation(instruction),
+        });
+        let range = Some(self.range(&mnemonic.span));
+        Some(Hover { contents, range })
+    }
+
+    /// All instructions, as well as all labels defined in the document.
+    pub fn completions(&self) -> Vec<CompletionItem> {
+        let instructions = ALL_INSTRUCTIONS.into_iter().map(|instruction| {
+            let documentation = MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: documentation(instruction),
+            };
+            CompletionItem {
+                label: instruction.name().to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                documentation: Some(Documentation::MarkupContent(documentation)),
+                ..CompletionItem::default()
+            }
+        });
+        let labels = self.label_definitions.iter().map(|label| CompletionItem {
+            label: label.name.clone(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            ..CompletionItem::default()
+        });
+
+        instructions.chain(labels).collect()
+    }
+
+    /// The name of the label defined or called at the given position.
+    fn label_at(&self, position: Position) -> Option<&str> {
+        let offset = self.offset(position);
+        self.label_definitions
+            .iter()
+            .chain(&self.label_references)
+            .find(|symbol| symbol.span.contains(&offset) || symbol.span.end == offset)
+            .map(|symbol| symbol.name.as_str())
+    }
+
+    fn range(&self, span: &Span<usize>) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        let Some(&line_start) = self.line_starts.get(line) else {
+            return self.text.len();
+        };
+        let line_end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |&next_line_start| next_line_start - 1);
+
+        let mut character = 0;
+        for (index, c) in self.text[line_start..line_end].char_indices() {
+            if character >= position.character as usize {
+                return line_start + index;
+            }
+            character += c.len_utf16();
+        }
+        line_end
+    }
+}
+
+fn skip_comments_and_whitespace(mut s: &str) -> &str {
+    loop {
+        s = s.trim_start();
+        if !s.starts_with("//") {
+            return s;
+        }
+        s = s.find('\n').map_or("", |index| &s[index..]);
+    }
+}
+
+/// Find instructions that definitely shrink the op stack below its minimum size
+/// of [`NUM_OP_STACK_REGISTERS`].
+///
+/// Only the straight-line code at the program's entry point is checked, where the
+/// op stack is known to have its minimum size. The check ends at the first label
+/// or control flow instruction, since the stack depth at the jump target is not
+/// known.
+fn stack_underflows(text: &str, tokens: &[InstructionToken]) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut depth = NUM_OP_STACK_REGISTERS as i32;
+    let mut is_conditional = false;
+    for token in tokens {
+        let instruction = match token {
+            InstructionToken::Instruction(instruction, _) => instruction,
+            InstructionToken::Label(..) => break,
+            _ => continue,
+        };
+
+        let new_depth = depth + instruction.op_stack_size_influence();
+        if is_conditional {
+            // The instruction might be skipped; assume the more forgiving outcome.
+            depth = depth.max(new_depth);
+            is_conditional = false;
+        } else if new_depth < NUM_OP_STACK_REGISTERS as i32 {
+            let token_str = token.token_str();
+            let start = token_str.as_ptr() as usize - text.as_ptr() as usize;
+            let message = format!(
+                "op stack underflow: `{}` leaves {new_depth} elements on the op stack, \
+                 but it must always hold at least {NUM_OP_STACK_REGISTERS}",
+                instruction.name(),
+            );
+            let span = start..start + instruction.name().len();
+            problems.push(Problem { message, span });
+            break;
+        } else {
+            depth = new_depth;
+        }
+
+        match instruction {
+            AnInstruction::Skiz => is_conditional = true,
+            AnInstruction::Halt
+            | AnInstruction::Call(_)
+            | AnInstruction::Return
+            | AnInstruction::Recurse
+            | AnInstruction::RecurseOrReturn => break,
+            _ => (),
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+
+    use isa::instruction::ALL_INSTRUCTION_NAMES;
+
+    use super::*;
+
+    #[test]
+    fn syntax_errors_are_reported_at_offending_token() {
+        let analysis = Analysis::new("push 1\npusj 2\nhalt");
+        let diagnostics = analysis.diagnostics();
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(Range::new(Position::new(1, 0), Position::new(1, 4)) == diagnostic.range);
+        assert!(diagnostic.message.contains("did you mean `push`?"));
+    }
+
+    #[test]
+    fn missing_labels_are_reported() {
+        let analysis = Analysis::new("call fooo halt\nfoo: return");
+        let diagnostics = analysis.diagnostics();
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(diagnostic.message.contains("missing label"));
+        assert!(diagnostic.message.contains("did you mean `foo`?"));
+    }
+
+    #[test]
+    fn stack_underflow_is_reported() {
+        let analysis = Analysis::new("push 1 pop 2 halt");
+        let diagnostics = analysis.diagnostics();
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(Range::new(Position::new(0, 7), Position::new(0, 10)) == diagnostic.range);
+        assert!(diagnostic.message.contains("op stack underflow"));
+    }
+
+    #[test]
+    fn balanced_stack_is_not_reported() {
+        let analysis = Analysis::new("push 1 push 2 add pop 1 call foo pop 5 halt\nfoo: return");
+        assert!(analysis.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn conditionally_skipped_instruction_does_not_cause_underflow_report() {
+        let analysis = Analysis::new("push 1 push 0 skiz pop 1 pop 1 halt");
+        assert!(analysis.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn definition_of_called_label_can_be_found() {
+        let analysis = Analysis::new("call foo\nhalt\nfoo:\n    return");
+        let definition = analysis.definition(Position::new(0, 6));
+        assert!(Some(Range::new(Position::new(2, 0), Position::new(2, 3))) == definition);
+    }
+
+    #[test]
+    fn all_references_of_label_can_be_found() {
+        let analysis = Analysis::new("call foo call bar call foo halt\nfoo: return\nbar: return");
+        let references = analysis.references(Position::new(1, 1), false);
+        assert!(2 == references.len());
+        assert!(Position::new(0, 5) == references[0].start);
+        assert!(Position::new(0, 23) == references[1].start);
+
+        let references_and_declaration = analysis.references(Position::new(1, 1), true);
+        assert!(3 == references_and_declaration.len());
+    }
+
+    #[test]
+    fn labels_called_from_within_macros_are_found_once() {
+        let code = ".macro m\n    call foo\n.endmacro\nm! m! halt\nfoo: return";
+        let analysis = Analysis::new(code);
+        let references = analysis.references(Position::new(4, 0), false);
+        assert!([Range::new(Position::new(1, 9), Position::new(1, 12))] == *references);
+    }
+
+    #[test]
+    fn structured_control_flow_is_understood() {
+        let analysis = Analysis::new("push 1 if { call foo } halt\nfoo: return");
+        assert!(analysis.diagnostics().is_empty());
+        let references = analysis.references(Position::new(1, 0), false);
+        assert!([Range::new(Position::new(0, 17), Position::new(0, 20))] == *references);
+        assert!(analysis.hover(Position::new(0, 8)).is_none());
+    }
+
+    #[test]
+    fn hovering_over_instruction_shows_its_documentation() {
+        let analysis = Analysis::new("push 1 hash halt");
+        let_assert!(Some(hover) = analysis.hover(Position::new(0, 9)));
+        let_assert!(HoverContents::Markup(contents) = hover.contents);
+        assert!(contents.value.contains("hash"));
+        assert!(contents.value.contains("Cascade"));
+        assert!(analysis.hover(Position::new(0, 6)).is_none());
+    }
+
+    #[test]
+    fn positions_count_utf16_code_units() {
+        // 𝔞 and 𝔟 are alphabetic, but outside the basic multilingual plane
+        let analysis = Analysis::new("call 𝔞𝔟 hash halt\n𝔞𝔟: return");
+        let definition = analysis.definition(Position::new(0, 7));
+        assert!(Some(Range::new(Position::new(1, 0), Position::new(1, 4))) == definition);
+
+        let_assert!(Some(hover) = analysis.hover(Position::new(0, 10)));
+        assert!(Some(Range::new(Position::new(0, 10), Position::new(0, 14))) == hover.range);
+
+        let analysis = Analysis::new("push 1 // 🦀🦀\npop 2 halt");
+        let diagnostics = analysis.diagnostics();
+        let_assert!([diagnostic] = diagnostics.as_slice());
+        assert!(Position::new(1, 0) == diagnostic.range.start);
+    }
+
+    #[test]
+    fn completions_include_all_instructions_and_labels() {
+        let analysis = Analysis::new("halt\nmy_label: return");
+        let completions = analysis.completions();
+        let labels = completions.iter().map(|c| c.label.as_str()).collect_vec();
+        assert!(ALL_INSTRUCTION_NAMES
+            .iter()
+            .all(|name| labels.contains(name)));
+        assert!(labels.contains(&"my_label"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Documentation of Triton VM's instructions, shown when hovering over them.
+
+use std::fmt::Write;
+
+use isa::instruction::AnInstruction;
+use isa::instruction::Instruction;
+
+/// The tables of the [Arithmetic Execution Tables][aet] an instruction adds rows
+/// to. Every instruction touches the Processor Table and the Program Table.
+///
+/// [aet]: https://triton-vm.org/spec/arithmetization.html
+fn tables_touched(instruction: Instruction) -> Vec<&'static str> {
+    let mut tables = vec!["Processor", "Program"];
+    if instruction.op_stack_size_influence() != 0 {
+        tables.push("Op Stack");
+    }
+    if matches!(
+        instruction,
+        AnInstruction::Call(_)
+            | AnInstruction::Return
+            | AnInstruction::Recurse
+            | AnInstruction::RecurseOrReturn
+    ) {
+        tables.push("Jump Stack");
+    }
+    if matches!(
+        instruction,
+        AnInstruction::ReadMem(_)
+            | AnInstruction::WriteMem(_)
+            | AnInstruction::SpongeAbsorbMem
+            | AnInstruction::MerkleStepMem
+            | AnInstruction::XxDotStep
+            | AnInstruction::XbDotStep
+    ) {
+        tables.push("RAM");
+    }
+    if matches!(
+        instruction,
+        AnInstruction::Hash
+            | AnInstruction::SpongeInit
+            | AnInstruction::SpongeAbsorb
+            | AnInstruction::SpongeAbsorbMem
+            | AnInstruction::SpongeSqueeze
+            | AnInstruction::MerkleStep
+            | AnInstruction::MerkleStepMem
+    ) {
+        tables.push("Hash");
+    }
+    if matches!(
+        instruction,
+        AnInstruction::Hash
+            | AnInstruction::SpongeAbsorb
+            | AnInstruction::SpongeAbsorbMem
+            | AnInstruction::SpongeSqueeze
+            | AnInstruction::MerkleStep
+            | AnInstruction::MerkleStepMem
+    ) {
+        tables.extend(["Cascade", "Lookup"]);
+    }
+    if instruction.is_u32_instruction() {
+        tables.push("U32");
+    }
+
+    tables
+}
+
+/// The instruction's signature, the op stack before and after executing it, and
+/// a description of what it does. Taken from the [specification][spec].
+///
+/// [spec]: https://triton-vm.org/spec/instructions.html
+fn specification(instruction: Instruction) -> [&'static str; 4] {
+    match instruction {
+        AnInstruction::Pop(_) => [
+            "`pop` + `n`",
+            "_ c b a",
+            "_",
+            "Pops the `n` top elements from the stack. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::Push(_) => [
+            "`push` + `a`",
+            "_",
+            "_ a",
+            "Pushes `a` onto the stack.",
+        ],
+        AnInstruction::Divine(_) => [
+            "`divine` + `n`",
+            "_",
+            "_ b a",
+            "Pushes `n` non-deterministic elements `a` to the stack. Interface for secret input. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::Pick(_) => [
+            "`pick` + `i`",
+            "_ d x c b a",
+            "_ d c b a x",
+            "Moves the element indicated by `i` to the top of the stack. 0 ⩽ `i` < 16",
+        ],
+        AnInstruction::Place(_) => [
+            "`place` + `i`",
+            "_ d c b a x",
+            "_ d x c b a",
+            "Moves the top of the stack to the indicated position `i`. 0 ⩽ `i` < 16",
+        ],
+        AnInstruction::Dup(_) => [
+            "`dup` + `i`",
+            "_ e d c b a",
+            "_ e d c b a d",
+            "Duplicates the element `i` positions away from the top. 0 ⩽ `i` < 16",
+        ],
+        AnInstruction::Swap(_) => [
+            "`swap` + `i`",
+            "_ e d c b a",
+            "_ e a c b d",
+            "Swaps the `i`th stack element with the top of the stack. 0 ⩽ `i` < 16",
+        ],
+        AnInstruction::Halt => [
+            "`halt`",
+            "_",
+            "_",
+            "Solves the halting problem (if the instruction is reached). Indicates graceful shutdown of the VM.",
+        ],
+        AnInstruction::Nop => [
+            "`nop`",
+            "_",
+            "_",
+            "Do nothing",
+        ],
+        AnInstruction::Skiz => [
+            "`skiz`",
+            "_ a",
+            "_",
+            "Skip next instruction if `a` is zero. `s` ∈ {1, 2, 3} depends on `a` and whether the next instruction takes an argument.",
+        ],
+        AnInstruction::Call(_) => [
+            "`call` + `d`",
+            "_",
+            "_",
+            "Push `(ip+2,d)` to the jump stack, and jump to absolute address `d`",
+        ],
+        AnInstruction::Return => [
+            "`return`",
+            "_",
+            "_",
+            "Pop one pair off the jump stack and jump to that pair's return address (which is the first element).",
+        ],
+        AnInstruction::Recurse => [
+            "`recurse`",
+            "_",
+            "_",
+            "Peek at the top pair of the jump stack and jump to that pair's destination address (which is the second element).",
+        ],
+        AnInstruction::RecurseOrReturn => [
+            "`recurse_or_return`",
+            "_ b a .....",
+            "_ b a .....",
+            "Like `recurse` if `st5 = a != b = st6`, like `return` if `a == b`.",
+        ],
+        AnInstruction::Assert => [
+            "`assert`",
+            "_ a",
+            "_",
+            "Pops `a` if `a == 1`, else crashes the virtual machine.",
+        ],
+        AnInstruction::ReadMem(_) => [
+            "`read_mem` + `n`",
+            "_ p+2",
+            "_ v2 v1 v0 p-1",
+            "Reads consecutive values `vi` from RAM at address `p` and puts them onto the op stack. Decrements RAM pointer (`st0`) by `n`. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::WriteMem(_) => [
+            "`write_mem` + `n`",
+            "_ v2 v1 v0 p",
+            "_ p+3",
+            "Writes op stack's `n` top-most values `vi` to RAM at the address `p+i`, popping the `vi`. Increments RAM pointer (`st0`) by `n`. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::Hash => [
+            "`hash`",
+            "_ jihgfedcba",
+            "_ yxwvu",
+            "Hashes the stack's 10 top-most elements and puts their digest onto the stack, shrinking the stack by 5.",
+        ],
+        AnInstruction::AssertVector => [
+            "`assert_vector`",
+            "_ edcba edcba",
+            "_ edcba",
+            "Assert equality of `st(i)` to `st(i+5)` for `0 <= i < 4`. Crashes the VM if any pair is unequal. Pops the 5 top-most elements.",
+        ],
+        AnInstruction::SpongeInit => [
+            "`sponge_init`",
+            "_",
+            "_",
+            "Initializes (resets) the Sponge's state. Must be the first Sponge instruction executed.",
+        ],
+        AnInstruction::SpongeAbsorb => [
+            "`sponge_absorb`",
+            "_ jihgfedcba",
+            "_",
+            "Absorbs the stack's ten top-most elements into the Sponge state.",
+        ],
+        AnInstruction::SpongeAbsorbMem => [
+            "`sponge_absorb_mem`",
+            "_ dcba p",
+            "_ hgfe (p+10)",
+            "Absorbs the ten RAM elements at addresses `p`, `p+1`, … into the Sponge state. Overwrites stack elements `st1` through `st4` with the first four absorbed elements.",
+        ],
+        AnInstruction::SpongeSqueeze => [
+            "`sponge_squeeze`",
+            "_",
+            "_ zyxwvutsrq",
+            "Squeezes the Sponge and pushes the 10 squeezed elements onto the stack.",
+        ],
+        AnInstruction::Add => [
+            "`add`",
+            "_ b a",
+            "_ c",
+            "Computes the sum (`c`) of the top two elements of the stack (`b` and `a`) over the field.",
+        ],
+        AnInstruction::AddI(_) => [
+            "`addi` + `a`",
+            "_ b",
+            "_ c",
+            "Computes the sum (`c`) of the top element of the stack (`b`) and the immediate argument (`a`).",
+        ],
+        AnInstruction::Mul => [
+            "`mul`",
+            "_ b a",
+            "_ c",
+            "Computes the product (`c`) of the top two elements of the stack (`b` and `a`) over the field.",
+        ],
+        AnInstruction::Invert => [
+            "`invert`",
+            "_ a",
+            "_ b",
+            "Computes the multiplicative inverse (over the field) of the top of the stack. Crashes the VM if the top of the stack is 0.",
+        ],
+        AnInstruction::Eq => [
+            "`eq`",
+            "_ b a",
+            "_ (a == b)",
+            "Tests the top two stack elements for equality.",
+        ],
+        AnInstruction::Split => [
+            "`split`",
+            "_ a",
+            "_ hi lo",
+            "Decomposes the top of the stack into the lower 32 bits and the upper 32 bits.",
+        ],
+        AnInstruction::Lt => [
+            "`lt`",
+            "_ b a",
+            "_ a<b",
+            "“Less than” of the stack's two top-most elements. Crashes the VM if `a` or `b` is not u32.",
+        ],
+        AnInstruction::And => [
+            "`and`",
+            "_ b a",
+            "_ a&b",
+            "Bitwise and of the stack's two top-most elements. Crashes the VM if `a` or `b` is not u32.",
+        ],
+        AnInstruction::Xor => [
+            "`xor`",
+            "_ b a",
+            "_ a^b",
+            "Bitwise exclusive or of the stack's two top-most elements. Crashes the VM if `a` or `b` is not u32.",
+        ],
+        AnInstruction::Log2Floor => [
+            "`log_2_floor`",
+            "_ a",
+            "_ ⌊log₂(a)⌋",
+            "The number of bits in `a` minus 1, _i.e._, ⌊log₂(a)⌋. Crashes the VM if `a` is 0 or not u32.",
+        ],
+        AnInstruction::Pow => [
+            "`pow`",
+            "_ e b",
+            "_ b**e",
+            "The top of the stack to the power of the stack's runner up. Crashes the VM if exponent `e` is not u32.",
+        ],
+        AnInstruction::DivMod => [
+            "`div_mod`",
+            "_ d n",
+            "_ q r",
+            "Division with remainder of numerator `n` by denominator `d`. Guarantees the properties `n == q·d + r` and `r < d`. Crashes the VM if `n` or `d` is not u32 or if `d` is 0.",
+        ],
+        AnInstruction::PopCount => [
+            "`pop_count`",
+            "_ a",
+            "_ w",
+            "Computes the [hamming weight](https://en.wikipedia.org/wiki/Hamming_weight) or “population count” of `a`. Crashes the VM if `a` is not u32.",
+        ],
+        AnInstruction::XxAdd => [
+            "`xx_add`",
+            "_ z y x b c a",
+            "_ w v u",
+            "Adds the two extension field elements encoded by field elements `z y x` and `b c a`.",
+        ],
+        AnInstruction::XxMul => [
+            "`xx_mul`",
+            "_ z y x b c a",
+            "_ w v u",
+            "Multiplies the two extension field elements encoded by field elements `z y x` and `b c a`.",
+        ],
+        AnInstruction::XInvert => [
+            "`x_invert`",
+            "_ z y x",
+            "_ w v u",
+            "Inverts the extension field element encoded by field elements `z y x` in-place. Crashes the VM if the extension field element is 0.",
+        ],
+        AnInstruction::XbMul => [
+            "`xb_mul`",
+            "_ z y x a",
+            "_ w v u",
+            "Scalar multiplication of the extension field element encoded by field elements `z y x` with field element `a`. Overwrites `z y x` with the result.",
+        ],
+        AnInstruction::ReadIo(_) => [
+            "`read_io` + `n`",
+            "_",
+            "_ c b a",
+            "Reads `n` B-Field elements from standard input and pushes them to the stack. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::WriteIo(_) => [
+            "`write_io` + `n`",
+            "_ c b a",
+            "_",
+            "Pops `n` elements from the stack and writes them to standard output. 1 ⩽ `n` ⩽ 5",
+        ],
+        AnInstruction::MerkleStep => [
+            "`merkle_step`",
+            "_ i edcba",
+            "_ (i div 2) zyxwv",
+            "Helps traversing a Merkle tree during authentication path verification. Crashes the VM if `i` is not u32.",
+        ],
+        AnInstruction::MerkleStepMem => [
+            "`merkle_step_mem`",
+            "_ p f i edcba",
+            "_ p+5 f (i div 2) zyxwv",
+            "Helps traversing a Merkle tree during authentication path verification with the authentication path being supplied in RAM. Crashes the VM if `i` is not u32.",
+        ],
+        AnInstruction::XxDotStep => [
+            "`xx_dot_step`",
+            "_ z y x *b *a",
+            "_ z+p2 y+p1 x+p0 *b+3 *a+3",
+            "Reads two extension field elements from RAM located at the addresses corresponding to the two top stack elements, multiplies the extension field elements, and adds the product `(p0, p1, p2)` to an accumulator located on stack immediately below the two pointers. Also, increase the pointers by the number of words read.",
+        ],
+        AnInstruction::XbDotStep => [
+            "`xb_dot_step`",
+            "_ z y x *b *a",
+            "_ z+p2 y+p1 x+p0 *b+3 *a+1",
+            "Reads one base field element from RAM located at the addresses corresponding to the top of the stack, one extension field element from RAM located at the address of the second stack element, multiplies the field elements, and adds the product `(p0, p1, p2)` to an accumulator located on stack immediately below the two pointers. Also, increase the pointers by the number of words read.",
+        ],
+    }
+}
+
+/// Markdown documentation for the given instruction: its opcode, its effect on
+/// the op stack, and the tables it touches.
+pub(crate) fn documentation(instruction: Instruction) -> String {
+    let [signature, old_op_stack, new_op_stack, description] = specification(instruction);
+    let tables = tables_touched(instruction).join(", ");
+
+    let mut documentation = String::new();
+    let _ = writeln!(documentation, "```tasm\n{}\n```", instruction.name());
+    let _ = writeln!(documentation, "{description}\n");
+    let _ = writeln!(documentation, "| | |\n|:--|:--|");
+    let _ = writeln!(documentation, "| instruction | {signature} |");
+    let _ = writeln!(documentation, "| opcode | {} |", instruction.opcode());
+    let _ = writeln!(documentation, "| op stack before | `{old_op_stack}` |");
+    let _ = writeln!(documentation, "| op stack after | `{new_op_stack}` |");
+    let _ = write!(documentation, "| tables | {tables} |");
+    documentation
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use isa::instruction::ALL_INSTRUCTIONS;
+
+    use super::*;
+
+    #[test]
+    fn every_instruction_is_documented() {
+        for instruction in ALL_INSTRUCTIONS {
+            let documentation = documentation(instruction);
+            assert!(documentation.contains(instruction.name()));
+            assert!(documentation.contains(&instruction.opcode().to_string()));
+        }
+    }
+
+    #[test]
+    fn hash_touches_all_hash_related_tables() {
+        let tables = tables_touched(AnInstruction::Hash);
+        assert!(["Op Stack", "Hash", "Cascade", "Lookup"]
+            .iter()
+            .all(|t| tables.contains(t)));
+        assert!(!tables.contains(&"U32"));
+    }
+}
@@ -0,0 +1,17 @@
+//! A [language server](https://microsoft.github.io/language-server-protocol/)
+//! for Triton assembly. Communicates over stdio.
+
+use lsp_server::Connection;
+
+mod analysis;
+mod docs;
+mod server;
+
+fn main() -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    server::run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! The language server's main loop: dispatching requests and notifications
+//! from the client to the [analysis](crate::analysis) of the relevant document.
+
+use std::collections::HashMap;
+
+use lsp_server::Connection;
+use lsp_server::ErrorCode;
+use lsp_server::Message;
+use lsp_server::Notification;
+use lsp_server::Request;
+use lsp_server::Response;
+use lsp_types::notification::DidChangeTextDocument;
+use lsp_types::notification::DidCloseTextDocument;
+use lsp_types::notification::DidOpenTextDocument;
+use lsp_types::notification::Notification as _;
+use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::Completion;
+use lsp_types::request::GotoDefinition;
+use lsp_types::request::HoverRequest;
+use lsp_types::request::References;
+use lsp_types::request::Request as _;
+use lsp_types::CompletionOptions;
+use lsp_types::CompletionResponse;
+use lsp_types::GotoDefinitionResponse;
+use lsp_types::HoverProviderCapability;
+use lsp_types::Location;
+use lsp_types::OneOf;
+use lsp_types::PublishDiagnosticsParams;
+use lsp_types::ServerCapabilities;
+use lsp_types::TextDocumentSyncCapability;
+use lsp_types::TextDocumentSyncKind;
+use lsp_types::Uri;
+
+use crate::analysis::Analysis;
+
+/// Run the language server on the given connection until the client asks it to
+/// shut down.
+pub fn run(connection: &Connection) -> anyhow::Result<()> {
+    let capabilities = serde_json::to_value(capabilities())?;
+    connection.initialize(capabilities)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(diagnostics) = server.handle_notification(notification) {
+                    connection.sender.send(Message::Notification(diagnostics))?;
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..ServerCapabilities::default()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Server {
+    documents: HashMap<Uri, Analysis>,
+}
+
+impl Server {
+    fn handle_request(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            GotoDefinition::METHOD => respond::<GotoDefinition>(request, |params| {
+                let params = params.text_document_position_params;
+                let uri = params.text_document.uri;
+                let range = self.documents.get(&uri)?.definition(params.position)?;
+                Some(GotoDefinitionResponse::Scalar(Location::new(uri, range)))
+            }),
+            References::METHOD => respond::<References>(request, |params| {
+                let include_declaration = params.context.include_declaration;
+                let params = params.text_document_position;
+                let uri = params.text_document.uri;
+                let analysis = self.documents.get(&uri)?;
+                let references = analysis
+                    .references(params.position, include_declaration)
+                    .into_iter()
+                    .map(|range| Location::new(uri.clone(), range))
+                    .collect();
+                Some(references)
+            }),
+            HoverRequest::METHOD => respond::<HoverRequest>(request, |params| {
+                let params = params.text_document_position_params;
+                let analysis = self.documents.get(&params.text_document.uri)?;
+                analysis.hover(params.position)
+            }),
+            Completion::METHOD => respond::<Completion>(request, |params| {
+                let uri = params.text_document_position.text_document.uri;
+                let completions = self.documents.get(&uri)?.completions();
+                Some(CompletionResponse::Array(completions))
+            }),
+            _ => {
+                let message = format!("unsupported request: {}", request.method);
+                Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message)
+            }
+        }
+    }
+
+    /// Update the affected document. Returns the notification publishing the
+    /// document's new diagnostics, if any.
+    fn handle_notification(&mut self, notification: Notification) -> Option<Notification> {
+        let (uri, version, text) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = extract::<DidOpenTextDocument>(notification)?;
+                let document = params.text_document;
+                (document.uri, document.version, Some(document.text))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = extract::<DidChangeTextDocument>(notification)?;
+                let document = params.text_document;
+                let text = params.content_changes.into_iter().last()?.text;
+                (document.uri, document.version, Some(text))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = extract::<DidCloseTextDocument>(notification)?;
+                (params.text_document.uri, 0, None)
+            }
+            _ => return None,
+        };
+
+        let diagnostics = match text {
+            Some(text) => {
+                let analysis = Analysis::new(text);
+                let diagnostics = analysis.diagnostics();
+                self.documents.insert(uri.clone(), analysis);
+                diagnostics
+            }
+            None => {
+                self.documents.remove(&uri);
+                vec![]
+            }
+        };
+
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, Some(version));
+        Some(Notification::new(PublishDiagnostics::METHOD.into(), params))
+    }
+}
+
+fn respond<R: lsp_types::request::Request>(
+    request: Request,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> Response {
+    let id = request.id.clone();
+    match request.extract(R::METHOD) {
+        Ok((id, params)) => Response::new_ok(id, handler(params)),
+        Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+    }
+}
+
+fn extract<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> Option<N::Params> {
+    notification.extract(N::METHOD).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use assert2::assert;
+    use assert2::let_assert;
+    use lsp_server::RequestId;
+    use lsp_types::request::Initialize;
+    use lsp_types::request::Shutdown;
+    use lsp_types::DidOpenTextDocumentParams;
+    use lsp_types::Hover;
+    use lsp_types::HoverContents;
+    use lsp_types::HoverParams;
+    use lsp_types::InitializeParams;
+    use lsp_types::Position;
+    use lsp_types::TextDocumentIdentifier;
+    use lsp_types::TextDocumentItem;
+    use lsp_types::TextDocumentPositionParams;
+
+    use super::*;
+
+    fn request<R: lsp_types::request::Request>(id: i32, params: R::Params) -> Message {
+        Message::Request(Request::new(RequestId::from(id), R::METHOD.into(), params))
+    }
+
+    fn notification<N: lsp_types::notification::Notification>(params: N::Params) -> Message {
+        Message::Notification(Notification::new(N::METHOD.into(), params))
+    }
+
+    #[test]
+    fn client_can_open_document_and_hover_over_instructions() {
+        let (server, client) = Connection::memory();
+        let server_thread = thread::spawn(move || run(&server));
+
+        client
+            .sender
+            .send(request::<Initialize>(1, InitializeParams::default()))
+            .unwrap();
+        let_assert!(Ok(Message::Response(response)) = client.receiver.recv());
+        let capabilities = response.result.unwrap()["capabilities"].clone();
+        assert!(serde_json::to_value(super::capabilities()).unwrap() == capabilities);
+        client
+            .sender
+            .send(notification::<lsp_types::notification::Initialized>(
+                lsp_types::InitializedParams {},
+            ))
+            .unwrap();
+
+        let uri = "file:///program.tasm".parse::<Uri>().unwrap();
+        let document = TextDocumentItem::new(uri.clone(), "tasm".into(), 0, "push 1 pusj".into());
+        let open_params = DidOpenTextDocumentParams {
+            text_document: document,
+        };
+        client
+            .sender
+            .send(notification::<DidOpenTextDocument>(open_params))
+            .unwrap();
+        let_assert!(Ok(Message::Notification(published)) = client.receiver.recv());
+        let_assert!(
+            Ok(diagnostics) =
+                published.extract::<PublishDiagnosticsParams>(PublishDiagnostics::METHOD)
+        );
+        assert!(1 == diagnostics.diagnostics.len());
+
+        let position =
+            TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), Position::new(0, 2));
+        let hover_params = HoverParams {
+            text_document_position_params: position,
+            work_done_progress_params: lsp_types::WorkDoneProgressParams::default(),
+        };
+        client
+            .sender
+            .send(request::<HoverRequest>(2, hover_params))
+            .unwrap();
+        let_assert!(Ok(Message::Response(response)) = client.receiver.recv());
+        let_assert!(
+            Ok(Some(hover)) = serde_json::from_value::<Option<Hover>>(response.result.unwrap())
+        );
+        let_assert!(HoverContents::Markup(contents) = hover.contents);
+        assert!(contents.value.contains("push"));
+
+        client.sender.send(request::<Shutdown>(3, ())).unwrap();
+        let_assert!(Ok(Message::Response(_)) = client.receiver.recv());
+        client
+            .sender
+            .send(notification::<lsp_types::notification::Exit>(()))
+            .unwrap();
+        let_assert!(Ok(Ok(())) = server_thread.join());
+    }
+}
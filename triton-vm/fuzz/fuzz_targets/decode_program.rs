@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triton_vm::prelude::*;
+use triton_vm_fuzz::bfield_elements;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(program) = Program::decode(&bfield_elements(data)) else {
+        return;
+    };
+    let re_decoded = Program::decode(&program.encode()).unwrap();
+    assert_eq!(program, re_decoded);
+});
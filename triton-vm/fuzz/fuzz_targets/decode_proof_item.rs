@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triton_vm::prelude::*;
+use triton_vm::proof_item::ProofItem;
+use triton_vm_fuzz::bfield_elements;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(item) = ProofItem::decode(&bfield_elements(data)) else {
+        return;
+    };
+    let re_decoded = ProofItem::decode(&item.encode()).unwrap();
+    assert_eq!(item, re_decoded);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triton_vm::prelude::*;
+use triton_vm::proof_stream::ProofStream;
+use triton_vm_fuzz::bfield_elements;
+
+fuzz_target!(|data: &[u8]| {
+    let proof = Proof(bfield_elements(data));
+    let Ok(proof_stream) = ProofStream::try_from(&proof) else {
+        return;
+    };
+    assert_eq!(proof, Proof::from(&proof_stream));
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triton_vm::isa::parser;
+use triton_vm::prelude::*;
+
+fuzz_target!(|code: &str| {
+    let _ = parser::tokenize(code);
+    let _ = Program::from_code(code);
+});
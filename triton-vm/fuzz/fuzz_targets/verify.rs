@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use triton_vm_fuzz::bfield_elements;
+use triton_vm_fuzz::decode_claim_and_proof;
+use triton_vm_fuzz::stark;
+
+// Seeded with valid proofs, the fuzzer mostly produces mutated proofs. The
+// verifier must reject those gracefully, without panicking.
+fuzz_target!(|data: &[u8]| {
+    let Some((claim, proof)) = decode_claim_and_proof(&bfield_elements(data)) else {
+        return;
+    };
+    let _ = stark().verify(&claim, &proof);
+});
@@ -0,0 +1,80 @@
+//! Generate the seed corpora for all fuzz targets from Triton VM's
+//! [example programs](triton_vm::example_programs). Run from the `fuzz`
+//! directory with `cargo run --release --bin generate_corpus`.
+
+use std::fs;
+use std::path::Path;
+
+use triton_vm::example_programs::*;
+use triton_vm::prelude::*;
+use triton_vm::proof_stream::ProofStream;
+use triton_vm_fuzz::bytes;
+use triton_vm_fuzz::encode_claim_and_proof;
+use triton_vm_fuzz::stark;
+
+fn main() -> std::io::Result<()> {
+    let example_programs = [
+        ("fibonacci_sequence", &*FIBONACCI_SEQUENCE, bfe_vec![10]),
+        (
+            "greatest_common_divisor",
+            &*GREATEST_COMMON_DIVISOR,
+            bfe_vec![42, 56],
+        ),
+        (
+            "many_u32_instructions",
+            &*PROGRAM_WITH_MANY_U32_INSTRUCTIONS,
+            vec![],
+        ),
+        ("verify_sudoku", &*VERIFY_SUDOKU, vec![]),
+        (
+            "calculate_new_mmr_peaks",
+            &*CALCULATE_NEW_MMR_PEAKS_FROM_APPEND_WITH_SAFE_LISTS,
+            vec![],
+        ),
+        (
+            "merkle_tree_authentication_path_verify",
+            &*MERKLE_TREE_AUTHENTICATION_PATH_VERIFY,
+            bfe_vec![0, 0, 0, 0, 0, 0],
+        ),
+        ("merkle_tree_update", &*MERKLE_TREE_UPDATE, vec![]),
+    ];
+
+    for (name, program, input) in example_programs {
+        write_seed("decode_program", name, &bytes(&program.encode()))?;
+        write_seed("tokenize", name, program.to_string().as_bytes())?;
+
+        // Programs that crash without further input still seed the targets above.
+        let public_input = PublicInput::new(input);
+        let non_determinism = NonDeterminism::default();
+        let Ok((aet, output)) =
+            VM::trace_execution(program.clone(), public_input.clone(), non_determinism)
+        else {
+            eprintln!("skipping proof for `{name}`: program crashes without more input");
+            continue;
+        };
+        let claim = Claim::about_program(program)
+            .with_input(public_input)
+            .with_output(output);
+        let proof = stark().prove(&claim, &aet).unwrap();
+
+        write_seed("proof_to_proof_stream", name, &bytes(&proof.0))?;
+        write_seed(
+            "verify",
+            name,
+            &bytes(&encode_claim_and_proof(&claim, &proof)),
+        )?;
+        let proof_stream = ProofStream::try_from(&proof).unwrap();
+        for (i, item) in proof_stream.items.iter().enumerate() {
+            let seed_name = format!("{name}_item_{i}");
+            write_seed("decode_proof_item", &seed_name, &bytes(&item.encode()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_seed(target: &str, name: &str, data: &[u8]) -> std::io::Result<()> {
+    let dir = Path::new("corpus").join(target);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), data)
+}
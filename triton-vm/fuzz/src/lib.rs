@@ -0,0 +1,50 @@
+//! Helpers shared between the fuzz targets and the generator of their seed
+//! corpora.
+
+use triton_vm::prelude::*;
+use triton_vm::test_utils::low_security_stark;
+use triton_vm::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+
+/// The number of bytes encoding one [`BFieldElement`].
+const BYTES_PER_ELEMENT: usize = 8;
+
+/// Interpret the raw fuzzer input as a sequence of field elements. Trailing
+/// bytes that do not make up an entire element are ignored, and values are
+/// reduced modulo the field's prime.
+pub fn bfield_elements(data: &[u8]) -> Vec<BFieldElement> {
+    data.chunks_exact(BYTES_PER_ELEMENT)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .map(BFieldElement::new)
+        .collect()
+}
+
+/// The inverse of [`bfield_elements`].
+pub fn bytes(elements: &[BFieldElement]) -> Vec<u8> {
+    elements
+        .iter()
+        .flat_map(|element| element.value().to_le_bytes())
+        .collect()
+}
+
+/// The parameters for all proofs generated and verified during fuzzing. Cheap,
+/// so that the fuzzer can make progress.
+pub fn stark() -> Stark {
+    low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS)
+}
+
+/// Encode a claim and a proof as input for fuzz target `verify`.
+pub fn encode_claim_and_proof(claim: &Claim, proof: &Proof) -> Vec<BFieldElement> {
+    vec![claim.encode(), proof.encode()].encode()
+}
+
+/// The inverse of [`encode_claim_and_proof`], if the input is well-formed.
+pub fn decode_claim_and_proof(sequence: &[BFieldElement]) -> Option<(Claim, Proof)> {
+    let parts = *Vec::<Vec<BFieldElement>>::decode(sequence).ok()?;
+    let [claim, proof] = parts.as_slice() else {
+        return None;
+    };
+    let claim = *Claim::decode(claim).ok()?;
+    let proof = *Proof::decode(proof).ok()?;
+
+    Some((claim, proof))
+}
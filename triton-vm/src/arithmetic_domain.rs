@@ -163,6 +163,19 @@ impl ArithmeticDomain {
         };
         Ok(domain)
     }
+
+    /// The domain of the `folding_factor`-th powers of this domain's elements.
+    /// Folding by 2 is the same as [halving](Self::halve).
+    ///
+    /// The folding factor must be a power of 2.
+    pub(crate) fn fold(&self, folding_factor: usize) -> Result<Self> {
+        debug_assert!(folding_factor.is_power_of_two());
+        let mut domain = *self;
+        for _ in 0..folding_factor.ilog2() {
+            domain = domain.halve()?;
+        }
+        Ok(domain)
+    }
 }
 
 #[cfg(test)]
@@ -316,6 +329,26 @@ mod tests {
         }
     }
 
+    #[proptest]
+    fn folding_domain_raises_all_points_to_folding_factor(
+        #[strategy(arbitrary_halveable_domain())] domain: ArithmeticDomain,
+        #[strategy(1..=#domain.length.ilog2())] log_2_folding_factor: u32,
+    ) {
+        let folding_factor = 1 << log_2_folding_factor;
+        let folded_domain = domain.fold(folding_factor)?;
+        prop_assert_eq!(domain.length / folding_factor, folded_domain.length);
+        for (domain_point, folded_domain_point) in domain
+            .domain_values()
+            .into_iter()
+            .zip(folded_domain.domain_values())
+        {
+            prop_assert_eq!(
+                domain_point.mod_pow(folding_factor as u64),
+                folded_domain_point
+            );
+        }
+    }
+
     #[test]
     fn too_small_domains_cannot_be_halved() {
         for i in [0, 1] {
@@ -0,0 +1,546 @@
+//! Persist the intermediate results of [proving](crate::stark::Prover::prove)
+//! in order to resume an interrupted proof. See
+//! [`Prover::with_checkpoint_directory`](crate::stark::Prover::with_checkpoint_directory).
+//!
+//! Since proving is deterministic given the claim, the algebraic execution
+//! trace, the parameters, and the randomness seed, a checkpoint only needs to
+//! contain the results of the expensive computations. Everything else,
+//! including the Fiat-Shamir state, is recomputed when resuming.
+//!
+//! Every checkpoint is stored in its own file. Each file starts with a
+//! fingerprint of the proof it belongs to, followed by the
+//! [encoding](BFieldCodec) of its content. Every [`BFieldElement`] is stored as
+//! 8 little-endian bytes.
+
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use ndarray::Array1;
+use ndarray::Array2;
+use ndarray::ArrayView2;
+use ndarray::Axis;
+use rand::prelude::StdRng;
+use rand_core::SeedableRng;
+use rayon::prelude::*;
+use twenty_first::prelude::*;
+
+use crate::proof::Claim;
+use crate::stark::Stark;
+use crate::stark::NUM_QUOTIENT_SEGMENTS;
+
+/// A point during proving after which the prover's state is persisted. In the
+/// order in which they occur.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub(crate) enum Stage {
+    /// After committing to the main table.
+    MainMerkleTree,
+
+    /// After committing to the auxiliary table.
+    AuxMerkleTree,
+
+    /// After committing to the quotient segments.
+    QuotientSegments,
+
+    /// After committing to the given round of [FRI](crate::fri::Fri).
+    FriRound(usize),
+}
+
+impl Stage {
+    fn all(num_fri_rounds: usize) -> impl Iterator<Item = Self> {
+        let fri_rounds = (0..=num_fri_rounds).map(Self::FriRound);
+        [
+            Self::MainMerkleTree,
+            Self::AuxMerkleTree,
+            Self::QuotientSegments,
+        ]
+        .into_iter()
+        .chain(fri_rounds)
+    }
+
+    fn file_name(self) -> String {
+        match self {
+            Self::MainMerkleTree => "main_merkle_tree.bin".to_string(),
+            Self::AuxMerkleTree => "aux_merkle_tree.bin".to_string(),
+            Self::QuotientSegments => "quotient_segments.bin".to_string(),
+            Self::FriRound(round) => format!("fri_round_{round}.bin"),
+        }
+    }
+}
+
+/// The quotient segments, both as codewords over the FRI domain and as
+/// polynomials.
+#[derive(Debug, Clone, Eq, PartialEq, BFieldCodec)]
+struct QuotientSegments {
+    /// The codewords over the FRI domain, row by row.
+    codewords: Vec<XFieldElement>,
+    polynomials: Vec<Polynomial<'static, XFieldElement>>,
+}
+
+/// The checkpoints of one proof.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub(crate) struct Checkpoints {
+    /// `None` if checkpointing is disabled.
+    directory: Option<PathBuf>,
+
+    /// Identifies the proof the checkpoints belong to.
+    fingerprint: Digest,
+
+    num_fri_rounds: usize,
+
+    /// The number of [stages](Stage), in order, for which a checkpoint of this
+    /// proof exists. Proving resumes after the last of them.
+    num_restorable_stages: usize,
+}
+
+impl Checkpoints {
+    /// The checkpoints of the proof identified by the given arguments, stored in
+    /// the given directory. If the directory is `None`, nothing is persisted or
+    /// restored.
+    ///
+    /// The padded main trace is part of the fingerprint because the claim does
+    /// not determine it: runs of the same program on the same public input can
+    /// differ in their non-determinism.
+    pub fn new(
+        directory: Option<&Path>,
+        claim: &Claim,
+        parameters: Stark,
+        randomness_seed: <StdRng as SeedableRng>::Seed,
+        padded_main_trace: ArrayView2<BFieldElement>,
+        num_fri_rounds: usize,
+    ) -> Self {
+        let Some(directory) = directory else {
+            return Self::default();
+        };
+
+        let parameters = [
+            parameters.security_level,
+            parameters.fri_expansion_factor,
+            parameters.num_trace_randomizers,
+            parameters.num_collinearity_checks,
+            parameters.fri_folding_factor,
+            parameters.num_grinding_bits,
+            padded_main_trace.nrows(),
+        ];
+        let parameters = parameters.map(|p| bfe!(p as u64));
+        let randomness_seed = randomness_seed.map(|byte| bfe!(byte));
+        let fingerprint_preimage = [
+            claim.encode(),
+            parameters.to_vec(),
+            randomness_seed.to_vec(),
+            Self::trace_digest(padded_main_trace).values().to_vec(),
+        ];
+
+        let mut checkpoints = Self {
+            directory: Some(directory.to_path_buf()),
+            fingerprint: Tip5::hash_varlen(&fingerprint_preimage.concat()),
+            num_fri_rounds,
+            num_restorable_stages: 0,
+        };
+        checkpoints.num_restorable_stages = Stage::all(num_fri_rounds)
+            .take_while(|&stage| checkpoints.exists(stage))
+            .count();
+
+        checkpoints
+    }
+
+    /// Hashes the rows in parallel, then the concatenation of their digests.
+    fn trace_digest(trace: ArrayView2<BFieldElement>) -> Digest {
+        let row_digests = trace
+            .axis_iter(Axis(0))
+            .into_par_iter()
+            .flat_map_iter(|row| Tip5::hash_varlen(&row.to_vec()).values())
+            .collect::<Vec<_>>();
+
+        Tip5::hash_varlen(&row_digests)
+    }
+
+    fn path(&self, stage: Stage) -> Option<PathBuf> {
+        Some(self.directory.as_ref()?.join(stage.file_name()))
+    }
+
+    /// Whether a checkpoint of this proof exists for the given stage. Only reads
+    /// the checkpoint's fingerprint.
+    fn exists(&self, stage: Stage) -> bool {
+        let Some(path) = self.path(stage) else {
+            return false;
+        };
+        let Ok(mut file) = File::open(path) else {
+            return false;
+        };
+        let mut fingerprint = [0; Digest::LEN * BFieldElement::BYTES];
+        if file.read_exact(&mut fingerprint).is_err() {
+            return false;
+        }
+
+        decode_bfes(&fingerprint).is_some_and(|f| f == self.fingerprint.values())
+    }
+
+    /// Whether the given stage can be restored instead of being recomputed.
+    pub fn is_restorable(&self, stage: Stage) -> bool {
+        let position = Stage::all(self.num_fri_rounds).position(|s| s == stage);
+        position.is_some_and(|position| position < self.num_restorable_stages)
+    }
+
+    fn restore<T: BFieldCodec>(&self, stage: Stage) -> Option<T> {
+        if !self.is_restorable(stage) {
+            return None;
+        }
+
+        let bytes = fs::read(self.path(stage)?).ok()?;
+        let bfes = decode_bfes(&bytes)?;
+        let (fingerprint, content) = bfes.split_at_checked(Digest::LEN)?;
+        if fingerprint != self.fingerprint.values().as_slice() {
+            return None;
+        }
+
+        T::decode(content).ok().map(|content| *content)
+    }
+
+    /// Persist the given content for the given stage. Does nothing if
+    /// checkpointing is disabled.
+    fn save<T: BFieldCodec>(&self, stage: Stage, content: &T) -> io::Result<()> {
+        let Some(path) = self.path(stage) else {
+            return Ok(());
+        };
+        if let Some(directory) = &self.directory {
+            fs::create_dir_all(directory)?;
+        }
+
+        // Write to a temporary file first and rename it afterward, so that no
+        // partially written checkpoint remains if the process dies while writing.
+        let temporary_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+        for bfe in self
+            .fingerprint
+            .values()
+            .into_iter()
+            .chain(content.encode())
+        {
+            writer.write_all(&<[u8; BFieldElement::BYTES]>::from(bfe))?;
+        }
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(temporary_path, path)
+    }
+
+    /// The Merkle tree of the given stage, if it can be restored and has the
+    /// expected number of leafs.
+    pub fn restore_merkle_tree(&self, stage: Stage, num_leafs: usize) -> Option<MerkleTree> {
+        let leafs = self.restore::<Vec<Digest>>(stage)?;
+        if leafs.len() != num_leafs {
+            return None;
+        }
+
+        MerkleTree::new::<CpuParallel>(&leafs).ok()
+    }
+
+    /// Persist the leafs of the given Merkle tree. The inner nodes are
+    /// recomputed when restoring.
+    pub fn save_merkle_tree(&self, stage: Stage, merkle_tree: &MerkleTree) -> io::Result<()> {
+        if self.directory.is_none() {
+            return Ok(());
+        }
+
+        self.save(stage, &merkle_tree.leafs().to_vec())
+    }
+
+    /// The codewords over the FRI domain and the polynomials of the quotient
+    /// segments, if they can be restored and the codewords have the expected
+    /// length.
+    pub fn restore_quotient_segments(
+        &self,
+        fri_domain_len: usize,
+    ) -> Option<(
+        Array2<XFieldElement>,
+        Array1<Polynomial<'static, XFieldElement>>,
+    )> {
+        let segments = self.restore::<QuotientSegments>(Stage::QuotientSegments)?;
+        let shape = (fri_domain_len, NUM_QUOTIENT_SEGMENTS);
+        let codewords = Array2::from_shape_vec(shape, segments.codewords).ok()?;
+        if segments.polynomials.len() != NUM_QUOTIENT_SEGMENTS {
+            return None;
+        }
+
+        Some((codewords, Array1::from(segments.polynomials)))
+    }
+
+    pub fn save_quotient_segments(
+        &self,
+        codewords: &Array2<XFieldElement>,
+        polynomials: &Array1<Polynomial<'static, XFieldElement>>,
+    ) -> io::Result<()> {
+        if self.directory.is_none() {
+            return Ok(());
+        }
+
+        let segments = QuotientSegments {
+            codewords: codewords.iter().copied().collect(),
+            polynomials: polynomials.to_vec(),
+        };
+        self.save(Stage::QuotientSegments, &segments)
+    }
+
+    /// The codeword of the given [FRI](crate::fri::Fri) round, if it can be
+    /// restored and has the expected length.
+    pub fn restore_fri_codeword(
+        &self,
+        round: usize,
+        codeword_len: usize,
+    ) -> Option<Vec<XFieldElement>> {
+        let codeword = self.restore::<Vec<XFieldElement>>(Stage::FriRound(round))?;
+        (codeword.len() == codeword_len).then_some(codeword)
+    }
+
+    pub fn save_fri_codeword(&self, round: usize, codeword: &[XFieldElement]) -> io::Result<()> {
+        if self.directory.is_none() {
+            return Ok(());
+        }
+
+        self.save(Stage::FriRound(round), &codeword.to_vec())
+    }
+
+    /// Delete all checkpoints of this proof. Checkpoints that cannot be deleted
+    /// are left behind.
+    pub fn clear(&self) {
+        for stage in Stage::all(self.num_fri_rounds) {
+            if let Some(path) = self.path(stage) {
+                fs::remove_file(path).ok();
+            }
+        }
+    }
+}
+
+/// Interpret the given bytes as [`BFieldElement`]s, each encoded as 8
+/// little-endian bytes.
+fn decode_bfes(bytes: &[u8]) -> Option<Vec<BFieldElement>> {
+    if bytes.len() % BFieldElement::BYTES != 0 {
+        return None;
+    }
+
+    bytes
+        .chunks_exact(BFieldElement::BYTES)
+        .map(BFieldElement::try_from)
+        .try_collect()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+
+    use super::*;
+    use crate::aet::AlgebraicExecutionTrace;
+    use crate::error::ProvingError;
+    use crate::prelude::*;
+    use crate::progress::CancellationToken;
+    use crate::progress::ProgressEvent;
+    use crate::progress::ProvingPhase;
+    use crate::stark::tests::master_main_table_for_low_security_level;
+    use crate::table::master_table::MasterTable;
+    use crate::test_utils::low_security_stark;
+    use crate::test_utils::ProgramAndInput;
+    use crate::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+
+    fn program_and_input() -> ProgramAndInput {
+        ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+            .with_input(bfe_vec![25])
+    }
+
+    fn claim_and_aet_of(program: ProgramAndInput) -> (Claim, AlgebraicExecutionTrace) {
+        let claim = program.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program.program,
+            program.public_input,
+            program.non_determinism,
+        )
+        .unwrap();
+
+        (claim, aet)
+    }
+
+    fn claim_and_aet() -> (Claim, AlgebraicExecutionTrace) {
+        claim_and_aet_of(program_and_input())
+    }
+
+    fn prover(seed: <StdRng as SeedableRng>::Seed) -> Prover {
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        Prover::new(stark).set_randomness_seed_which_may_break_zero_knowledge(seed)
+    }
+
+    /// Simulates a crash by cancelling the prover once it enters the given phase.
+    fn interrupt_proving_at(
+        phase: ProvingPhase,
+        seed: <StdRng as SeedableRng>::Seed,
+        directory: &Path,
+    ) {
+        interrupt_proving_of(claim_and_aet(), phase, seed, directory);
+    }
+
+    fn interrupt_proving_of(
+        (claim, aet): (Claim, AlgebraicExecutionTrace),
+        phase: ProvingPhase,
+        seed: <StdRng as SeedableRng>::Seed,
+        directory: &Path,
+    ) {
+        let token = CancellationToken::new();
+        let observer_token = token.clone();
+        let observer = move |event: ProgressEvent| {
+            if event.phase == phase {
+                observer_token.cancel();
+            }
+        };
+
+        let_assert!(
+            Err(ProvingError::Cancelled) = prover(seed)
+                .with_checkpoint_directory(directory)
+                .with_progress_observer(observer)
+                .with_cancellation_token(token)
+                .prove(&claim, &aet)
+        );
+    }
+
+    #[test]
+    fn resumed_proof_is_identical_to_uninterrupted_proof() {
+        let (claim, aet) = claim_and_aet();
+        let seed = [7; 32];
+        let proof = prover(seed).prove(&claim, &aet).unwrap();
+
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        let num_rounds = stark.fri(aet.padded_height()).unwrap().num_rounds();
+        let fri_rounds = (0..num_rounds).map(|round| ProvingPhase::FriRound { round, num_rounds });
+        let interruptions = [
+            ProvingPhase::MainTableMerkleTree,
+            ProvingPhase::AuxTable,
+            ProvingPhase::Quotient,
+            ProvingPhase::OutOfDomainRows,
+        ]
+        .into_iter()
+        .chain(fri_rounds)
+        .chain([ProvingPhase::OpenTraceRows]);
+
+        for phase in interruptions {
+            let directory = tempfile::tempdir().unwrap();
+            interrupt_proving_at(phase, seed, directory.path());
+            let resumed_proof = prover(seed)
+                .with_checkpoint_directory(directory.path())
+                .prove(&claim, &aet)
+                .unwrap();
+            assert!(proof == resumed_proof, "phase: {phase}");
+        }
+    }
+
+    #[test]
+    fn resuming_restores_all_checkpoints_saved_before_interruption() {
+        let seed = [8; 32];
+        let directory = tempfile::tempdir().unwrap();
+        interrupt_proving_at(ProvingPhase::OpenTraceRows, seed, directory.path());
+
+        let (stark, claim, mut main_table) =
+            master_main_table_for_low_security_level(program_and_input());
+        main_table.pad();
+        let num_fri_rounds = stark.fri(main_table.trace_table().nrows());
+        let num_fri_rounds = num_fri_rounds.unwrap().num_rounds();
+        let checkpoints = Checkpoints::new(
+            Some(directory.path()),
+            &claim,
+            stark,
+            seed,
+            main_table.trace_table(),
+            num_fri_rounds,
+        );
+        for stage in Stage::all(num_fri_rounds) {
+            assert!(checkpoints.is_restorable(stage), "stage: {stage:?}");
+        }
+    }
+
+    #[test]
+    fn checkpoints_of_different_proof_are_ignored() {
+        let (claim, aet) = claim_and_aet();
+        let directory = tempfile::tempdir().unwrap();
+        interrupt_proving_at(ProvingPhase::OpenTraceRows, [9; 32], directory.path());
+
+        let seed = [10; 32];
+        let proof = prover(seed).prove(&claim, &aet).unwrap();
+        let proof_with_foreign_checkpoints = prover(seed)
+            .with_checkpoint_directory(directory.path())
+            .prove(&claim, &aet)
+            .unwrap();
+        assert!(proof == proof_with_foreign_checkpoints);
+    }
+
+    #[test]
+    fn checkpoints_are_deleted_once_proof_is_done() {
+        let (claim, aet) = claim_and_aet();
+        let directory = tempfile::tempdir().unwrap();
+        prover([11; 32])
+            .with_checkpoint_directory(directory.path())
+            .prove(&claim, &aet)
+            .unwrap();
+
+        let num_remaining_files = fs::read_dir(directory.path()).unwrap().count();
+        assert!(0 == num_remaining_files);
+    }
+
+    #[test]
+    fn corrupted_checkpoint_is_recomputed() {
+        let (claim, aet) = claim_and_aet();
+        let seed = [12; 32];
+        let proof = prover(seed).prove(&claim, &aet).unwrap();
+
+        let directory = tempfile::tempdir().unwrap();
+        interrupt_proving_at(ProvingPhase::OutOfDomainRows, seed, directory.path());
+        let quotient_path = directory.path().join(Stage::QuotientSegments.file_name());
+        let mut bytes = fs::read(&quotient_path).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        fs::write(&quotient_path, bytes).unwrap();
+
+        let resumed_proof = prover(seed)
+            .with_checkpoint_directory(directory.path())
+            .prove(&claim, &aet)
+            .unwrap();
+        assert!(proof == resumed_proof);
+    }
+
+    #[test]
+    fn restoring_from_disabled_checkpoints_gives_nothing() {
+        let checkpoints = Checkpoints::default();
+        let merkle_tree = MerkleTree::new::<CpuParallel>(&[Digest::default(); 4]).unwrap();
+        checkpoints
+            .save_merkle_tree(Stage::MainMerkleTree, &merkle_tree)
+            .unwrap();
+        assert!(!checkpoints.is_restorable(Stage::MainMerkleTree));
+        assert!(checkpoints
+            .restore_merkle_tree(Stage::MainMerkleTree, 4)
+            .is_none());
+    }
+
+    #[test]
+    fn checkpoints_of_run_with_different_secret_input_are_ignored() {
+        let program = triton_program!(divine 1 pop 1 halt);
+        let run = |secret| {
+            let program = ProgramAndInput::new(program.clone());
+            claim_and_aet_of(program.with_non_determinism(bfe_vec![secret]))
+        };
+
+        let directory = tempfile::tempdir().unwrap();
+        let seed = [11; 32];
+        interrupt_proving_of(run(0), ProvingPhase::OpenTraceRows, seed, directory.path());
+
+        let (claim, aet) = run(1);
+        assert!(claim == run(0).0);
+        let proof = prover(seed).prove(&claim, &aet).unwrap();
+        let proof_with_foreign_checkpoints = prover(seed)
+            .with_checkpoint_directory(directory.path())
+            .prove(&claim, &aet)
+            .unwrap();
+        assert!(proof == proof_with_foreign_checkpoints);
+    }
+}
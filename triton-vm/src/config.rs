@@ -14,12 +14,20 @@
 //! via the following environment variables:
 //!
 //! - `TVM_LDE_TRACE`: Set to `cache` to cache the low-degree extended trace.
-//!   Set to `no_cache` to not cache it. If unset (or set to anything else),
+//!   Set to `no_cache` to not cache it. Set to `cache_on_disk` to cache it in
+//!   memory-mapped temporary files. If unset (or set to anything else),
 //!   Triton VM will make an automatic decision based on free memory.
+//! - `TVM_MEMORY_BUDGET`: The maximum number of bytes to use for proving. Units
+//!   `k`, `M`, `G`, and `T` stand for powers of 1024, for example, `64G` or
+//!   `64GiB`. If unset (or unparsable), there is no budget. See also
+//!   [`overwrite_memory_budget_to`].
 
 use std::cell::RefCell;
 
 use arbitrary::Arbitrary;
+use rayon::ThreadPool;
+
+use crate::profiler::PROFILER;
 
 thread_local! {
     pub(crate) static CONFIG: RefCell<Config> = RefCell::new(Config::default());
@@ -27,9 +35,25 @@ thread_local! {
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Arbitrary)]
 pub enum CacheDecision {
+    /// Keep the low-degree extended trace in RAM. Fastest, but needs the most
+    /// memory.
     #[default]
     Cache,
+
+    /// Recompute the low-degree extended trace whenever it is needed. Slowest, but
+    /// needs the least memory.
     NoCache,
+
+    /// Keep the low-degree extended trace in memory-mapped temporary files. The
+    /// operating system moves the data between RAM and disk as needed. This is
+    /// usually slower than [`Cache`](Self::Cache) but faster than
+    /// [`NoCache`](Self::NoCache), and suits very long traces on machines with
+    /// fast disks but limited RAM.
+    ///
+    /// The files are created in the directory given by the environment variable
+    /// `TMPDIR`, or the operating system's default temporary directory. If they
+    /// cannot be created, the trace is not cached.
+    CacheOnDisk,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Arbitrary)]
@@ -41,6 +65,12 @@ struct Config {
     /// [lde]: crate::table::master_table::MasterTable::maybe_low_degree_extend_all_columns
     /// [proving]: crate::stark::Stark::prove
     pub cache_lde_trace_overwrite: Option<CacheDecision>,
+
+    /// The maximum number of bytes to use for [proving], if any.
+    /// Can be accessed via [`memory_budget`].
+    ///
+    /// [proving]: crate::stark::Stark::prove
+    pub memory_budget: Option<usize>,
 }
 
 impl Config {
@@ -49,15 +79,47 @@ impl Config {
         let cache_lde_trace_overwrite = match maybe_overwrite {
             Ok(t) if &t == "cache" => Some(CacheDecision::Cache),
             Ok(f) if &f == "no_cache" => Some(CacheDecision::NoCache),
+            Ok(d) if &d == "cache_on_disk" => Some(CacheDecision::CacheOnDisk),
             _ => None,
         };
+        let memory_budget = std::env::var("TVM_MEMORY_BUDGET")
+            .ok()
+            .and_then(|budget| parse_num_bytes(&budget));
 
         Self {
             cache_lde_trace_overwrite,
+            memory_budget,
         }
     }
 }
 
+/// Parse a number of bytes, optionally followed by a unit like `k`, `M`, `GiB`,
+/// or `TB`. All units are powers of 1024.
+fn parse_num_bytes(num_bytes: &str) -> Option<usize> {
+    let num_bytes = num_bytes.trim().to_ascii_lowercase();
+    let num_bytes = num_bytes
+        .strip_suffix("ib")
+        .or_else(|| num_bytes.strip_suffix('b'))
+        .unwrap_or(&num_bytes);
+    let unit_start = num_bytes
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(num_bytes.len());
+    let (number, unit) = num_bytes.split_at(unit_start);
+    let exponent = match unit.trim() {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => return None,
+    };
+
+    number
+        .parse::<usize>()
+        .ok()?
+        .checked_mul(1_usize << (10 * exponent))
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::new()
@@ -65,7 +127,8 @@ impl Default for Config {
 }
 
 /// Overwrite the automatic decision whether to cache the [low-degree extended trace][lde] when
-/// [proving]. Takes precedence over the environment variable `TVM_LDE_TRACE`.
+/// [proving]. Takes precedence over the environment variable `TVM_LDE_TRACE`. Applies to all
+/// proofs generated from the calling thread, including those using a [dedicated thread pool][pool].
 ///
 /// Caching the low-degree extended trace improves proving speed but requires more memory. It is
 /// generally recommended to cache the trace. Triton VM will make an automatic decision based on
@@ -73,6 +136,7 @@ impl Default for Config {
 ///
 /// [lde]: crate::table::master_table::MasterTable::maybe_low_degree_extend_all_columns
 /// [proving]: crate::stark::Stark::prove
+/// [pool]: crate::stark::Prover::with_thread_pool
 pub fn overwrite_lde_trace_caching_to(decision: CacheDecision) {
     CONFIG.with_borrow_mut(|config| config.cache_lde_trace_overwrite = Some(decision));
 }
@@ -85,13 +149,65 @@ pub(crate) fn cache_lde_trace() -> Option<CacheDecision> {
     CONFIG.with_borrow(|config| config.cache_lde_trace_overwrite)
 }
 
+/// Limit the memory used for [proving] to the given number of bytes. Takes precedence over the
+/// environment variable `TVM_MEMORY_BUDGET`. Applies to all proofs generated from the calling
+/// thread.
+///
+/// Within the budget, the prover decides whether to cache the [low-degree extended trace][lde],
+/// how many rows to process at once when computing the quotients just in time, and whether to keep
+/// the codewords of all [FRI](crate::fri::Fri) rounds. An [overwritten caching
+/// decision][overwrite] takes precedence over the budget. If proving needs more memory than the
+/// budget allows, proving fails early with [`MemoryBudgetExceeded`][err].
+///
+/// The budget is compared to estimates of the prover's largest allocations, not to its actual
+/// memory usage. It excludes the memory for the [algebraic execution trace][aet].
+///
+/// [proving]: crate::stark::Stark::prove
+/// [lde]: crate::table::master_table::MasterTable::maybe_low_degree_extend_all_columns
+/// [overwrite]: overwrite_lde_trace_caching_to
+/// [err]: crate::error::ProvingError::MemoryBudgetExceeded
+/// [aet]: crate::aet::AlgebraicExecutionTrace
+pub fn overwrite_memory_budget_to(num_bytes: usize) {
+    CONFIG.with_borrow_mut(|config| config.memory_budget = Some(num_bytes));
+}
+
+/// The maximum number of bytes to use for proving, if any.
+pub(crate) fn memory_budget() -> Option<usize> {
+    CONFIG.with_borrow(|config| config.memory_budget)
+}
+
+/// Run the given function in the given thread pool, with the configuration and
+/// the [profiler](crate::profiler) of the calling thread. Whatever the function
+/// profiles is recorded by the calling thread's profiler. The configuration and
+/// profiler of the thread pool's threads are restored afterwards.
+pub(crate) fn install<F, R>(thread_pool: &ThreadPool, f: F) -> R
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    let config = CONFIG.with_borrow(|config| *config);
+    let profiler = PROFILER.take();
+    let (result, profiler) = thread_pool.install(|| {
+        let pool_config = CONFIG.replace(config);
+        let pool_profiler = PROFILER.replace(profiler);
+        let result = f();
+        CONFIG.set(pool_config);
+        (result, PROFILER.replace(pool_profiler))
+    });
+    PROFILER.set(profiler);
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::error::ProvingError;
     use crate::example_programs::FIBONACCI_SEQUENCE;
     use crate::prelude::*;
-    use crate::shared_tests::prove_and_verify;
-    use crate::shared_tests::ProgramAndInput;
-    use crate::shared_tests::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+    use crate::test_utils::low_security_stark;
+    use crate::test_utils::prove_and_verify;
+    use crate::test_utils::ProgramAndInput;
+    use crate::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
 
     use super::*;
 
@@ -107,6 +223,88 @@ mod tests {
         prove_and_verify_a_triton_vm_program();
     }
 
+    #[test]
+    fn triton_vm_can_generate_valid_proof_with_lde_trace_cached_on_disk() {
+        overwrite_lde_trace_caching_to(CacheDecision::CacheOnDisk);
+        prove_and_verify_a_triton_vm_program();
+    }
+
+    #[test]
+    fn num_bytes_can_be_parsed() {
+        assert_eq!(Some(1234), parse_num_bytes("1234"));
+        assert_eq!(Some(1234), parse_num_bytes(" 1234B "));
+        assert_eq!(Some(3 << 10), parse_num_bytes("3k"));
+        assert_eq!(Some(5 << 20), parse_num_bytes("5 MiB"));
+        assert_eq!(Some(64 << 30), parse_num_bytes("64G"));
+        assert_eq!(Some(64 << 30), parse_num_bytes("64gb"));
+        assert_eq!(Some(2 << 40), parse_num_bytes("2TiB"));
+        assert_eq!(None, parse_num_bytes(""));
+        assert_eq!(None, parse_num_bytes("G"));
+        assert_eq!(None, parse_num_bytes("-3G"));
+        assert_eq!(None, parse_num_bytes("3 lots"));
+        assert_eq!(None, parse_num_bytes(&format!("{}T", usize::MAX)));
+    }
+
+    #[test]
+    fn configuration_of_calling_thread_applies_in_thread_pool() {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+
+        overwrite_lde_trace_caching_to(CacheDecision::NoCache);
+        let decision = install(&thread_pool, cache_lde_trace);
+        assert_eq!(Some(CacheDecision::NoCache), decision);
+
+        overwrite_lde_trace_caching_to(CacheDecision::Cache);
+        let decision = install(&thread_pool, cache_lde_trace);
+        assert_eq!(Some(CacheDecision::Cache), decision);
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, not(feature = "no_profile")))]
+    fn profiler_of_calling_thread_records_work_in_thread_pool() {
+        use crate::profiler::profiler;
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+
+        crate::profiler::start("thread pool");
+        install(&thread_pool, || profiler!(start "in thread pool"));
+        install(&thread_pool, || profiler!(stop "in thread pool"));
+        let profile = crate::profiler::finish().to_string();
+        assert!(profile.contains("in thread pool"), "{profile}");
+    }
+
+    #[test]
+    fn proving_within_tight_memory_budget_produces_same_proof() {
+        let program = ProgramAndInput::new(FIBONACCI_SEQUENCE.clone()).with_input(bfe_vec![100]);
+        let claim = program.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program.program,
+            program.public_input,
+            program.non_determinism,
+        )
+        .unwrap();
+
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        let prover =
+            || Prover::new(stark).set_randomness_seed_which_may_break_zero_knowledge([3; 32]);
+        let proof = prover().prove(&claim, &aet).unwrap();
+
+        overwrite_memory_budget_to(0);
+        let Err(ProvingError::MemoryBudgetExceeded { required, .. }) = prover().prove(&claim, &aet)
+        else {
+            panic!("proving must fail if the memory budget is exceeded");
+        };
+
+        overwrite_memory_budget_to(required);
+        let frugal_proof = prover().prove(&claim, &aet).unwrap();
+        assert_eq!(proof, frugal_proof);
+    }
+
     fn prove_and_verify_a_triton_vm_program() {
         let program_and_input = ProgramAndInput::new(FIBONACCI_SEQUENCE.clone())
             .with_input(PublicInput::from(bfe_array![100]));
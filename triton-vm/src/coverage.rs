@@ -0,0 +1,199 @@
+//! Coverage reports for [Triton](crate) programs: which instructions an
+//! execution reaches, and how often. Together with the
+//! [source locations](isa::program::Program::source_location) of a program
+//! [parsed from source code](isa::program::Program::from_code), a report points
+//! at the lines of source code that were never executed.
+//!
+//! Use [`VM::coverage`](crate::vm::VM::coverage) to generate a report.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::fmt::Write;
+
+use isa::instruction::Instruction;
+use isa::program::Program;
+use isa::program::SourceLocation;
+
+/// A report of how often each instruction of a program was executed.
+///
+/// Offers a human-readable [`Display`] implementation, listing all instructions
+/// that were never executed, and can be exported to the widely supported
+/// [LCOV](Self::to_lcov) format.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ExecutionCoverage {
+    /// One line per instruction of the program, in order of their addresses.
+    pub lines: Vec<CoverageLine>,
+}
+
+/// The coverage of a single instruction in an [`ExecutionCoverage`] report.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct CoverageLine {
+    /// The address of the instruction in the program.
+    pub address: u64,
+    pub instruction: Instruction,
+
+    /// The location of the instruction in the source code, if known.
+    pub source_location: Option<SourceLocation>,
+
+    /// The number of times the instruction was executed.
+    pub execution_count: u64,
+}
+
+impl ExecutionCoverage {
+    /// The coverage of the given program, where `execution_counts` holds the
+    /// number of executions for every address of the program.
+    pub(crate) fn new(program: &Program, execution_counts: &[u64]) -> Self {
+        let mut lines = vec![];
+        let mut address = 0;
+        for instruction in program.clone() {
+            lines.push(CoverageLine {
+                address,
+                instruction,
+                source_location: program.source_location(address),
+                execution_count: execution_counts[address as usize],
+            });
+            address += instruction.size() as u64;
+        }
+
+        Self { lines }
+    }
+
+    /// The instructions that were executed at least once.
+    pub fn covered(&self) -> impl Iterator<Item = &CoverageLine> {
+        self.lines.iter().filter(|line| line.execution_count > 0)
+    }
+
+    /// The instructions that were never executed.
+    pub fn uncovered(&self) -> impl Iterator<Item = &CoverageLine> {
+        self.lines.iter().filter(|line| line.execution_count == 0)
+    }
+
+    /// The fraction of instructions that were executed at least once, between 0
+    /// and 1. A program without instructions is fully covered.
+    pub fn fraction_covered(&self) -> f64 {
+        if self.lines.is_empty() {
+            return 1.0;
+        }
+        self.covered().count() as f64 / self.lines.len() as f64
+    }
+
+    /// The report in the [LCOV] tracefile format, as understood by tools like
+    /// `genhtml` and most code editors.
+    ///
+    /// A line of source code counts as executed as often as the most frequently
+    /// executed instruction on it. Instructions without a known source location
+    /// are omitted. Instructions from source code of an unknown file are attributed
+    /// to file `<unknown>`.
+    ///
+    /// [LCOV]: https://github.com/linux-test-project/lcov
+    pub fn to_lcov(&self) -> String {
+        let mut files = BTreeMap::<_, BTreeMap<_, _>>::new();
+        for line in &self.lines {
+            let Some(location) = &line.source_location else {
+                continue;
+            };
+            let file = location.file.as_deref().unwrap_or("<unknown>");
+            let execution_count = files.entry(file).or_default().entry(location.line);
+            let execution_count = execution_count.or_default();
+            *execution_count = line.execution_count.max(*execution_count);
+        }
+
+        let mut lcov = String::new();
+        for (file, execution_counts) in files {
+            let num_lines = execution_counts.len();
+            let num_hit_lines = execution_counts.values().filter(|&&c| c > 0).count();
+
+            writeln!(lcov, "SF:{file}").unwrap();
+            for (line, execution_count) in execution_counts {
+                writeln!(lcov, "DA:{line},{execution_count}").unwrap();
+            }
+            writeln!(lcov, "LF:{num_lines}").unwrap();
+            writeln!(lcov, "LH:{num_hit_lines}").unwrap();
+            writeln!(lcov, "end_of_record").unwrap();
+        }
+
+        lcov
+    }
+}
+
+impl Display for CoverageLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let address = self.address;
+        let instruction = self.instruction;
+        write!(f, "{address:>6}: {instruction}")?;
+        if let Some(location) = &self.source_location {
+            write!(f, " ({location})")?;
+        }
+        write!(f, " – executed {} times", self.execution_count)
+    }
+}
+
+impl Display for ExecutionCoverage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let num_covered = self.covered().count();
+        let num_instructions = self.lines.len();
+        let percentage = 100.0 * self.fraction_covered();
+        write!(
+            f,
+            "covered {num_covered} of {num_instructions} instructions ({percentage:.1}%)"
+        )?;
+
+        let mut uncovered = self.uncovered().peekable();
+        if uncovered.peek().is_some() {
+            write!(f, "\nnever executed:")?;
+        }
+        for line in uncovered {
+            write!(f, "\n{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+    use isa::program::Program;
+    use isa::triton_program;
+
+    use crate::vm::VM;
+
+    #[test]
+    fn coverage_counts_executions_of_every_instruction() {
+        let program = triton_program! {
+            push 3 call loop halt
+            loop: dup 0 push 0 eq skiz return addi -1 recurse
+            unused: push 42 return
+        };
+        let_assert!(Ok((_, coverage)) = VM::coverage(program, [].into(), [].into()));
+
+        let counts = coverage.lines.iter().map(|line| line.execution_count);
+        let expected_counts = [1, 1, 1, 4, 4, 4, 4, 1, 3, 3, 0, 0];
+        assert!(expected_counts.to_vec() == counts.collect::<Vec<_>>());
+
+        let uncovered = coverage.uncovered().map(|line| line.address);
+        assert!(vec![15, 17] == uncovered.collect::<Vec<_>>());
+        assert!(10.0 / 12.0 == coverage.fraction_covered());
+    }
+
+    #[test]
+    fn coverage_report_points_to_unexecuted_source_lines() {
+        let source_code = "push 0\nskiz\n  call foo\nhalt\nfoo:\n  return";
+        let program = Program::from_code(source_code)
+            .unwrap()
+            .with_source_file("main.tasm");
+        let_assert!(Ok((_, coverage)) = VM::coverage(program, [].into(), [].into()));
+
+        let report = coverage.to_string();
+        assert!(report.starts_with("covered 3 of 5 instructions (60.0%)"));
+        assert!(report.contains("(main.tasm:3:3)"));
+        assert!(report.contains("return (main.tasm:6:3)"));
+
+        let expected_lcov = "SF:main.tasm\nDA:1,1\nDA:2,1\nDA:3,0\nDA:4,1\nDA:6,0\n\
+            LF:5\nLH:3\nend_of_record\n";
+        assert!(expected_lcov == coverage.to_lcov());
+    }
+}
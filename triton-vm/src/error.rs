@@ -9,6 +9,7 @@ use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use isa::program::SourceLocation;
 use thiserror::Error;
 use twenty_first::error::MerkleTreeError;
 use twenty_first::prelude::*;
@@ -33,11 +34,21 @@ impl VMError {
         let vm_state = Box::new(vm_state);
         Self { source, vm_state }
     }
+
+    /// The location in the source code of the instruction that caused the crash,
+    /// if known. See also [`Program::source_location`](isa::program::Program::source_location).
+    pub fn source_location(&self) -> Option<SourceLocation> {
+        let instruction_pointer = self.vm_state.instruction_pointer.try_into().ok()?;
+        self.vm_state.program.source_location(instruction_pointer)
+    }
 }
 
 impl Display for VMError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "VM error: {}", self.source)?;
+        if let Some(location) = self.source_location() {
+            writeln!(f, "at {location}")?;
+        }
         writeln!(f, "VM state:")?;
         writeln!(f, "{}", self.vm_state)
     }
@@ -87,6 +98,9 @@ pub enum FriSetupError {
     #[error("the expansion factor must be smaller than the domain length")]
     ExpansionFactorMismatch,
 
+    #[error("the folding factor must be a power of 2 greater than 1")]
+    FoldingFactorUnsupported,
+
     #[error(transparent)]
     ArithmeticDomainError(#[from] ArithmeticDomainError),
 }
@@ -99,6 +113,12 @@ pub enum FriProvingError {
 
     #[error(transparent)]
     ArithmeticDomainError(#[from] ArithmeticDomainError),
+
+    #[error("proving was cancelled")]
+    Cancelled,
+
+    #[error("failed to write checkpoint: {0}")]
+    CheckpointError(std::io::ErrorKind),
 }
 
 #[non_exhaustive]
@@ -122,6 +142,9 @@ pub enum FriValidationError {
     #[error("received codeword of last round does not correspond to its commitment")]
     BadMerkleRootForLastCodeword,
 
+    #[error("proof of work is insufficient")]
+    InsufficientProofOfWork,
+
     #[error(transparent)]
     ProofStreamError(#[from] ProofStreamError),
 
@@ -161,6 +184,18 @@ pub enum ProvingError {
 
     #[error(transparent)]
     VMError(#[from] VMError),
+
+    #[error("proving was cancelled")]
+    Cancelled,
+
+    #[error("failed to create thread pool for proving: {0}")]
+    ThreadPoolCreationError(String),
+
+    #[error("proving needs at least {required} bytes, but the memory budget is {budget} bytes")]
+    MemoryBudgetExceeded { budget: usize, required: usize },
+
+    #[error("failed to write checkpoint: {0}")]
+    CheckpointError(std::io::ErrorKind),
 }
 
 #[non_exhaustive]
@@ -196,6 +231,9 @@ pub enum VerificationError {
     #[error("the number of received auxiliary table rows does not match the parameters")]
     IncorrectNumberOfAuxTableRows,
 
+    #[error("the verifier is prepared for padded height {expected}, but the proof has {actual}")]
+    PaddedHeightMismatch { expected: usize, actual: usize },
+
     #[error(transparent)]
     ProofStreamError(#[from] ProofStreamError),
 
@@ -209,6 +247,35 @@ pub enum VerificationError {
     FriValidationError(#[from] FriValidationError),
 }
 
+/// Indicates that at least one proof of a
+/// [batch](crate::stark::Verifier::verify_batch) failed verification.
+#[derive(Debug, Error)]
+#[error("{} of {num_proofs} proofs failed verification", .failures.len())]
+pub struct BatchVerificationError {
+    /// The number of proofs in the batch.
+    pub num_proofs: usize,
+
+    /// The index of every proof that failed verification, in ascending order,
+    /// along with the reason.
+    pub failures: Vec<(usize, VerificationError)>,
+}
+
+/// Indicates that a [snippet test](crate::snippet_test::SnippetTest) failed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum SnippetTestError {
+    #[error("snippet halted without returning")]
+    MissingReturn,
+
+    #[error("snippet crashed: {0}")]
+    Crash(#[from] VMError),
+
+    /// The final state of the snippet's execution differs from the expectation.
+    /// Contains a human-readable diff.
+    #[error("snippet's final state does not match expectation\n{0}")]
+    Mismatch(String),
+}
+
 #[cfg(test)]
 mod tests {
     use assert2::assert;
@@ -8,6 +8,7 @@ use std::ops::Sub;
 
 use air::table::hash::PERMUTATION_TRACE_LENGTH;
 use arbitrary::Arbitrary;
+use isa::program::SourceLocation;
 use twenty_first::prelude::*;
 
 use crate::table::u32::U32TableEntry;
@@ -33,6 +34,7 @@ pub struct ProfileLine {
     pub table_heights_start: VMTableHeights,
 
     table_heights_stop: VMTableHeights,
+    source_location: Option<SourceLocation>,
 }
 
 /// A report for the completed execution of a [Triton](crate) program.
@@ -68,6 +70,20 @@ impl ExecutionTraceProfiler {
     }
 
     pub fn enter_span(&mut self, label: impl Into<String>) {
+        self.enter_span_with_source_location(label, None);
+    }
+
+    /// Like [`enter_span`](Self::enter_span), but also records where in the
+    /// source code the span starts.
+    pub fn enter_span_at(&mut self, label: impl Into<String>, source_location: SourceLocation) {
+        self.enter_span_with_source_location(label, Some(source_location));
+    }
+
+    fn enter_span_with_source_location(
+        &mut self,
+        label: impl Into<String>,
+        source_location: Option<SourceLocation>,
+    ) {
         let call_stack_len = self.call_stack.len();
         let line_number = self.profile.len();
 
@@ -76,6 +92,7 @@ impl ExecutionTraceProfiler {
             call_depth: call_stack_len,
             table_heights_start: self.table_heights,
             table_heights_stop: VMTableHeights::default(),
+            source_location,
         };
 
         self.profile.push(profile_line);
@@ -167,6 +184,12 @@ impl AddAssign<Self> for VMTableHeights {
 }
 
 impl ProfileLine {
+    /// The location in the source code of the called subroutine, if known. See
+    /// also [`Program::source_location`](isa::program::Program::source_location).
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
+
     fn table_height_contributions(&self) -> VMTableHeights {
         self.table_heights_stop - self.table_heights_start
     }
@@ -177,7 +200,11 @@ impl Display for ProfileLine {
         let indentation = "  ".repeat(self.call_depth);
         let label = &self.label;
         let cycle_count = self.table_height_contributions().processor;
-        write!(f, "{indentation}{label}: {cycle_count}")
+        write!(f, "{indentation}{label}")?;
+        if let Some(location) = &self.source_location {
+            write!(f, " ({location})")?;
+        }
+        write!(f, ": {cycle_count}")
     }
 }
 
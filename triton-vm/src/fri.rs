@@ -6,10 +6,14 @@ use twenty_first::math::traits::FiniteField;
 use twenty_first::prelude::*;
 
 use crate::arithmetic_domain::ArithmeticDomain;
+use crate::checkpoint::Checkpoints;
 use crate::error::FriProvingError;
 use crate::error::FriSetupError;
 use crate::error::FriValidationError;
 use crate::profiler::profiler;
+use crate::progress::CancellationToken;
+use crate::progress::ProgressMonitor;
+use crate::progress::ProvingPhase;
 use crate::proof_item::FriResponse;
 use crate::proof_item::ProofItem;
 use crate::proof_stream::ProofStream;
@@ -25,6 +29,18 @@ pub struct Fri {
     pub expansion_factor: usize,
     pub num_collinearity_checks: usize,
     pub domain: ArithmeticDomain,
+
+    /// The factor by which every round shrinks the codeword, and the degree of
+    /// the polynomial it corresponds to. Larger folding factors lead to fewer
+    /// rounds, but every round reveals more codeword elements per collinearity
+    /// check. Always a power of 2.
+    pub folding_factor: usize,
+
+    /// The number of leading zeros the prover has to [grind][grind] for before
+    /// the collinearity checks are sampled. Zero disables grinding.
+    ///
+    /// [grind]: ProofStream::grind
+    pub num_grinding_bits: usize,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -33,8 +49,13 @@ struct FriProver<'stream> {
     rounds: Vec<ProverRound>,
     first_round_domain: ArithmeticDomain,
     num_rounds: usize,
+    folding_factor: usize,
     num_collinearity_checks: usize,
+    num_grinding_bits: usize,
     first_round_collinearity_check_indices: Vec<usize>,
+    progress_monitor: &'stream ProgressMonitor,
+    keep_round_codewords: bool,
+    checkpoints: &'stream Checkpoints,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -47,7 +68,11 @@ struct ProverRound {
 impl FriProver<'_> {
     fn commit(&mut self, codeword: &[XFieldElement]) -> ProverResult<()> {
         self.commit_to_first_round(codeword)?;
-        for _ in 0..self.num_rounds {
+        for round in 0..self.num_rounds {
+            let num_rounds = self.num_rounds;
+            self.progress_monitor
+                .enter(ProvingPhase::FriRound { round, num_rounds })
+                .map_err(|_| FriProvingError::Cancelled)?;
             self.commit_to_next_round()?;
         }
         self.send_last_codeword();
@@ -64,6 +89,9 @@ impl FriProver<'_> {
 
     fn commit_to_next_round(&mut self) -> ProverResult<()> {
         let next_round = self.construct_next_round()?;
+        if !self.keep_round_codewords {
+            self.rounds.last_mut().unwrap().forget_codeword();
+        }
         self.commit_to_round(&next_round);
         self.store_round(next_round);
         Ok(())
@@ -82,8 +110,21 @@ impl FriProver<'_> {
     fn construct_next_round(&mut self) -> ProverResult<ProverRound> {
         let previous_round = self.rounds.last().unwrap();
         let folding_challenge = self.proof_stream.sample_scalars(1)[0];
-        let codeword = previous_round.split_and_fold(folding_challenge);
-        let domain = previous_round.domain.halve()?;
+        let domain = previous_round.domain.fold(self.folding_factor)?;
+
+        let round = self.rounds.len();
+        let restored_codeword = self.checkpoints.restore_fri_codeword(round, domain.length);
+        let codeword = if let Some(codeword) = restored_codeword {
+            codeword
+        } else {
+            let cancellation_token = self.progress_monitor.cancellation_token();
+            let codeword =
+                previous_round.fold(folding_challenge, self.folding_factor, &cancellation_token)?;
+            self.checkpoints
+                .save_fri_codeword(round, &codeword)
+                .map_err(|err| FriProvingError::CheckpointError(err.kind()))?;
+            codeword
+        };
         ProverRound::new(domain, &codeword)
     }
 
@@ -103,6 +144,11 @@ impl FriProver<'_> {
     }
 
     fn query(&mut self) -> ProverResult<()> {
+        if self.num_grinding_bits > 0 {
+            profiler!(start "grinding");
+            self.proof_stream.grind(self.num_grinding_bits);
+            profiler!(stop "grinding");
+        }
         self.sample_first_round_collinearity_check_indices();
 
         let initial_a_indices = self.first_round_collinearity_check_indices.clone();
@@ -110,8 +156,8 @@ impl FriProver<'_> {
 
         let num_rounds_that_have_a_next_round = self.rounds.len() - 1;
         for round_number in 0..num_rounds_that_have_a_next_round {
-            let b_indices = self.collinearity_check_b_indices_for_round(round_number);
-            self.authentically_reveal_codeword_of_round_at_indices(round_number, &b_indices)?;
+            let sibling_indices = self.sibling_indices_for_round(round_number);
+            self.authentically_reveal_codeword_of_round_at_indices(round_number, &sibling_indices)?;
         }
 
         Ok(())
@@ -124,11 +170,11 @@ impl FriProver<'_> {
             .sample_indices(indices_upper_bound, self.num_collinearity_checks);
     }
 
-    fn collinearity_check_b_indices_for_round(&self, round_number: usize) -> Vec<usize> {
+    fn sibling_indices_for_round(&self, round_number: usize) -> Vec<usize> {
         let domain_length = self.rounds[round_number].domain.length;
         self.first_round_collinearity_check_indices
             .iter()
-            .map(|&a_index| (a_index + domain_length / 2) % domain_length)
+            .flat_map(|&a_index| sibling_indices(a_index, domain_length, self.folding_factor))
             .collect()
     }
 
@@ -137,10 +183,10 @@ impl FriProver<'_> {
         round_number: usize,
         indices: &[usize],
     ) -> ProverResult<()> {
-        let codeword = &self.rounds[round_number].codeword;
-        let revealed_leaves = indices.iter().map(|&i| codeword[i]).collect_vec();
+        let round = &self.rounds[round_number];
+        let revealed_leaves = indices.iter().map(|&i| round.codeword_element(i)).collect();
 
-        let merkle_tree = &self.rounds[round_number].merkle_tree;
+        let merkle_tree = &round.merkle_tree;
         let auth_structure = merkle_tree.authentication_structure(indices)?;
 
         let fri_response = FriResponse {
@@ -170,24 +216,133 @@ impl ProverRound {
         MerkleTree::new::<CpuParallel>(&digests).map_err(FriProvingError::MerkleTreeError)
     }
 
-    fn split_and_fold(&self, folding_challenge: XFieldElement) -> Vec<XFieldElement> {
-        let one = xfe!(1);
-        let two_inverse = xfe!(2).inverse();
+    /// Free the memory of the codeword. Its elements can still be
+    /// [read](Self::codeword_element) from the Merkle tree's leafs.
+    fn forget_codeword(&mut self) {
+        self.codeword = vec![];
+    }
 
-        let domain_points = self.domain.domain_values();
-        let domain_point_inverses = BFieldElement::batch_inversion(domain_points);
+    fn codeword_element(&self, index: usize) -> XFieldElement {
+        if !self.codeword.is_empty() {
+            return self.codeword[index];
+        }
 
-        let n = self.codeword.len();
-        (0..n / 2)
-            .into_par_iter()
+        let leaf = self.merkle_tree.leaf(index).unwrap();
+        XFieldElement::try_from(leaf).expect("leafs are digests of codeword elements")
+    }
+
+    /// Fold the codeword by the given factor, a power of 2. Folding by 2ⁿ using
+    /// challenge α is the same as [splitting and folding](split_and_fold) n times,
+    /// using challenges α, α², α⁴, …
+    ///
+    /// Stops early with [`FriProvingError::Cancelled`] if the given token is
+    /// cancelled.
+    fn fold(
+        &self,
+        folding_challenge: XFieldElement,
+        folding_factor: usize,
+        cancellation_token: &CancellationToken,
+    ) -> ProverResult<Vec<XFieldElement>> {
+        let mut domain = self.domain;
+        let mut folding_challenge = folding_challenge;
+        let mut codeword = split_and_fold(
+            domain,
+            &self.codeword,
+            folding_challenge,
+            cancellation_token,
+        )?;
+        for _ in 1..folding_factor.ilog2() {
+            domain = domain.halve()?;
+            folding_challenge = folding_challenge.square();
+            codeword = split_and_fold(domain, &codeword, folding_challenge, cancellation_token)?;
+        }
+        Ok(codeword)
+    }
+}
+
+fn split_and_fold(
+    domain: ArithmeticDomain,
+    codeword: &[XFieldElement],
+    folding_challenge: XFieldElement,
+    cancellation_token: &CancellationToken,
+) -> ProverResult<Vec<XFieldElement>> {
+    let one = xfe!(1);
+    let two_inverse = xfe!(2).inverse();
+
+    let domain_points = domain.domain_values();
+    let domain_point_inverses = BFieldElement::batch_inversion(domain_points);
+
+    let n = codeword.len();
+    (0..n / 2)
+        .into_par_iter()
+        .map(|i| {
+            if cancellation_token.is_cancelled() {
+                return Err(FriProvingError::Cancelled);
+            }
+            let scaled_offset_inv = folding_challenge * domain_point_inverses[i];
+            let left_summand = (one + scaled_offset_inv) * codeword[i];
+            let right_summand = (one - scaled_offset_inv) * codeword[n / 2 + i];
+            Ok((left_summand + right_summand) * two_inverse)
+        })
+        .collect()
+}
+
+/// The indices of those elements of a codeword that are [folded](ProverRound::fold)
+/// into the same element of the next round's codeword as the element at the given
+/// index. The index may exceed the domain length, in which case it wraps around.
+fn sibling_indices(
+    index: usize,
+    domain_length: usize,
+    folding_factor: usize,
+) -> impl Iterator<Item = usize> {
+    let stride = domain_length / folding_factor;
+    (1..folding_factor).map(move |i| (index + i * stride) % domain_length)
+}
+
+/// Fold the codeword element at the given index together with its
+/// [siblings](sibling_indices) into one element of the next round's codeword.
+/// The result agrees with [folding](ProverRound::fold) the entire codeword.
+fn fold_element_and_siblings(
+    domain: ArithmeticDomain,
+    index: usize,
+    element: XFieldElement,
+    siblings: &[XFieldElement],
+    folding_challenge: XFieldElement,
+) -> XFieldElement {
+    let folding_factor = siblings.len() + 1;
+    let stride = domain.length / folding_factor;
+    let index = index % domain.length;
+
+    // order the elements by their index in the domain
+    let position = index / stride;
+    let mut elements = vec![element; folding_factor];
+    for (i, &sibling) in siblings.iter().enumerate() {
+        elements[(position + 1 + i) % folding_factor] = sibling;
+    }
+
+    let first_index = index % stride;
+    let mut points = (0..folding_factor / 2)
+        .map(|i| domain.domain_value((first_index + i * stride) as u32))
+        .collect_vec();
+    let mut folding_challenge = folding_challenge;
+    while elements.len() > 1 {
+        let half = elements.len() / 2;
+        elements = (0..half)
             .map(|i| {
-                let scaled_offset_inv = folding_challenge * domain_point_inverses[i];
-                let left_summand = (one + scaled_offset_inv) * self.codeword[i];
-                let right_summand = (one - scaled_offset_inv) * self.codeword[n / 2 + i];
-                (left_summand + right_summand) * two_inverse
+                let point = points[i].lift();
+                let left = (point, elements[i]);
+                let right = (-point, elements[half + i]);
+                Polynomial::get_colinear_y(left, right, folding_challenge)
             })
-            .collect()
+            .collect();
+        points = points[..half / 2]
+            .iter()
+            .map(|point| point.square())
+            .collect();
+        folding_challenge = folding_challenge.square();
     }
+
+    elements[0]
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -199,7 +354,9 @@ struct FriVerifier<'stream> {
     last_round_polynomial: Polynomial<'static, XFieldElement>,
     last_round_max_degree: usize,
     num_rounds: usize,
+    folding_factor: usize,
     num_collinearity_checks: usize,
+    num_grinding_bits: usize,
     first_round_collinearity_check_indices: Vec<usize>,
 }
 
@@ -207,7 +364,7 @@ struct FriVerifier<'stream> {
 struct VerifierRound {
     domain: ArithmeticDomain,
     partial_codeword_a: Vec<XFieldElement>,
-    partial_codeword_b: Vec<XFieldElement>,
+    partial_codeword_siblings: Vec<XFieldElement>,
     merkle_root: Digest,
     folding_challenge: Option<XFieldElement>,
 }
@@ -220,7 +377,7 @@ impl FriVerifier<'_> {
 
         for _ in 0..self.num_rounds {
             let previous_round = self.rounds.last().unwrap();
-            let domain = previous_round.domain.halve()?;
+            let domain = previous_round.domain.fold(self.folding_factor)?;
             let next_round = self.construct_round_with_domain(domain)?;
             self.rounds.push(next_round);
         }
@@ -242,7 +399,7 @@ impl FriVerifier<'_> {
         let verifier_round = VerifierRound {
             domain,
             partial_codeword_a: vec![],
-            partial_codeword_b: vec![],
+            partial_codeword_siblings: vec![],
             merkle_root,
             folding_challenge,
         };
@@ -260,12 +417,25 @@ impl FriVerifier<'_> {
     }
 
     fn compute_last_round_folded_partial_codeword(&mut self) -> VerifierResult<()> {
+        self.receive_proof_of_work()?;
         self.sample_first_round_collinearity_check_indices();
         self.receive_authentic_partially_revealed_codewords()?;
         self.successively_fold_partial_codeword_of_each_round();
         Ok(())
     }
 
+    fn receive_proof_of_work(&mut self) -> VerifierResult<()> {
+        if self.num_grinding_bits == 0 {
+            return Ok(());
+        }
+
+        self.proof_stream.dequeue()?.try_into_proof_of_work()?;
+        if !self.proof_stream.has_proof_of_work(self.num_grinding_bits) {
+            return Err(FriValidationError::InsufficientProofOfWork);
+        }
+        Ok(())
+    }
+
     fn sample_first_round_collinearity_check_indices(&mut self) {
         let upper_bound = self.first_round_domain.length;
         self.first_round_collinearity_check_indices = self
@@ -279,8 +449,8 @@ impl FriVerifier<'_> {
 
         let num_rounds_that_have_a_next_round = self.rounds.len() - 1;
         for round_number in 0..num_rounds_that_have_a_next_round {
-            let auth_structure = self.receive_partial_codeword_b_for_round(round_number)?;
-            self.authenticate_partial_codeword_b_for_round(round_number, auth_structure)?;
+            let auth_structure = self.receive_partial_codeword_siblings_for_round(round_number)?;
+            self.authenticate_partial_codeword_siblings_for_round(round_number, auth_structure)?;
         }
         Ok(())
     }
@@ -294,12 +464,12 @@ impl FriVerifier<'_> {
             revealed_leaves,
         } = fri_response;
 
-        self.assert_enough_leaves_were_received(&revealed_leaves)?;
+        self.assert_enough_leaves_were_received(&revealed_leaves, self.num_collinearity_checks)?;
         self.rounds[0].partial_codeword_a = revealed_leaves;
         Ok(auth_structure)
     }
 
-    fn receive_partial_codeword_b_for_round(
+    fn receive_partial_codeword_siblings_for_round(
         &mut self,
         round_number: usize,
     ) -> VerifierResult<AuthenticationStructure> {
@@ -309,13 +479,18 @@ impl FriVerifier<'_> {
             revealed_leaves,
         } = fri_response;
 
-        self.assert_enough_leaves_were_received(&revealed_leaves)?;
-        self.rounds[round_number].partial_codeword_b = revealed_leaves;
+        let num_siblings = self.num_collinearity_checks * (self.folding_factor - 1);
+        self.assert_enough_leaves_were_received(&revealed_leaves, num_siblings)?;
+        self.rounds[round_number].partial_codeword_siblings = revealed_leaves;
         Ok(auth_structure)
     }
 
-    fn assert_enough_leaves_were_received(&self, leaves: &[XFieldElement]) -> VerifierResult<()> {
-        match self.num_collinearity_checks == leaves.len() {
+    fn assert_enough_leaves_were_received(
+        &self,
+        leaves: &[XFieldElement],
+        num_expected_leaves: usize,
+    ) -> VerifierResult<()> {
+        match num_expected_leaves == leaves.len() {
             true => Ok(()),
             false => Err(FriValidationError::IncorrectNumberOfRevealedLeaves),
         }
@@ -343,16 +518,16 @@ impl FriVerifier<'_> {
         }
     }
 
-    fn authenticate_partial_codeword_b_for_round(
+    fn authenticate_partial_codeword_siblings_for_round(
         &self,
         round_number: usize,
         authentication_structure: AuthenticationStructure,
     ) -> VerifierResult<()> {
         let round = &self.rounds[round_number];
-        let revealed_leaves = &round.partial_codeword_b;
+        let revealed_leaves = &round.partial_codeword_siblings;
         let revealed_digests = codeword_as_digests(revealed_leaves);
 
-        let leaf_indices = self.collinearity_check_b_indices_for_round(round_number);
+        let leaf_indices = self.sibling_indices_for_round(round_number);
         let indexed_leafs = leaf_indices.into_iter().zip_eq(revealed_digests).collect();
 
         let inclusion_proof = MerkleTreeInclusionProof {
@@ -378,20 +553,19 @@ impl FriVerifier<'_> {
     fn fold_partial_codeword_of_round(&self, round_number: usize) -> Vec<XFieldElement> {
         let round = &self.rounds[round_number];
         let a_indices = self.collinearity_check_a_indices_for_round(round_number);
-        let b_indices = self.collinearity_check_b_indices_for_round(round_number);
         let partial_codeword_a = &round.partial_codeword_a;
-        let partial_codeword_b = &round.partial_codeword_b;
+        let siblings = round
+            .partial_codeword_siblings
+            .chunks(self.folding_factor - 1)
+            .collect_vec();
         let domain = round.domain;
         let folding_challenge = round.folding_challenge.unwrap();
 
         (0..self.num_collinearity_checks)
             .into_par_iter()
             .map(|i| {
-                let point_a_x = domain.domain_value(a_indices[i] as u32).lift();
-                let point_b_x = domain.domain_value(b_indices[i] as u32).lift();
-                let point_a = (point_a_x, partial_codeword_a[i]);
-                let point_b = (point_b_x, partial_codeword_b[i]);
-                Polynomial::get_colinear_y(point_a, point_b, folding_challenge)
+                let (index, element) = (a_indices[i], partial_codeword_a[i]);
+                fold_element_and_siblings(domain, index, element, siblings[i], folding_challenge)
             })
             .collect()
     }
@@ -402,10 +576,12 @@ impl FriVerifier<'_> {
         self.collinearity_check_indices_with_offset_and_modulus(a_offset, domain_length)
     }
 
-    fn collinearity_check_b_indices_for_round(&self, round_number: usize) -> Vec<usize> {
+    fn sibling_indices_for_round(&self, round_number: usize) -> Vec<usize> {
         let domain_length = self.rounds[round_number].domain.length;
-        let b_offset = domain_length / 2;
-        self.collinearity_check_indices_with_offset_and_modulus(b_offset, domain_length)
+        self.first_round_collinearity_check_indices
+            .iter()
+            .flat_map(|&a_index| sibling_indices(a_index, domain_length, self.folding_factor))
+            .collect()
     }
 
     fn collinearity_check_indices_with_offset_and_modulus(
@@ -518,16 +694,68 @@ impl Fri {
             expansion_factor,
             num_collinearity_checks,
             domain,
+            folding_factor: 2,
+            num_grinding_bits: 0,
         })
     }
 
+    /// Shrink the codeword by the given factor in every round.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the folding factor is not a power of 2 greater than 1.
+    pub fn with_folding_factor(mut self, folding_factor: usize) -> SetupResult<Self> {
+        if folding_factor <= 1 || !folding_factor.is_power_of_two() {
+            return Err(FriSetupError::FoldingFactorUnsupported);
+        }
+        self.folding_factor = folding_factor;
+        Ok(self)
+    }
+
+    /// Require the prover to [grind](Self::num_grinding_bits) for the given
+    /// number of bits.
+    #[must_use]
+    pub fn with_num_grinding_bits(mut self, num_grinding_bits: usize) -> Self {
+        self.num_grinding_bits = num_grinding_bits;
+        self
+    }
+
     /// Create a FRI proof and return a-indices of revealed elements of round 0.
     pub fn prove(
         &self,
         codeword: &[XFieldElement],
         proof_stream: &mut ProofStream,
     ) -> ProverResult<Vec<usize>> {
-        let mut prover = self.prover(proof_stream);
+        let progress_monitor = ProgressMonitor::default();
+        let checkpoints = Checkpoints::default();
+        self.prove_with_progress(
+            codeword,
+            proof_stream,
+            &progress_monitor,
+            true,
+            &checkpoints,
+        )
+    }
+
+    /// Like [`prove`](Self::prove), but reports every round to the given monitor,
+    /// and stops if proving is cancelled. Unless the codewords of all rounds are
+    /// kept, the codeword of each round is freed once the next round is
+    /// committed to. The codeword of every round is restored from, or else saved
+    /// to, the given checkpoints.
+    pub(crate) fn prove_with_progress(
+        &self,
+        codeword: &[XFieldElement],
+        proof_stream: &mut ProofStream,
+        progress_monitor: &ProgressMonitor,
+        keep_round_codewords: bool,
+        checkpoints: &Checkpoints,
+    ) -> ProverResult<Vec<usize>> {
+        let mut prover = self.prover(
+            proof_stream,
+            progress_monitor,
+            keep_round_codewords,
+            checkpoints,
+        );
 
         prover.commit(codeword)?;
         prover.query()?;
@@ -544,14 +772,22 @@ impl Fri {
     fn prover<'stream>(
         &'stream self,
         proof_stream: &'stream mut ProofStream,
+        progress_monitor: &'stream ProgressMonitor,
+        keep_round_codewords: bool,
+        checkpoints: &'stream Checkpoints,
     ) -> FriProver<'stream> {
         FriProver {
             proof_stream,
             rounds: vec![],
             first_round_domain: self.domain,
             num_rounds: self.num_rounds(),
+            folding_factor: self.folding_factor,
             num_collinearity_checks: self.num_collinearity_checks,
+            num_grinding_bits: self.num_grinding_bits,
             first_round_collinearity_check_indices: vec![],
+            progress_monitor,
+            keep_round_codewords,
+            checkpoints,
         }
     }
 
@@ -589,26 +825,30 @@ impl Fri {
             last_round_polynomial: Polynomial::zero(),
             last_round_max_degree: self.last_round_max_degree(),
             num_rounds: self.num_rounds(),
+            folding_factor: self.folding_factor,
             num_collinearity_checks: self.num_collinearity_checks,
+            num_grinding_bits: self.num_grinding_bits,
             first_round_collinearity_check_indices: vec![],
         }
     }
 
     pub fn num_rounds(&self) -> usize {
         let first_round_code_dimension = self.first_round_max_degree() + 1;
-        let max_num_rounds = first_round_code_dimension.next_power_of_two().ilog2();
+        let max_num_halvings = first_round_code_dimension.next_power_of_two().ilog2();
 
         // Skip rounds for which Merkle tree verification cost exceeds arithmetic cost,
         // because more than half the codeword's locations are queried.
-        let num_rounds_checking_all_locations = self.num_collinearity_checks.ilog2();
-        let num_rounds_checking_most_locations = num_rounds_checking_all_locations + 1;
+        let num_halvings_checking_all_locations = self.num_collinearity_checks.ilog2();
+        let num_halvings_checking_most_locations = num_halvings_checking_all_locations + 1;
 
-        let num_rounds = max_num_rounds.saturating_sub(num_rounds_checking_most_locations);
+        let num_halvings = max_num_halvings.saturating_sub(num_halvings_checking_most_locations);
+        let num_rounds = num_halvings / self.folding_factor.ilog2();
         num_rounds.try_into().unwrap()
     }
 
     pub fn last_round_max_degree(&self) -> usize {
-        self.first_round_max_degree() >> self.num_rounds()
+        let num_halvings = self.num_rounds() * self.folding_factor.ilog2() as usize;
+        self.first_round_max_degree() >> num_halvings
     }
 
     pub fn first_round_max_degree(&self) -> usize {
@@ -650,6 +890,7 @@ mod tests {
         )(
             log_2_expansion_factor in Just(log_2_expansion_factor),
             log_2_domain_length in log_2_expansion_factor..=18,
+            log_2_folding_factor in 1_usize..=4,
             num_collinearity_checks in 1_usize..=320,
             offset in arb(),
         ) -> Fri {
@@ -666,7 +907,10 @@ mod tests {
             let maybe_domain = ArithmeticDomain::of_length(domain_length);
             let fri_domain = maybe_domain.unwrap().with_offset(offset);
 
-            Fri::new(fri_domain, expansion_factor, num_collinearity_checks).unwrap()
+            Fri::new(fri_domain, expansion_factor, num_collinearity_checks)
+                .unwrap()
+                .with_folding_factor(1 << log_2_folding_factor)
+                .unwrap()
         }
     }
 
@@ -697,14 +941,104 @@ mod tests {
 
     #[proptest]
     fn num_rounds_are_reasonable(fri: Fri) {
-        let expected_last_round_max_degree = fri.first_round_max_degree() >> fri.num_rounds();
+        let first_round_code_dimension = fri.first_round_max_degree() + 1;
+        let last_round_code_dimension =
+            first_round_code_dimension / fri.folding_factor.pow(fri.num_rounds() as u32);
+        let expected_last_round_max_degree = last_round_code_dimension - 1;
         prop_assert_eq!(expected_last_round_max_degree, fri.last_round_max_degree());
         if fri.num_rounds() > 0 {
+            let max_last_round_max_degree = fri.folding_factor * fri.num_collinearity_checks;
             prop_assert!(fri.num_collinearity_checks <= expected_last_round_max_degree);
-            prop_assert!(expected_last_round_max_degree < 2 * fri.num_collinearity_checks);
+            prop_assert!(expected_last_round_max_degree < max_last_round_max_degree);
         }
     }
 
+    #[proptest]
+    fn larger_folding_factor_does_not_increase_number_of_rounds(
+        fri: Fri,
+        #[strategy(1_u32..=4)] log_2_folding_factor: u32,
+    ) {
+        let folding_factor = 1 << log_2_folding_factor;
+        let binary_fri = fri.with_folding_factor(2).unwrap();
+        let other_fri = fri.with_folding_factor(folding_factor).unwrap();
+        prop_assert!(other_fri.num_rounds() <= binary_fri.num_rounds());
+        prop_assert!(binary_fri.last_round_max_degree() <= other_fri.last_round_max_degree());
+    }
+
+    #[proptest(cases = 10)]
+    fn folding_stops_once_cancelled(
+        #[strategy(arbitrary_fri_supporting_degree(15))] fri: Fri,
+        #[strategy(arbitrary_polynomial())] polynomial: XfePoly,
+        #[strategy(arb())] folding_challenge: XFieldElement,
+    ) {
+        let codeword = fri.domain.evaluate(&polynomial);
+        let round = ProverRound::new(fri.domain, &codeword).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let_assert!(
+            Err(FriProvingError::Cancelled) =
+                round.fold(folding_challenge, fri.folding_factor, &token)
+        );
+    }
+
+    #[proptest(cases = 50)]
+    fn folding_element_and_siblings_agrees_with_folding_entire_codeword(
+        #[strategy(arbitrary_fri_supporting_degree(15))] fri: Fri,
+        #[strategy(arbitrary_polynomial())] polynomial: XfePoly,
+        #[strategy(arb())] folding_challenge: XFieldElement,
+        #[strategy(0_usize..#fri.domain.length)] index: usize,
+    ) {
+        let codeword = fri.domain.evaluate(&polynomial);
+        let round = ProverRound::new(fri.domain, &codeword).unwrap();
+        let_assert!(
+            Ok(folded_codeword) = round.fold(
+                folding_challenge,
+                fri.folding_factor,
+                &CancellationToken::new()
+            )
+        );
+        let folded_domain = fri.domain.fold(fri.folding_factor).unwrap();
+        prop_assert_eq!(folded_domain.length, folded_codeword.len());
+
+        let siblings = sibling_indices(index, fri.domain.length, fri.folding_factor)
+            .map(|i| codeword[i])
+            .collect_vec();
+        let folded_element = fold_element_and_siblings(
+            fri.domain,
+            index,
+            codeword[index],
+            &siblings,
+            folding_challenge,
+        );
+        prop_assert_eq!(
+            folded_codeword[index % folded_domain.length],
+            folded_element
+        );
+    }
+
+    #[proptest(cases = 20)]
+    fn folding_reduces_degree_by_folding_factor(
+        #[strategy(arbitrary_fri_supporting_degree(15))] fri: Fri,
+        #[strategy(Just(#fri.first_round_max_degree() as i64))] _degree: i64,
+        #[strategy(arbitrary_polynomial_of_degree(#_degree))] polynomial: XfePoly,
+        #[strategy(arb())] folding_challenge: XFieldElement,
+    ) {
+        let codeword = fri.domain.evaluate(&polynomial);
+        let round = ProverRound::new(fri.domain, &codeword).unwrap();
+        let folded_codeword = round
+            .fold(
+                folding_challenge,
+                fri.folding_factor,
+                &CancellationToken::new(),
+            )
+            .unwrap();
+
+        let folded_domain = fri.domain.fold(fri.folding_factor).unwrap();
+        let folded_polynomial = folded_domain.interpolate(&folded_codeword);
+        let max_folded_degree = (fri.first_round_max_degree() + 1) / fri.folding_factor;
+        prop_assert!(folded_polynomial.degree() < max_folded_degree as isize);
+    }
+
     #[proptest(cases = 20)]
     fn prove_and_verify_low_degree_of_twice_cubing_plus_one(
         #[strategy(arbitrary_fri_supporting_degree(3))] fri: Fri,
@@ -737,6 +1071,41 @@ mod tests {
         prop_assert!(verdict.is_ok());
     }
 
+    #[proptest(cases = 50)]
+    fn forgetting_round_codewords_does_not_change_proof(
+        fri: Fri,
+        #[strategy(arbitrary_polynomial())] polynomial: XfePoly,
+    ) {
+        let codeword = fri.domain.evaluate(&polynomial);
+        let progress_monitor = ProgressMonitor::default();
+        let checkpoints = Checkpoints::default();
+
+        let mut proof_stream = ProofStream::new();
+        let indices = fri
+            .prove_with_progress(
+                &codeword,
+                &mut proof_stream,
+                &progress_monitor,
+                true,
+                &checkpoints,
+            )
+            .unwrap();
+
+        let mut forgetful_proof_stream = ProofStream::new();
+        let forgetful_indices = fri
+            .prove_with_progress(
+                &codeword,
+                &mut forgetful_proof_stream,
+                &progress_monitor,
+                false,
+                &checkpoints,
+            )
+            .unwrap();
+
+        prop_assert_eq!(indices, forgetful_indices);
+        prop_assert_eq!(proof_stream, forgetful_proof_stream);
+    }
+
     #[proptest(cases = 50)]
     fn prove_and_fail_to_verify_high_degree_polynomial(
         fri: Fri,
@@ -771,6 +1140,16 @@ mod tests {
         Fri::new(domain, expansion_factor, num_collinearity_checks).unwrap()
     }
 
+    #[proptest]
+    fn folding_factor_not_a_power_of_two_greater_than_one_is_rejected(
+        #[filter(#folding_factor <= 1 || !#folding_factor.is_power_of_two())] folding_factor: usize,
+    ) {
+        let err = smallest_fri()
+            .with_folding_factor(folding_factor)
+            .unwrap_err();
+        prop_assert_eq!(FriSetupError::FoldingFactorUnsupported, err);
+    }
+
     #[test]
     fn too_small_expansion_factor_is_rejected() {
         let domain = ArithmeticDomain::of_length(2).unwrap();
@@ -168,25 +168,34 @@ use crate::prelude::*;
 pub mod aet;
 pub mod arithmetic_domain;
 pub mod challenges;
+mod checkpoint;
 pub mod config;
 pub mod constraints;
+pub mod coverage;
 pub mod error;
 pub mod example_programs;
 pub mod execution_trace_profiler;
 pub mod fri;
 pub mod memory_layout;
+mod memory_plan;
 mod ndarray_helper;
 pub mod prelude;
 pub mod profiler;
+pub mod progress;
 pub mod proof;
 pub mod proof_item;
 pub mod proof_stream;
+pub mod snippet_test;
 pub mod stark;
 pub mod table;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 pub mod vm;
 
 #[cfg(test)]
 mod shared_tests;
+#[cfg(test)]
+mod stdlib_tests;
 
 /// Prove correct execution of a program written in Triton assembly.
 /// This is a convenience function, abstracting away the details of the STARK construction.
@@ -345,6 +354,8 @@ mod tests {
         implements_auto_traits::<aet::AlgebraicExecutionTrace>();
         implements_auto_traits::<aet::TableHeight>();
         implements_auto_traits::<arithmetic_domain::ArithmeticDomain>();
+        implements_auto_traits::<coverage::ExecutionCoverage>();
+        implements_auto_traits::<coverage::CoverageLine>();
         implements_auto_traits::<execution_trace_profiler::ExecutionTraceProfile>();
         implements_auto_traits::<execution_trace_profiler::ProfileLine>();
         implements_auto_traits::<execution_trace_profiler::VMTableHeights>();
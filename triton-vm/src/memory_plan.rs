@@ -0,0 +1,293 @@
+//! Decide how to [prove](crate::stark::Prover::prove) within a
+//! [memory budget](crate::config::overwrite_memory_budget_to).
+//!
+//! The sizes used here are estimates of the prover's largest allocations. They
+//! ignore small allocations, like the ones for polynomials of low degree, as
+//! well as the memory needed for the algebraic execution trace, which exists
+//! before proving starts.
+
+use std::mem::size_of;
+
+use twenty_first::prelude::*;
+
+use crate::arithmetic_domain::ArithmeticDomain;
+use crate::config;
+use crate::config::CacheDecision;
+use crate::error::ProvingError;
+use crate::stark::NUM_QUOTIENT_SEGMENTS;
+use crate::table::master_table::randomized_trace_len;
+use crate::table::master_table::MasterAuxTable;
+use crate::table::master_table::MasterMainTable;
+use crate::table::master_table::MasterTable;
+
+const BFE_SIZE: usize = size_of::<BFieldElement>();
+const XFE_SIZE: usize = size_of::<XFieldElement>();
+const DIGEST_SIZE: usize = size_of::<Digest>();
+
+/// The number of bytes of one row of both the main and the auxiliary table.
+const ROW_SIZE: usize =
+    MasterMainTable::NUM_COLUMNS * BFE_SIZE + MasterAuxTable::NUM_COLUMNS * XFE_SIZE;
+
+/// The smallest [ratio](MemoryPlan::randomized_trace_len_to_working_domain_len_ratio)
+/// of the randomized trace length to the length of the working domain used for
+/// computing the quotients just in time.
+const MIN_WORKING_DOMAIN_RATIO: usize = 2;
+
+/// How to trade time for memory while proving.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub(crate) struct MemoryPlan {
+    /// Whether to cache the low-degree extended main and auxiliary tables. `None`
+    /// means the decision is made automatically, based on free memory. Either
+    /// both tables are cached or neither is, since computing the quotients needs
+    /// either both caches or neither.
+    pub cache_lde_trace: Option<CacheDecision>,
+
+    /// If the low-degree extended tables are not cached, the quotients are
+    /// computed in chunks, one coset of a “working domain” at a time. This is the
+    /// ratio of the randomized trace length to the length of the working domain.
+    /// A larger ratio means more, smaller chunks. Always a power of two.
+    pub randomized_trace_len_to_working_domain_len_ratio: usize,
+
+    /// Whether [FRI](crate::fri::Fri) keeps the codewords of all rounds in memory.
+    /// If not, the revealed codeword elements are read from the Merkle trees'
+    /// leafs.
+    pub keep_fri_round_codewords: bool,
+}
+
+impl Default for MemoryPlan {
+    fn default() -> Self {
+        Self {
+            cache_lde_trace: None,
+            randomized_trace_len_to_working_domain_len_ratio: MIN_WORKING_DOMAIN_RATIO,
+            keep_fri_round_codewords: true,
+        }
+    }
+}
+
+impl MemoryPlan {
+    /// The plan for the configured [memory budget](config::memory_budget).
+    /// An [overwritten LDE caching decision](config::overwrite_lde_trace_caching_to)
+    /// takes precedence over the budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProvingError::MemoryBudgetExceeded`] if proving needs more memory
+    /// than the budget allows, no matter the plan.
+    pub fn new(
+        padded_height: usize,
+        num_trace_randomizers: usize,
+        quotient_domain: ArithmeticDomain,
+        fri_domain: ArithmeticDomain,
+    ) -> Result<Self, ProvingError> {
+        let mut plan = match config::memory_budget() {
+            None => Self::default(),
+            Some(budget) => {
+                let sizes = DomainLengths::new(
+                    padded_height,
+                    num_trace_randomizers,
+                    quotient_domain,
+                    fri_domain,
+                );
+                Self::within_budget(budget, sizes)?
+            }
+        };
+        if let Some(decision) = config::cache_lde_trace() {
+            plan.cache_lde_trace = Some(decision);
+        }
+
+        Ok(plan)
+    }
+
+    fn within_budget(budget: usize, sizes: DomainLengths) -> Result<Self, ProvingError> {
+        let max_ratio = sizes.max_working_domain_ratio();
+        let required = sizes.always_required() + sizes.just_in_time_quotients(max_ratio);
+        if budget < required {
+            return Err(ProvingError::MemoryBudgetExceeded { budget, required });
+        }
+
+        let with_cache = sizes.always_required() + sizes.cached_quotients();
+        let (cache_lde_trace, ratio, used) = if with_cache <= budget {
+            (CacheDecision::Cache, MIN_WORKING_DOMAIN_RATIO, with_cache)
+        } else {
+            let mut ratio = MIN_WORKING_DOMAIN_RATIO;
+            let used = |ratio| sizes.always_required() + sizes.just_in_time_quotients(ratio);
+            while used(ratio) > budget {
+                ratio *= 2;
+            }
+            (CacheDecision::NoCache, ratio, used(ratio))
+        };
+        let keep_fri_round_codewords = used + sizes.fri_round_codewords() <= budget;
+
+        Ok(Self {
+            cache_lde_trace: Some(cache_lde_trace),
+            randomized_trace_len_to_working_domain_len_ratio: ratio,
+            keep_fri_round_codewords,
+        })
+    }
+}
+
+/// The lengths of all relevant domains, from which the sizes of the prover's
+/// largest allocations can be estimated.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+struct DomainLengths {
+    randomized_trace: usize,
+    quotient_domain: usize,
+    fri_domain: usize,
+}
+
+impl DomainLengths {
+    fn new(
+        padded_height: usize,
+        num_trace_randomizers: usize,
+        quotient_domain: ArithmeticDomain,
+        fri_domain: ArithmeticDomain,
+    ) -> Self {
+        Self {
+            randomized_trace: randomized_trace_len(padded_height, num_trace_randomizers),
+            quotient_domain: quotient_domain.length,
+            fri_domain: fri_domain.length,
+        }
+    }
+
+    fn evaluation_domain(self) -> usize {
+        self.quotient_domain.max(self.fri_domain)
+    }
+
+    /// The memory needed independent of the plan: the trace tables, the Merkle
+    /// trees of the main table, the auxiliary table, and the quotient segments,
+    /// the quotient segment codewords, the DEEP codewords, and the FRI Merkle
+    /// trees.
+    fn always_required(self) -> usize {
+        let trace_len = self.randomized_trace / 2;
+        let trace = trace_len * ROW_SIZE;
+        let merkle_trees = 3 * Self::merkle_tree(self.fri_domain);
+        let quotient_segments = self.fri_domain * NUM_QUOTIENT_SEGMENTS * XFE_SIZE;
+
+        // Roughly: the combination codeword of the main and auxiliary tables, the
+        // combination codeword of the quotient segments, and the DEEP codewords.
+        let deep = 5 * self.fri_domain * XFE_SIZE;
+
+        // The domains of the FRI rounds halve from round to round.
+        let fri_merkle_trees = 2 * Self::merkle_tree(self.fri_domain);
+
+        trace + merkle_trees + quotient_segments + deep + fri_merkle_trees
+    }
+
+    /// The memory needed for caching the low-degree extended tables, and
+    /// computing the quotients from the cache.
+    fn cached_quotients(self) -> usize {
+        let lde_tables = self.evaluation_domain() * ROW_SIZE;
+        let quotient_codeword = self.quotient_domain * XFE_SIZE;
+
+        lde_tables + quotient_codeword
+    }
+
+    /// The memory needed for computing the quotients just in time, using a
+    /// working domain that is shorter than the randomized trace by the given
+    /// ratio. If the working domain is shorter than the trace domain, the tables
+    /// holding the “next rows” need to be evaluated separately.
+    fn just_in_time_quotients(self, ratio: usize) -> usize {
+        let working_domain_len = self.randomized_trace / ratio;
+        let num_working_tables = if working_domain_len < self.randomized_trace / 2 {
+            2
+        } else {
+            1
+        };
+        let working_tables = num_working_tables * working_domain_len * ROW_SIZE;
+        let multicoset_evaluations = self.randomized_trace * NUM_QUOTIENT_SEGMENTS * XFE_SIZE;
+
+        working_tables + multicoset_evaluations
+    }
+
+    /// The largest ratio for which the working domain is at least as long as the
+    /// number of cosets it is evaluated on.
+    fn max_working_domain_ratio(self) -> usize {
+        let mut ratio = MIN_WORKING_DOMAIN_RATIO;
+        let num_cosets = |ratio| NUM_QUOTIENT_SEGMENTS * ratio;
+        while self.randomized_trace / (2 * ratio) >= num_cosets(2 * ratio) {
+            ratio *= 2;
+        }
+        ratio
+    }
+
+    fn fri_round_codewords(self) -> usize {
+        // The domains of the FRI rounds halve from round to round.
+        2 * self.fri_domain * XFE_SIZE
+    }
+
+    fn merkle_tree(num_leafs: usize) -> usize {
+        2 * num_leafs * DIGEST_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+    use test_strategy::proptest;
+
+    use super::*;
+
+    impl DomainLengths {
+        fn for_log2_padded_height(log2_padded_height: u32) -> Self {
+            let randomized_trace_len = 1 << (log2_padded_height + 1);
+            let fri_domain_len = 4 * randomized_trace_len;
+            Self {
+                randomized_trace: randomized_trace_len,
+                quotient_domain: fri_domain_len,
+                fri_domain: fri_domain_len,
+            }
+        }
+    }
+
+    #[proptest]
+    fn ample_budget_caches_everything(#[strategy(4_u32..25)] log2_padded_height: u32) {
+        let plan = MemoryPlan::within_budget(
+            usize::MAX,
+            DomainLengths::for_log2_padded_height(log2_padded_height),
+        )
+        .unwrap();
+        assert!(Some(CacheDecision::Cache) == plan.cache_lde_trace);
+        assert!(plan.keep_fri_round_codewords);
+    }
+
+    #[proptest]
+    fn insufficient_budget_is_reported(#[strategy(4_u32..25)] log2_padded_height: u32) {
+        let sizes = DomainLengths::for_log2_padded_height(log2_padded_height);
+        let_assert!(
+            Err(ProvingError::MemoryBudgetExceeded { required, .. }) =
+                MemoryPlan::within_budget(1000, sizes)
+        );
+        assert!(MemoryPlan::within_budget(required, sizes).is_ok());
+        assert!(MemoryPlan::within_budget(required - 1, sizes).is_err());
+    }
+
+    #[proptest]
+    fn smaller_budget_gives_smaller_chunks(
+        #[strategy(10_u32..25)] log2_padded_height: u32,
+        #[strategy(0.0..1.0)] fraction_of_jit_memory: f64,
+    ) {
+        let sizes = DomainLengths::for_log2_padded_height(log2_padded_height);
+        let max_ratio = sizes.max_working_domain_ratio();
+        let min_jit = sizes.always_required() + sizes.just_in_time_quotients(max_ratio);
+        let max_jit = sizes.always_required() + sizes.just_in_time_quotients(2);
+        let budget = min_jit + ((max_jit - min_jit) as f64 * fraction_of_jit_memory) as usize;
+
+        let plan = MemoryPlan::within_budget(budget, sizes).unwrap();
+        let ratio = plan.randomized_trace_len_to_working_domain_len_ratio;
+        assert!(Some(CacheDecision::NoCache) == plan.cache_lde_trace);
+        assert!(ratio.is_power_of_two());
+        assert!((MIN_WORKING_DOMAIN_RATIO..=max_ratio).contains(&ratio));
+        assert!(sizes.always_required() + sizes.just_in_time_quotients(ratio) <= budget);
+    }
+
+    #[proptest]
+    fn working_domain_is_at_least_as_long_as_number_of_cosets(
+        #[strategy(3_u32..30)] log2_padded_height: u32,
+    ) {
+        let sizes = DomainLengths::for_log2_padded_height(log2_padded_height);
+        let ratio = sizes.max_working_domain_ratio();
+        let working_domain_len = sizes.randomized_trace / ratio;
+        assert!(working_domain_len >= NUM_QUOTIENT_SEGMENTS * ratio);
+    }
+}
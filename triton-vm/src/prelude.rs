@@ -38,6 +38,7 @@ pub use crate::error::ProgramDecodingError;
 pub use crate::error::VMError;
 pub use crate::proof::Claim;
 pub use crate::proof::Proof;
+pub use crate::stark::PreparedVerifier;
 pub use crate::stark::Prover;
 pub use crate::stark::Stark;
 pub use crate::stark::Verifier;
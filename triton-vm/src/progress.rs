@@ -0,0 +1,442 @@
+//! Observe the progress of a [`Prover`](crate::stark::Prover), and cancel it.
+//!
+//! Proving can take minutes. A [`ProgressObserver`] is notified whenever the
+//! prover enters a new [`ProvingPhase`], together with an estimate of the
+//! fraction of the work that is done. A [`CancellationToken`] makes the prover
+//! stop at the next opportunity, returning [`ProvingError::Cancelled`]. The
+//! prover checks for cancellation whenever it enters a new phase, as well as
+//! within its long-running loops.
+//!
+//! ```no_run
+//! # use triton_vm::prelude::*;
+//! # use triton_vm::progress::CancellationToken;
+//! # use triton_vm::progress::ProgressEvent;
+//! # let (claim, aet) = unimplemented!();
+//! let cancellation_token = CancellationToken::new();
+//! let prover = Prover::default()
+//!     .with_progress_observer(|event: ProgressEvent| {
+//!         println!("{:>3.0}% – {}", 100.0 * event.fraction_done, event.phase)
+//!     })
+//!     .with_cancellation_token(cancellation_token.clone());
+//!
+//! // on some other thread, if the proof is no longer needed:
+//! cancellation_token.cancel();
+//!
+//! let result = prover.prove(&claim, &aet);
+//! ```
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::error::ProvingError;
+
+/// A phase of [proving](crate::stark::Prover::prove), in the order in which
+/// they are entered.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ProvingPhase {
+    /// Creating and padding the main table from the
+    /// [algebraic execution trace](crate::aet::AlgebraicExecutionTrace).
+    MainTable,
+
+    /// Low-degree extending the main table, if the result can be cached.
+    MainTableLowDegreeExtension,
+
+    /// Committing to the main table. Includes the low-degree extension if it is
+    /// not cached.
+    MainTableMerkleTree,
+
+    /// Deriving the auxiliary table from the main table.
+    AuxTable,
+
+    /// Low-degree extending the auxiliary table, if the result can be cached.
+    AuxTableLowDegreeExtension,
+
+    /// Committing to the auxiliary table. Includes the low-degree extension if it
+    /// is not cached.
+    AuxTableMerkleTree,
+
+    /// Computing and committing to the segments of the combined quotient.
+    Quotient,
+
+    /// Evaluating all tables and the quotient segments out of domain.
+    OutOfDomainRows,
+
+    /// Combining all codewords, and the DEEP update.
+    Deep,
+
+    /// The given folding round of [FRI](crate::fri::Fri), counting from 0. The
+    /// number of rounds is [`Fri::num_rounds`](crate::fri::Fri::num_rounds).
+    FriRound { round: usize, num_rounds: usize },
+
+    /// Revealing the rows of all tables that FRI asks for.
+    OpenTraceRows,
+
+    /// The proof is complete.
+    Done,
+}
+
+/// A notification about the progress of a [`Prover`](crate::stark::Prover).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProgressEvent {
+    /// The phase the prover just entered.
+    pub phase: ProvingPhase,
+
+    /// An estimate of the fraction of work that is done, between 0 and 1. Based on
+    /// typical relative durations of the phases; the actual durations depend on
+    /// the machine and the program being proven.
+    pub fraction_done: f64,
+}
+
+/// Receives [`ProgressEvent`]s from a [`Prover`](crate::stark::Prover).
+/// Implemented for all suitable closures.
+///
+/// Observers are notified on the thread that is proving, which waits for the
+/// observer to return. Observers should return quickly.
+pub trait ProgressObserver: Send + Sync {
+    fn observe(&self, event: ProgressEvent);
+}
+
+impl<F> ProgressObserver for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn observe(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// Cancels [proving](crate::stark::Prover::prove) when [cancelled](Self::cancel).
+/// All clones of a token are connected: cancelling one cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl ProvingPhase {
+    /// The estimated fraction of work that is done when entering this phase.
+    pub fn fraction_done(self) -> f64 {
+        // The estimated fraction of the total work that each phase takes.
+        const MAIN_TABLE: f64 = 0.05;
+        const MAIN_TABLE_LDE: f64 = 0.1;
+        const MAIN_TABLE_MERKLE_TREE: f64 = 0.1;
+        const AUX_TABLE: f64 = 0.05;
+        const AUX_TABLE_LDE: f64 = 0.1;
+        const AUX_TABLE_MERKLE_TREE: f64 = 0.1;
+        const QUOTIENT: f64 = 0.25;
+        const OUT_OF_DOMAIN_ROWS: f64 = 0.02;
+        const DEEP: f64 = 0.08;
+        const FRI: f64 = 0.12;
+
+        const BEFORE_MAIN_TABLE_LDE: f64 = MAIN_TABLE;
+        const BEFORE_MAIN_TABLE_MERKLE_TREE: f64 = BEFORE_MAIN_TABLE_LDE + MAIN_TABLE_LDE;
+        const BEFORE_AUX_TABLE: f64 = BEFORE_MAIN_TABLE_MERKLE_TREE + MAIN_TABLE_MERKLE_TREE;
+        const BEFORE_AUX_TABLE_LDE: f64 = BEFORE_AUX_TABLE + AUX_TABLE;
+        const BEFORE_AUX_TABLE_MERKLE_TREE: f64 = BEFORE_AUX_TABLE_LDE + AUX_TABLE_LDE;
+        const BEFORE_QUOTIENT: f64 = BEFORE_AUX_TABLE_MERKLE_TREE + AUX_TABLE_MERKLE_TREE;
+        const BEFORE_OUT_OF_DOMAIN_ROWS: f64 = BEFORE_QUOTIENT + QUOTIENT;
+        const BEFORE_DEEP: f64 = BEFORE_OUT_OF_DOMAIN_ROWS + OUT_OF_DOMAIN_ROWS;
+        const BEFORE_FRI: f64 = BEFORE_DEEP + DEEP;
+        const BEFORE_OPEN_TRACE_ROWS: f64 = BEFORE_FRI + FRI;
+
+        match self {
+            Self::MainTable => 0.0,
+            Self::MainTableLowDegreeExtension => BEFORE_MAIN_TABLE_LDE,
+            Self::MainTableMerkleTree => BEFORE_MAIN_TABLE_MERKLE_TREE,
+            Self::AuxTable => BEFORE_AUX_TABLE,
+            Self::AuxTableLowDegreeExtension => BEFORE_AUX_TABLE_LDE,
+            Self::AuxTableMerkleTree => BEFORE_AUX_TABLE_MERKLE_TREE,
+            Self::Quotient => BEFORE_QUOTIENT,
+            Self::OutOfDomainRows => BEFORE_OUT_OF_DOMAIN_ROWS,
+            Self::Deep => BEFORE_DEEP,
+            Self::FriRound { round, num_rounds } => {
+                let fraction_of_fri = round as f64 / (num_rounds.max(1) as f64);
+                BEFORE_FRI + FRI * fraction_of_fri.min(1.0)
+            }
+            Self::OpenTraceRows => BEFORE_OPEN_TRACE_ROWS,
+            Self::Done => 1.0,
+        }
+    }
+}
+
+impl Display for ProvingPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MainTable => write!(f, "main table"),
+            Self::MainTableLowDegreeExtension => write!(f, "main table: low-degree extension"),
+            Self::MainTableMerkleTree => write!(f, "main table: Merkle tree"),
+            Self::AuxTable => write!(f, "auxiliary table"),
+            Self::AuxTableLowDegreeExtension => write!(f, "auxiliary table: low-degree extension"),
+            Self::AuxTableMerkleTree => write!(f, "auxiliary table: Merkle tree"),
+            Self::Quotient => write!(f, "quotient"),
+            Self::OutOfDomainRows => write!(f, "out-of-domain rows"),
+            Self::Deep => write!(f, "DEEP"),
+            Self::FriRound { round, num_rounds } => {
+                write!(f, "FRI round {} of {num_rounds}", round + 1)
+            }
+            Self::OpenTraceRows => write!(f, "open trace rows"),
+            Self::Done => write!(f, "done"),
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all proving that uses this token or one of its clones.
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::Relaxed)
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`ProvingError::Cancelled`] if proving has been cancelled.
+    pub(crate) fn check(&self) -> Result<(), ProvingError> {
+        match self.is_cancelled() {
+            true => Err(ProvingError::Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Everything a [`Prover`](crate::stark::Prover) needs to report progress and to
+/// notice cancellation.
+#[derive(Clone, Default)]
+pub(crate) struct ProgressMonitor {
+    observer: Option<Arc<dyn ProgressObserver>>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl ProgressMonitor {
+    pub(crate) fn set_observer(&mut self, observer: Arc<dyn ProgressObserver>) {
+        self.observer = Some(observer);
+    }
+
+    pub(crate) fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = Some(token);
+    }
+
+    /// Notify the observer, if any, that the given phase is entered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProvingError::Cancelled`] if proving has been cancelled.
+    pub(crate) fn enter(&self, phase: ProvingPhase) -> Result<(), ProvingError> {
+        self.check_cancellation()?;
+        if let Some(observer) = &self.observer {
+            let fraction_done = phase.fraction_done();
+            observer.observe(ProgressEvent {
+                phase,
+                fraction_done,
+            });
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`ProvingError::Cancelled`] if proving has been cancelled.
+    pub(crate) fn check_cancellation(&self) -> Result<(), ProvingError> {
+        let token = self.cancellation_token.as_ref();
+        token.map_or(Ok(()), CancellationToken::check)
+    }
+
+    /// The token that cancels proving, for checks within parallel loops. Never
+    /// cancelled if no token was set.
+    pub(crate) fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone().unwrap_or_default()
+    }
+}
+
+impl Debug for ProgressMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressMonitor")
+            .field("has_observer", &self.observer.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .finish()
+    }
+}
+
+/// Two monitors are equal if they report to the same observer and listen to the
+/// same cancellation token.
+impl PartialEq for ProgressMonitor {
+    fn eq(&self, other: &Self) -> bool {
+        let same_observer = match (&self.observer, &other.observer) {
+            (Some(own), Some(other)) => Arc::ptr_eq(own, other),
+            (own, other) => own.is_none() && other.is_none(),
+        };
+        let same_token = match (&self.cancellation_token, &other.cancellation_token) {
+            (Some(own), Some(other)) => Arc::ptr_eq(&own.is_cancelled, &other.is_cancelled),
+            (own, other) => own.is_none() && other.is_none(),
+        };
+
+        same_observer && same_token
+    }
+}
+
+impl Eq for ProgressMonitor {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use assert2::assert;
+    use assert2::let_assert;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_utils::low_security_stark;
+    use crate::test_utils::ProgramAndInput;
+    use crate::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+
+    fn claim_and_aet() -> (Claim, crate::aet::AlgebraicExecutionTrace) {
+        let program = ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+            .with_input(bfe_vec![25]);
+        let claim = program.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program.program,
+            program.public_input,
+            program.non_determinism,
+        )
+        .unwrap();
+
+        (claim, aet)
+    }
+
+    fn prover() -> Prover {
+        Prover::new(low_security_stark(
+            DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS,
+        ))
+    }
+
+    #[test]
+    fn estimated_fraction_done_increases_monotonically_from_0_to_1() {
+        let num_rounds = 5;
+        let fri_rounds = (0..num_rounds).map(|round| ProvingPhase::FriRound { round, num_rounds });
+        let phases = [
+            ProvingPhase::MainTable,
+            ProvingPhase::MainTableLowDegreeExtension,
+            ProvingPhase::MainTableMerkleTree,
+            ProvingPhase::AuxTable,
+            ProvingPhase::AuxTableLowDegreeExtension,
+            ProvingPhase::AuxTableMerkleTree,
+            ProvingPhase::Quotient,
+            ProvingPhase::OutOfDomainRows,
+            ProvingPhase::Deep,
+        ]
+        .into_iter()
+        .chain(fri_rounds)
+        .chain([ProvingPhase::OpenTraceRows, ProvingPhase::Done]);
+
+        let fractions = phases.map(ProvingPhase::fraction_done).collect_vec();
+        assert!(0.0 == fractions[0]);
+        assert!(1.0 == *fractions.last().unwrap());
+        for (earlier, later) in fractions.into_iter().tuple_windows() {
+            assert!(earlier < later);
+        }
+    }
+
+    #[test]
+    fn observer_is_notified_of_every_phase_in_order() {
+        let (claim, aet) = claim_and_aet();
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded_events = Arc::clone(&events);
+        let observer = move |event| recorded_events.lock().unwrap().push(event);
+        prover()
+            .with_progress_observer(observer)
+            .prove(&claim, &aet)
+            .unwrap();
+
+        let events = events.lock().unwrap();
+        let_assert!(Some(first) = events.first());
+        let_assert!(Some(last) = events.last());
+        assert!(ProvingPhase::MainTable == first.phase);
+        assert!(ProvingPhase::Done == last.phase);
+        assert!(events
+            .iter()
+            .any(|event| event.phase == ProvingPhase::Quotient));
+
+        let num_fri_rounds = events
+            .iter()
+            .filter(|event| matches!(event.phase, ProvingPhase::FriRound { .. }))
+            .count();
+        assert!(num_fri_rounds > 0);
+
+        for (earlier, later) in events.iter().tuple_windows() {
+            assert!(earlier.fraction_done < later.fraction_done);
+        }
+    }
+
+    #[test]
+    fn proving_with_cancelled_token_fails() {
+        let (claim, aet) = claim_and_aet();
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let_assert!(
+            Err(ProvingError::Cancelled) =
+                prover().with_cancellation_token(token).prove(&claim, &aet)
+        );
+    }
+
+    #[test]
+    fn cancelling_during_proving_stops_prover_in_next_phase() {
+        let (claim, aet) = claim_and_aet();
+        let token = CancellationToken::new();
+        let observer_token = token.clone();
+        let last_phase = Arc::new(Mutex::new(None));
+        let observed_last_phase = Arc::clone(&last_phase);
+        let observer = move |event: ProgressEvent| {
+            *observed_last_phase.lock().unwrap() = Some(event.phase);
+            if event.phase == ProvingPhase::Quotient {
+                observer_token.cancel();
+            }
+        };
+
+        let_assert!(
+            Err(ProvingError::Cancelled) = prover()
+                .with_progress_observer(observer)
+                .with_cancellation_token(token)
+                .prove(&claim, &aet)
+        );
+        assert!(Some(ProvingPhase::Quotient) == *last_phase.lock().unwrap());
+    }
+
+    #[test]
+    fn cancelling_one_clone_of_token_cancels_all() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn uncancelled_prover_with_observer_produces_same_proof_as_plain_prover() {
+        let (claim, aet) = claim_and_aet();
+        let seed = [42; 32];
+        let plain_proof = prover()
+            .set_randomness_seed_which_may_break_zero_knowledge(seed)
+            .prove(&claim, &aet)
+            .unwrap();
+        let observed_proof = prover()
+            .set_randomness_seed_which_may_break_zero_knowledge(seed)
+            .with_progress_observer(|_| ())
+            .with_cancellation_token(CancellationToken::new())
+            .prove(&claim, &aet)
+            .unwrap();
+        assert!(plain_proof == observed_proof);
+    }
+}
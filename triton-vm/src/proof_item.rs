@@ -21,6 +21,10 @@ pub struct FriResponse {
     pub auth_structure: AuthenticationStructure,
 
     /// The values of the opened leaves of the Merkle tree.
+    ///
+    /// For FRI rounds that get folded into a next round, these are the elements
+    /// folded together with the queried elements: `folding_factor - 1` many per
+    /// collinearity check, in order of the collinearity checks.
     pub revealed_leaves: Vec<XFieldElement>,
 }
 
@@ -112,6 +116,9 @@ proof_items!(
     FriCodeword(Vec<XFieldElement>) => false, try_into_fri_codeword,
     FriPolynomial(Polynomial<'static, XFieldElement>) => false, try_into_fri_polynomial,
     FriResponse(FriResponse) => false, try_into_fri_response,
+
+    // grinding is only effective if the proof of work alters the Fiat-Shamir state
+    ProofOfWork(u64) => true, try_into_proof_of_work,
 );
 
 #[cfg(test)]
@@ -126,7 +133,7 @@ pub(crate) mod tests {
 
     use crate::proof::Proof;
     use crate::proof_stream::ProofStream;
-    use crate::shared_tests::LeavedMerkleTreeTestData;
+    use crate::test_utils::LeavedMerkleTreeTestData;
 
     use super::*;
 
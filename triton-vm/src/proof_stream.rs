@@ -1,4 +1,5 @@
 use arbitrary::Arbitrary;
+use rayon::prelude::*;
 use twenty_first::prelude::*;
 
 use crate::error::ProofStreamError;
@@ -91,6 +92,45 @@ impl ProofStream {
     pub fn sample_scalars(&mut self, num_scalars: usize) -> Vec<XFieldElement> {
         self.sponge.sample_scalars(num_scalars)
     }
+
+    /// Search for the smallest nonce that, once [enqueued](Self::enqueue) as a
+    /// [proof of work](ProofItem::ProofOfWork), leaves the Fiat-Shamir state with
+    /// at least `num_bits` [leading zeros](Self::has_proof_of_work). Then, enqueue
+    /// that proof of work.
+    ///
+    /// The expected number of attempts is 2^`num_bits`.
+    pub fn grind(&mut self, num_bits: usize) {
+        const NONCES_PER_BATCH: u64 = 1 << 12;
+
+        let is_proof_of_work = |&nonce: &u64| {
+            let mut sponge = self.sponge.clone();
+            sponge.pad_and_absorb_all(&ProofItem::ProofOfWork(nonce).encode());
+            Self::num_leading_zeros(&sponge) >= num_bits
+        };
+
+        // Search batch by batch to find the smallest nonce, which keeps proofs
+        // deterministic, without searching far beyond it.
+        let nonce = (0..)
+            .step_by(NONCES_PER_BATCH as usize)
+            .find_map(|start| {
+                (start..start + NONCES_PER_BATCH)
+                    .into_par_iter()
+                    .find_first(is_proof_of_work)
+            })
+            .expect("a proof of work should exist for any reasonable number of bits");
+        self.enqueue(ProofItem::ProofOfWork(nonce));
+    }
+
+    /// Whether the Fiat-Shamir state has at least `num_bits` leading zeros. In
+    /// combination with a [proof of work](ProofItem::ProofOfWork), this proves
+    /// that the prover did the work of [grinding](Self::grind).
+    pub fn has_proof_of_work(&self, num_bits: usize) -> bool {
+        Self::num_leading_zeros(&self.sponge) >= num_bits
+    }
+
+    fn num_leading_zeros(sponge: &Tip5) -> usize {
+        sponge.state[0].value().leading_zeros() as usize
+    }
 }
 
 impl TryFrom<&Proof> for ProofStream {
@@ -128,10 +168,10 @@ mod tests {
 
     use crate::proof_item::FriResponse;
     use crate::proof_item::ProofItem;
-    use crate::shared_tests::LeavedMerkleTreeTestData;
     use crate::table::AuxiliaryRow;
     use crate::table::MainRow;
     use crate::table::QuotientSegments;
+    use crate::test_utils::LeavedMerkleTreeTestData;
 
     use super::*;
 
@@ -283,4 +323,31 @@ mod tests {
     fn encoded_length_of_prove_stream_is_not_known_at_compile_time() {
         assert!(ProofStream::static_length().is_none());
     }
+
+    #[proptest(cases = 20)]
+    fn grinding_finds_smallest_proof_of_work(
+        #[strategy(arb())] root: Digest,
+        #[strategy(0_usize..12)] num_bits: usize,
+    ) {
+        let mut proof_stream = ProofStream::new();
+        proof_stream.enqueue(ProofItem::MerkleRoot(root));
+        proof_stream.grind(num_bits);
+        assert!(proof_stream.has_proof_of_work(num_bits));
+
+        let mut proof_stream = ProofStream::try_from(&Proof::from(proof_stream)).unwrap();
+        proof_stream.dequeue().unwrap();
+        let before_proof_of_work = proof_stream.clone();
+        let nonce = proof_stream
+            .dequeue()
+            .unwrap()
+            .try_into_proof_of_work()
+            .unwrap();
+        assert!(proof_stream.has_proof_of_work(num_bits));
+
+        for smaller_nonce in 0..nonce {
+            let mut proof_stream = before_proof_of_work.clone();
+            proof_stream.alter_fiat_shamir_state_with(&ProofItem::ProofOfWork(smaller_nonce));
+            assert!(!proof_stream.has_proof_of_work(num_bits));
+        }
+    }
 }
@@ -0,0 +1,360 @@
+//! Unit tests for snippets of Triton assembly.
+//!
+//! A snippet is a sequence of instructions that is executed from its first
+//! instruction until it returns, like the body of a subroutine. It may contain
+//! further subroutines, which can end in any instruction. A [`SnippetTest`] runs a snippet from a given initial op stack,
+//! RAM, and input, and compares the final op stack, RAM, and output to
+//! expectations. Any mismatch is reported as a human-readable diff.
+//!
+//! ```
+//! # use triton_vm::prelude::*;
+//! # use triton_vm::snippet_test::SnippetTest;
+//! let subtract = triton_asm!(push -1 mul add return);
+//! SnippetTest::new(subtract)
+//!     .with_initial_stack(bfe_vec![7, 5])
+//!     .expect_stack(bfe_vec![2])
+//!     .assert_passes();
+//! ```
+//!
+//! The op stack of Triton VM always holds at least [`NUM_OP_STACK_REGISTERS`]
+//! elements. The stacks given to and expected from a snippet test sit on top of
+//! that many zeros. Like [`OpStack::stack`](isa::op_stack::OpStack::stack), they
+//! are listed bottom first, _i.e._, the last element is the top of the stack.
+
+use std::collections::HashMap;
+
+use isa::instruction::AnInstruction;
+use isa::op_stack::NUM_OP_STACK_REGISTERS;
+use itertools::Itertools;
+
+use crate::error::SnippetTestError;
+use crate::prelude::*;
+
+/// Run a snippet of Triton assembly from a given initial state and compare the
+/// final state to expectations. See the [module-level documentation](self) for
+/// an example.
+///
+/// Only those parts of the final state for which an expectation is set are
+/// checked.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SnippetTest {
+    snippet: Vec<LabelledInstruction>,
+    initial_stack: Vec<BFieldElement>,
+    public_input: PublicInput,
+    non_determinism: NonDeterminism,
+    expected_stack: Option<Vec<BFieldElement>>,
+    expected_ram: Option<HashMap<BFieldElement, BFieldElement>>,
+    expected_output: Option<Vec<BFieldElement>>,
+}
+
+impl SnippetTest {
+    /// A test for the given snippet, which must [return](Self::run) to its caller.
+    /// Initially, the stack holds no elements besides the zeros at its bottom,
+    /// RAM is all zeros, and there is no input.
+    pub fn new(snippet: Vec<LabelledInstruction>) -> Self {
+        Self {
+            snippet,
+            initial_stack: vec![],
+            public_input: PublicInput::default(),
+            non_determinism: NonDeterminism::default(),
+            expected_stack: None,
+            expected_ram: None,
+            expected_output: None,
+        }
+    }
+
+    /// The elements on the stack when the snippet starts, bottom first.
+    #[must_use]
+    pub fn with_initial_stack(mut self, stack: Vec<BFieldElement>) -> Self {
+        self.initial_stack = stack;
+        self
+    }
+
+    /// The content of RAM when the snippet starts. Overwrites the RAM of any
+    /// previously set [non-determinism](Self::with_non_determinism).
+    #[must_use]
+    pub fn with_initial_ram<H: Into<HashMap<BFieldElement, BFieldElement>>>(
+        mut self,
+        ram: H,
+    ) -> Self {
+        self.non_determinism.ram = ram.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_public_input(mut self, public_input: PublicInput) -> Self {
+        self.public_input = public_input;
+        self
+    }
+
+    /// The secret input of the snippet, including the initial content of RAM.
+    #[must_use]
+    pub fn with_non_determinism(mut self, non_determinism: NonDeterminism) -> Self {
+        self.non_determinism = non_determinism;
+        self
+    }
+
+    /// The elements expected on the stack when the snippet returns, bottom first.
+    #[must_use]
+    pub fn expect_stack(mut self, stack: Vec<BFieldElement>) -> Self {
+        self.expected_stack = Some(stack);
+        self
+    }
+
+    /// The content of RAM expected when the snippet returns. Addresses that are
+    /// not listed are expected to hold zero.
+    #[must_use]
+    pub fn expect_ram<H: Into<HashMap<BFieldElement, BFieldElement>>>(mut self, ram: H) -> Self {
+        self.expected_ram = Some(ram.into());
+        self
+    }
+
+    /// The public output expected when the snippet returns.
+    #[must_use]
+    pub fn expect_output(mut self, output: Vec<BFieldElement>) -> Self {
+        self.expected_output = Some(output);
+        self
+    }
+
+    /// Run the snippet and check all set expectations. Returns the final
+    /// [`VMState`] if the snippet returns and all expectations are met.
+    ///
+    /// The snippet is run as if it had been called: returning from it lands on an
+    /// appended `halt`. If the VM halts in any other way, for example because the
+    /// snippet executes `halt` or runs past its last instruction, the snippet is
+    /// [missing a return](SnippetTestError::MissingReturn).
+    pub fn run(self) -> Result<VMState, SnippetTestError> {
+        let halt = LabelledInstruction::Instruction(AnInstruction::Halt);
+        let program = Program::new(&[self.snippet, vec![halt]].concat());
+        let halt_address = program.len_bwords() - 1;
+
+        let mut state = VMState::new(program, self.public_input, self.non_determinism);
+        state.op_stack.stack = with_stack_bottom(self.initial_stack);
+        state.jump_stack.push((bfe!(halt_address), bfe!(0)));
+        if let Err(err) = state.run() {
+            return Err(VMError::new(err, state).into());
+        }
+
+        // Only returning from the snippet pops the jump stack's initial entry.
+        if !state.jump_stack.is_empty() {
+            return Err(SnippetTestError::MissingReturn);
+        }
+
+        let mut diff = String::new();
+        if let Some(expected_stack) = self.expected_stack {
+            let expected_stack = with_stack_bottom(expected_stack);
+            let top_first = |stack: &[_]| stack.iter().rev().copied().collect_vec();
+            let rows = sequence_diff_rows(
+                &top_first(&expected_stack),
+                &top_first(&state.op_stack.stack),
+                |i| format!("st{i}"),
+            );
+            diff += &diff_table("op stack, top first", rows);
+        }
+        if let Some(expected_ram) = self.expected_ram {
+            diff += &diff_table("RAM", ram_diff_rows(&expected_ram, &state.ram));
+        }
+        if let Some(expected_output) = self.expected_output {
+            let rows = sequence_diff_rows(&expected_output, &state.public_output, |i| {
+                format!("output[{i}]")
+            });
+            diff += &diff_table("public output", rows);
+        }
+
+        if !diff.is_empty() {
+            return Err(SnippetTestError::Mismatch(diff));
+        }
+        Ok(state)
+    }
+
+    /// Like [`run`](Self::run), but panics with a readable explanation if the
+    /// snippet does not behave as expected.
+    #[track_caller]
+    pub fn assert_passes(self) {
+        if let Err(err) = self.run() {
+            panic!("{err}");
+        }
+    }
+}
+
+fn with_stack_bottom(stack: Vec<BFieldElement>) -> Vec<BFieldElement> {
+    [bfe_vec![0; NUM_OP_STACK_REGISTERS], stack].concat()
+}
+
+/// One row of a diff: the position, the expected value, and the actual value.
+/// A missing value means that the position does not exist.
+type DiffRow = (String, Option<BFieldElement>, Option<BFieldElement>);
+
+fn sequence_diff_rows(
+    expected: &[BFieldElement],
+    actual: &[BFieldElement],
+    position_name: impl Fn(usize) -> String,
+) -> Vec<DiffRow> {
+    if expected == actual {
+        return vec![];
+    }
+
+    (0..expected.len().max(actual.len()))
+        .map(|i| {
+            (
+                position_name(i),
+                expected.get(i).copied(),
+                actual.get(i).copied(),
+            )
+        })
+        .collect()
+}
+
+/// Only the addresses whose values differ, since RAM is usually sparse.
+fn ram_diff_rows(
+    expected: &HashMap<BFieldElement, BFieldElement>,
+    actual: &HashMap<BFieldElement, BFieldElement>,
+) -> Vec<DiffRow> {
+    let read = |ram: &HashMap<_, _>, address| ram.get(&address).copied().unwrap_or_default();
+    expected
+        .keys()
+        .chain(actual.keys())
+        .unique()
+        .filter(|&&address| read(expected, address) != read(actual, address))
+        .sorted_by_key(|address| address.value())
+        .map(|&address| {
+            let position = format!("address {address}");
+            (
+                position,
+                Some(read(expected, address)),
+                Some(read(actual, address)),
+            )
+        })
+        .collect()
+}
+
+/// Render the rows as a table, marking those that differ. Empty if there are no
+/// rows.
+fn diff_table(title: &str, rows: Vec<DiffRow>) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    let show =
+        |value: Option<BFieldElement>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let rows = rows
+        .into_iter()
+        .map(|(position, expected, actual)| {
+            (position, show(expected), show(actual), expected != actual)
+        })
+        .collect_vec();
+    let position_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+    let expected_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0).max(8);
+    let actual_width = rows.iter().map(|row| row.2.len()).max().unwrap_or(0).max(6);
+
+    let header = format!(
+        "  {:position_width$}  {:>expected_width$}  {:>actual_width$}",
+        "", "expected", "actual"
+    );
+    let lines = rows.into_iter().map(|(position, expected, actual, differs)| {
+        let marker = if differs { "  <--" } else { "" };
+        format!("  {position:position_width$}  {expected:>expected_width$}  {actual:>actual_width$}{marker}")
+    });
+    let table = [format!("{title}:"), header]
+        .into_iter()
+        .chain(lines)
+        .join("\n");
+    table + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use assert2::let_assert;
+
+    use super::*;
+
+    #[test]
+    fn snippet_meeting_all_expectations_passes() {
+        let snippet = triton_asm! {
+            read_io 1 push 2 mul     // _ 2·input
+            dup 0 write_io 1         // _ 2·input
+            push 100 write_mem 1 pop 1
+            return
+        };
+        let final_state = SnippetTest::new(snippet)
+            .with_initial_stack(bfe_vec![42])
+            .with_public_input(PublicInput::new(bfe_vec![21]))
+            .expect_stack(bfe_vec![42])
+            .expect_ram([(bfe!(100), bfe!(42))])
+            .expect_output(bfe_vec![42])
+            .run()
+            .unwrap();
+        assert!(final_state.halting);
+    }
+
+    #[test]
+    fn initial_ram_is_readable() {
+        let snippet = triton_asm!(push 7 read_mem 1 pop 1 return);
+        SnippetTest::new(snippet)
+            .with_initial_ram([(bfe!(7), bfe!(3))])
+            .expect_stack(bfe_vec![3])
+            .expect_ram([(bfe!(7), bfe!(3))])
+            .assert_passes();
+    }
+
+    #[test]
+    fn mismatch_is_reported_as_diff() {
+        let snippet = triton_asm!(push 1 push 2 push 3 write_io 1 return);
+        let_assert!(
+            Err(SnippetTestError::Mismatch(diff)) = SnippetTest::new(snippet)
+                .expect_stack(bfe_vec![1, 3])
+                .expect_ram([(bfe!(5), bfe!(5))])
+                .expect_output(bfe_vec![3])
+                .run()
+        );
+
+        let differing_lines = diff
+            .lines()
+            .filter(|line| line.ends_with("<--"))
+            .collect_vec();
+        let_assert!([st0, ram] = differing_lines.as_slice());
+        assert!(st0.trim_start().starts_with("st0"));
+        assert!(st0.contains('3') && st0.contains('2'));
+        assert!(ram.trim_start().starts_with("address 5"));
+        assert!(!diff.contains("public output"));
+    }
+
+    #[test]
+    fn snippet_without_return_is_rejected() {
+        for snippet in [
+            triton_asm!(push 1 halt),
+            triton_asm!(push 1),
+            triton_asm!(push 0 skiz return halt),
+        ] {
+            let_assert!(Err(SnippetTestError::MissingReturn) = SnippetTest::new(snippet).run());
+        }
+    }
+
+    #[test]
+    fn snippet_may_end_in_subroutine_that_does_not_return() {
+        let snippet = triton_asm!(
+            push 3 call loop pop 1 return
+            loop: dup 0 push 0 eq skiz return addi -1 recurse
+        );
+        SnippetTest::new(snippet)
+            .expect_stack(vec![])
+            .assert_passes();
+    }
+
+    #[test]
+    fn crash_of_snippet_is_reported() {
+        let snippet = triton_asm!(push 0 assert return);
+        let_assert!(Err(SnippetTestError::Crash(err)) = SnippetTest::new(snippet).run());
+        let_assert!(InstructionError::AssertionFailed(_) = err.source);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match expectation")]
+    fn failing_assertion_panics() {
+        let snippet = triton_asm!(push 1 return);
+        SnippetTest::new(snippet)
+            .expect_stack(bfe_vec![2])
+            .assert_passes();
+    }
+}
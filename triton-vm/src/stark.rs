@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use std::io;
 use std::ops::Mul;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use arbitrary::Arbitrary;
 use arbitrary::Unstructured;
@@ -13,6 +17,8 @@ use rand::prelude::StdRng;
 use rand::random;
 use rand_core::SeedableRng;
 use rayon::prelude::*;
+use rayon::ThreadPool;
+use rayon::ThreadPoolBuilder;
 use serde::Deserialize;
 use serde::Serialize;
 use twenty_first::math::ntt::intt;
@@ -24,18 +30,30 @@ use twenty_first::prelude::*;
 use crate::aet::AlgebraicExecutionTrace;
 use crate::arithmetic_domain::ArithmeticDomain;
 use crate::challenges::Challenges;
+use crate::checkpoint::Checkpoints;
+use crate::checkpoint::Stage;
+use crate::config;
+use crate::config::CacheDecision;
+use crate::error::BatchVerificationError;
+use crate::error::FriProvingError;
 use crate::error::ProvingError;
 use crate::error::VerificationError;
 use crate::fri;
 use crate::fri::Fri;
+use crate::memory_plan::MemoryPlan;
 use crate::ndarray_helper;
 use crate::profiler::profiler;
+use crate::progress::CancellationToken;
+use crate::progress::ProgressMonitor;
+use crate::progress::ProgressObserver;
+use crate::progress::ProvingPhase;
 use crate::proof::Claim;
 use crate::proof::Proof;
 use crate::proof_item::ProofItem;
 use crate::proof_stream::ProofStream;
 use crate::table::auxiliary_table::Evaluable;
-use crate::table::master_table::all_quotients_combined;
+use crate::table::master_table::all_quotients_combined_unless_cancelled;
+use crate::table::master_table::all_quotients_combined_with_next_rows;
 use crate::table::master_table::interpolant_degree;
 use crate::table::master_table::max_degree_with_origin;
 use crate::table::master_table::randomized_trace_len;
@@ -80,6 +98,27 @@ pub struct Stark {
 
     /// The number of collinearity checks to perform in [FRI](Fri).
     pub num_collinearity_checks: usize,
+
+    /// The factor by which every round of [FRI](Fri) shrinks the codeword. Must
+    /// be a power of 2. Larger folding factors mean fewer FRI rounds, and thus
+    /// fewer Merkle trees and smaller authentication structures, but more
+    /// revealed codeword elements per round.
+    ///
+    /// See also [`Stark::with_fri_folding_factor`].
+    #[serde(default = "Stark::default_fri_folding_factor")]
+    pub fri_folding_factor: usize,
+
+    /// The number of bits of proof of work the prover has to [grind][grind] for
+    /// before the collinearity checks are sampled. Every bit of proof of work
+    /// increases the cost of an attack as much as one bit of security from
+    /// collinearity checks does, but costs the prover 2^`num_grinding_bits`
+    /// hashes.
+    ///
+    /// See also [`Stark::with_grinding_bits`].
+    ///
+    /// [grind]: ProofStream::grind
+    #[serde(default)]
+    pub num_grinding_bits: usize,
 }
 
 /// The prover for Triton VM's [zk-STARK](Stark). The core method is
@@ -90,8 +129,18 @@ pub struct Stark {
 /// prover randomness. To prevent accidental randomness re-use, the [`Prover`]
 /// does not implement [`Clone`].
 ///
+/// The prover can [report its progress][progress] and can be
+/// [cancelled][cancel]. By default, it uses rayon's global thread pool. It can
+/// instead use a [dedicated thread pool][pool] or a [limited number of
+/// threads][threads]. It can [persist checkpoints][checkpoints] from which an
+/// interrupted proof can be resumed.
+///
 /// [seed]: Prover::set_randomness_seed_which_may_break_zero_knowledge
-#[expect(missing_copy_implementations)]
+/// [progress]: Prover::with_progress_observer
+/// [cancel]: Prover::with_cancellation_token
+/// [pool]: Prover::with_thread_pool
+/// [threads]: Prover::with_max_num_threads
+/// [checkpoints]: Prover::with_checkpoint_directory
 #[derive(Debug, Eq, PartialEq, Arbitrary)]
 pub struct Prover {
     parameters: Stark,
@@ -102,6 +151,29 @@ pub struct Prover {
     /// uniformly at random, and independently of all other input. No information
     /// about it must reach the verifier.
     randomness_seed: <StdRng as SeedableRng>::Seed,
+
+    #[arbitrary(default)]
+    progress_monitor: ProgressMonitor,
+
+    #[arbitrary(default)]
+    parallelism: Parallelism,
+
+    #[arbitrary(default)]
+    checkpoint_directory: Option<PathBuf>,
+}
+
+/// The threads a [`Prover`] uses for its parallel work.
+#[derive(Debug, Clone, Default)]
+enum Parallelism {
+    /// Rayon's global thread pool.
+    #[default]
+    GlobalThreadPool,
+
+    /// A thread pool provided by the caller.
+    ThreadPool(Arc<ThreadPool>),
+
+    /// A thread pool with the given number of threads, created for every proof.
+    MaxNumThreads(usize),
 }
 
 /// The verifier for Triton VM's [zs-STARK](Stark). The core method is
@@ -112,6 +184,20 @@ pub struct Verifier {
     parameters: Stark,
 }
 
+/// Two thread pools are equal if they are the same pool.
+impl PartialEq for Parallelism {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::GlobalThreadPool, Self::GlobalThreadPool) => true,
+            (Self::ThreadPool(own), Self::ThreadPool(other)) => Arc::ptr_eq(own, other),
+            (Self::MaxNumThreads(own), Self::MaxNumThreads(other)) => own == other,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Parallelism {}
+
 impl Prover {
     /// A [`Prover`] with a sane [randomness seed][seed].
     ///
@@ -120,6 +206,9 @@ impl Prover {
         Self {
             parameters,
             randomness_seed: random(),
+            progress_monitor: ProgressMonitor::default(),
+            parallelism: Parallelism::default(),
+            checkpoint_directory: None,
         }
     }
 
@@ -149,12 +238,97 @@ impl Prover {
         self
     }
 
+    /// Notify the given observer whenever [proving](Self::prove) enters a new
+    /// [phase](ProvingPhase). See also the [`progress`](crate::progress) module.
+    #[must_use]
+    pub fn with_progress_observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.progress_monitor.set_observer(Arc::new(observer));
+        self
+    }
+
+    /// Stop [proving](Self::prove) with [`ProvingError::Cancelled`] once the given
+    /// token is [cancelled](CancellationToken::cancel).
+    #[must_use]
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.progress_monitor.set_cancellation_token(token);
+        self
+    }
+
+    /// Perform all parallel work of [proving](Self::prove) in the given thread
+    /// pool instead of rayon's global one. Several provers can share one pool.
+    /// The [profiler](crate::profiler) of the calling thread keeps recording.
+    ///
+    /// The [`VM`](crate::vm::VM) has no such option: it executes programs on the
+    /// calling thread, without any parallel work. To bound the CPU share of many
+    /// executions, run them on threads of the desired pool.
+    #[must_use]
+    pub fn with_thread_pool(mut self, thread_pool: Arc<ThreadPool>) -> Self {
+        self.parallelism = Parallelism::ThreadPool(thread_pool);
+        self
+    }
+
+    /// Perform all parallel work of [proving](Self::prove) using at most the given
+    /// number of threads. Creates a dedicated thread pool for every proof. If the
+    /// number is 0, rayon picks the number of threads, like for its global pool.
+    #[must_use]
+    pub fn with_max_num_threads(mut self, num_threads: usize) -> Self {
+        self.parallelism = Parallelism::MaxNumThreads(num_threads);
+        self
+    }
+
+    /// Persist intermediate results of [proving](Self::prove) in the given
+    /// directory after every major commitment: to the main table, to the
+    /// auxiliary table, to the quotient segments, and to every round of
+    /// [FRI](Fri). If the directory holds checkpoints of an earlier, interrupted
+    /// attempt at the same proof, proving resumes after the latest of them.
+    ///
+    /// The resumed proof is identical to an uninterrupted one if the claim, the
+    /// algebraic execution trace, the [parameters](Stark), and the
+    /// [randomness seed][seed] are the same. In particular, the seed has to be set
+    /// explicitly. Checkpoints are matched to proofs by claim, parameters, seed,
+    /// and padded main trace; checkpoints of other proofs are ignored and
+    /// eventually overwritten.
+    /// Once the proof is done, its checkpoints are deleted.
+    ///
+    /// Checkpoints contain information about the secret parts of the execution
+    /// trace. Store them accordingly.
+    ///
+    /// [seed]: Self::set_randomness_seed_which_may_break_zero_knowledge
+    #[must_use]
+    pub fn with_checkpoint_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.checkpoint_directory = Some(directory.into());
+        self
+    }
+
     /// See also [`Stark::prove`].
     pub fn prove(
         self,
         claim: &Claim,
         aet: &AlgebraicExecutionTrace,
     ) -> Result<Proof, ProvingError> {
+        let prove = || self.prove_in_current_thread_pool(claim, aet);
+        match &self.parallelism {
+            Parallelism::GlobalThreadPool => prove(),
+            Parallelism::ThreadPool(thread_pool) => config::install(thread_pool, prove),
+            &Parallelism::MaxNumThreads(num_threads) => {
+                let thread_pool = ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .map_err(|err| ProvingError::ThreadPoolCreationError(err.to_string()))?;
+                config::install(&thread_pool, prove)
+            }
+        }
+    }
+
+    fn prove_in_current_thread_pool(
+        &self,
+        claim: &Claim,
+        aet: &AlgebraicExecutionTrace,
+    ) -> Result<Proof, ProvingError> {
+        let progress = &self.progress_monitor;
+        let cancellation_token = progress.cancellation_token();
+        progress.enter(ProvingPhase::MainTable)?;
+
         profiler!(start "Fiat-Shamir: claim" ("hash"));
         let mut proof_stream = ProofStream::new();
         proof_stream.alter_fiat_shamir_state_with(claim);
@@ -165,6 +339,12 @@ impl Prover {
         let max_degree = self.parameters.max_degree(padded_height);
         let fri = self.parameters.fri(padded_height)?;
         let quotient_domain = Self::quotient_domain(fri.domain, max_degree)?;
+        let memory_plan = MemoryPlan::new(
+            padded_height,
+            self.parameters.num_trace_randomizers,
+            quotient_domain,
+            fri.domain,
+        )?;
         proof_stream.enqueue(ProofItem::Log2PaddedHeight(padded_height.ilog2()));
         profiler!(stop "derive additional parameters");
 
@@ -183,10 +363,43 @@ impl Prover {
         master_main_table.pad();
         profiler!(stop "pad");
 
-        master_main_table.maybe_low_degree_extend_all_columns();
+        profiler!(start "checkpoints");
+        let checkpoints = Checkpoints::new(
+            self.checkpoint_directory.as_deref(),
+            claim,
+            self.parameters,
+            self.randomness_seed,
+            master_main_table.trace_table(),
+            fri.num_rounds(),
+        );
+        let checkpoint_error = |err: io::Error| ProvingError::CheckpointError(err.kind());
+
+        // Restored quotient segments make the low-degree extended tables obsolete.
+        let cache_lde_trace = if checkpoints.is_restorable(Stage::QuotientSegments) {
+            Some(CacheDecision::NoCache)
+        } else {
+            memory_plan.cache_lde_trace
+        };
+        profiler!(stop "checkpoints");
+
+        progress.enter(ProvingPhase::MainTableLowDegreeExtension)?;
+        master_main_table
+            .maybe_low_degree_extend_all_columns_subject_to(cache_lde_trace, &cancellation_token)?;
 
+        progress.enter(ProvingPhase::MainTableMerkleTree)?;
         profiler!(start "Merkle tree");
-        let main_merkle_tree = master_main_table.merkle_tree();
+        let restored_main_merkle_tree =
+            checkpoints.restore_merkle_tree(Stage::MainMerkleTree, fri.domain.length);
+        let main_merkle_tree = if let Some(merkle_tree) = restored_main_merkle_tree {
+            merkle_tree
+        } else {
+            let merkle_tree =
+                master_main_table.merkle_tree_unless_cancelled(&cancellation_token)?;
+            checkpoints
+                .save_merkle_tree(Stage::MainMerkleTree, &merkle_tree)
+                .map_err(checkpoint_error)?;
+            merkle_tree
+        };
         profiler!(stop "Merkle tree");
 
         profiler!(start "Fiat-Shamir" ("hash"));
@@ -195,16 +408,30 @@ impl Prover {
         let challenges = Challenges::new(challenges, claim);
         profiler!(stop "Fiat-Shamir");
 
+        progress.enter(ProvingPhase::AuxTable)?;
         profiler!(start "extend" ("gen"));
         let mut master_aux_table = master_main_table.extend(&challenges);
         profiler!(stop "extend");
         profiler!(stop "main tables");
 
         profiler!(start "aux tables");
-        master_aux_table.maybe_low_degree_extend_all_columns();
+        progress.enter(ProvingPhase::AuxTableLowDegreeExtension)?;
+        master_aux_table
+            .maybe_low_degree_extend_all_columns_subject_to(cache_lde_trace, &cancellation_token)?;
 
+        progress.enter(ProvingPhase::AuxTableMerkleTree)?;
         profiler!(start "Merkle tree");
-        let aux_merkle_tree = master_aux_table.merkle_tree();
+        let restored_aux_merkle_tree =
+            checkpoints.restore_merkle_tree(Stage::AuxMerkleTree, fri.domain.length);
+        let aux_merkle_tree = if let Some(merkle_tree) = restored_aux_merkle_tree {
+            merkle_tree
+        } else {
+            let merkle_tree = master_aux_table.merkle_tree_unless_cancelled(&cancellation_token)?;
+            checkpoints
+                .save_merkle_tree(Stage::AuxMerkleTree, &merkle_tree)
+                .map_err(checkpoint_error)?;
+            merkle_tree
+        };
         profiler!(stop "Merkle tree");
 
         profiler!(start "Fiat-Shamir" ("hash"));
@@ -216,27 +443,43 @@ impl Prover {
         profiler!(stop "Fiat-Shamir");
         profiler!(stop "aux tables");
 
+        progress.enter(ProvingPhase::Quotient)?;
+        let restored_quotient_segments = checkpoints.restore_quotient_segments(fri.domain.length);
         let (fri_domain_quotient_segment_codewords, quotient_segment_polynomials) =
-            Self::compute_quotient_segments(
-                &mut master_main_table,
-                &mut master_aux_table,
-                quotient_domain,
-                &challenges,
-                &quotient_combination_weights,
-            );
+            if let Some(quotient_segments) = restored_quotient_segments {
+                quotient_segments
+            } else {
+                let (codewords, polynomials) = Self::compute_quotient_segments(
+                    &mut master_main_table,
+                    &mut master_aux_table,
+                    quotient_domain,
+                    &challenges,
+                    &quotient_combination_weights,
+                    progress,
+                    &memory_plan,
+                )?;
+                checkpoints
+                    .save_quotient_segments(&codewords, &polynomials)
+                    .map_err(checkpoint_error)?;
+                (codewords, polynomials)
+            };
 
         profiler!(start "hash rows of quotient segments" ("hash"));
         let interpret_xfe_as_bfes = |xfe: &XFieldElement| xfe.coefficients.to_vec();
-        let hash_row = |row: ArrayView1<_>| {
+        let hash_row = |row: ArrayView1<_>| -> Result<_, ProvingError> {
+            cancellation_token.check()?;
             let row_as_bfes = row.iter().map(interpret_xfe_as_bfes).concat();
-            Tip5::hash_varlen(&row_as_bfes)
+            Ok(Tip5::hash_varlen(&row_as_bfes))
         };
         let quotient_segments_rows = fri_domain_quotient_segment_codewords
             .axis_iter(Axis(0))
             .into_par_iter();
-        let fri_domain_quotient_segment_codewords_digests =
-            quotient_segments_rows.map(hash_row).collect::<Vec<_>>();
+        let fri_domain_quotient_segment_codewords_digests = quotient_segments_rows
+            .map(hash_row)
+            .collect::<Result<Vec<_>, _>>();
         profiler!(stop "hash rows of quotient segments");
+        let fri_domain_quotient_segment_codewords_digests =
+            fri_domain_quotient_segment_codewords_digests?;
         profiler!(start "Merkle tree" ("hash"));
         let quot_merkle_tree =
             MerkleTree::new::<CpuParallel>(&fri_domain_quotient_segment_codewords_digests)?;
@@ -246,6 +489,7 @@ impl Prover {
 
         debug_assert_eq!(fri.domain.length, quot_merkle_tree.num_leafs());
 
+        progress.enter(ProvingPhase::OutOfDomainRows)?;
         profiler!(start "out-of-domain rows");
         let trace_domain_generator = master_main_table.trace_domain().generator;
         let out_of_domain_point_curr_row = proof_stream.sample_scalars(1)[0];
@@ -283,122 +527,147 @@ impl Prover {
         let weights = LinearCombinationWeights::sample(&mut proof_stream);
         profiler!(stop "Fiat-Shamir");
 
-        let fri_domain_is_short_domain = fri.domain.length <= quotient_domain.length;
-        let short_domain = if fri_domain_is_short_domain {
-            fri.domain
+        progress.enter(ProvingPhase::Deep)?;
+        let restored_fri_combination_codeword =
+            checkpoints.restore_fri_codeword(0, fri.domain.length);
+        let fri_combination_codeword = if let Some(codeword) = restored_fri_combination_codeword {
+            codeword
         } else {
-            quotient_domain
-        };
+            let fri_domain_is_short_domain = fri.domain.length <= quotient_domain.length;
+            let short_domain = if fri_domain_is_short_domain {
+                fri.domain
+            } else {
+                quotient_domain
+            };
 
-        profiler!(start "linear combination");
-        profiler!(start "main" ("CC"));
-        let main_combination_poly = master_main_table.weighted_sum_of_columns(weights.main);
-        profiler!(stop "main");
-
-        profiler!(start "aux" ("CC"));
-        let aux_combination_poly = master_aux_table.weighted_sum_of_columns(weights.aux);
-        profiler!(stop "aux");
-        let main_and_aux_combination_polynomial = main_combination_poly + aux_combination_poly;
-        let main_and_aux_codeword = short_domain.evaluate(&main_and_aux_combination_polynomial);
-
-        profiler!(start "quotient" ("CC"));
-        let quotient_segments_combination_polynomial = quotient_segment_polynomials
-            .into_iter()
-            .zip_eq(weights.quot_segments)
-            .fold(Polynomial::zero(), |acc, (poly, w)| acc + poly * w);
-        let quotient_segments_combination_codeword =
-            short_domain.evaluate(&quotient_segments_combination_polynomial);
-        profiler!(stop "quotient");
+            profiler!(start "linear combination");
+            profiler!(start "main" ("CC"));
+            let main_combination_poly = master_main_table.weighted_sum_of_columns(weights.main);
+            profiler!(stop "main");
 
-        profiler!(stop "linear combination");
+            profiler!(start "aux" ("CC"));
+            let aux_combination_poly = master_aux_table.weighted_sum_of_columns(weights.aux);
+            profiler!(stop "aux");
+            let main_and_aux_combination_polynomial = main_combination_poly + aux_combination_poly;
+            let main_and_aux_codeword = short_domain.evaluate(&main_and_aux_combination_polynomial);
 
-        profiler!(start "DEEP");
-        // There are (at least) two possible ways to perform the DEEP update.
-        // 1. The one used here, where main & aux codewords are DEEP'd twice: once with the out-of-
-        //    domain point for the current row (i.e., α) and once using the out-of-domain point for
-        //    the next row (i.e., ω·α). The DEEP update's denominator is a degree-1 polynomial in
-        //    both cases, namely (ω^i - α) and (ω^i - ω·α) respectively.
-        // 2. One where the main & aux codewords are DEEP'd only once, using the degree-2 polynomial
-        //    (ω^i - α)·(ω^i - ω·α) as the denominator. This requires a linear interpolation in the
-        //    numerator: b(ω^i) - i((b(α), α) + (b(ω·α), ω·α))(w^i).
-        //
-        // In either case, the DEEP'd quotient polynomial is an additional summand for the
-        // combination codeword: (q(ω^i) - q(α)) / (ω^i - α).
-        // All (three or two) summands are weighted and summed to form the combination codeword.
-        // The weights are sampled through the Fiat-Shamir heuristic.
-        //
-        // Both approaches are sound. The first approach is more efficient, as it requires fewer
-        // operations.
-        profiler!(start "main&aux curr row");
-        let out_of_domain_curr_row_main_and_aux_value =
-            main_and_aux_combination_polynomial.evaluate(out_of_domain_point_curr_row);
-        let main_and_aux_curr_row_deep_codeword = Self::deep_codeword(
-            &main_and_aux_codeword,
-            short_domain,
-            out_of_domain_point_curr_row,
-            out_of_domain_curr_row_main_and_aux_value,
-        );
-        profiler!(stop "main&aux curr row");
-
-        profiler!(start "main&aux next row");
-        let out_of_domain_next_row_main_and_aux_value =
-            main_and_aux_combination_polynomial.evaluate(out_of_domain_point_next_row);
-        let main_and_aux_next_row_deep_codeword = Self::deep_codeword(
-            &main_and_aux_codeword,
-            short_domain,
-            out_of_domain_point_next_row,
-            out_of_domain_next_row_main_and_aux_value,
-        );
-        profiler!(stop "main&aux next row");
-
-        profiler!(start "segmented quotient");
-        let out_of_domain_curr_row_quot_segments_value = quotient_segments_combination_polynomial
-            .evaluate(out_of_domain_point_curr_row_pow_num_segments);
-        let quotient_segments_curr_row_deep_codeword = Self::deep_codeword(
-            &quotient_segments_combination_codeword,
-            short_domain,
-            out_of_domain_point_curr_row_pow_num_segments,
-            out_of_domain_curr_row_quot_segments_value,
-        );
-        profiler!(stop "segmented quotient");
-        profiler!(stop "DEEP");
-
-        profiler!(start "combined DEEP polynomial");
-        profiler!(start "sum" ("CC"));
-        let deep_codeword = [
-            main_and_aux_curr_row_deep_codeword,
-            main_and_aux_next_row_deep_codeword,
-            quotient_segments_curr_row_deep_codeword,
-        ]
-        .into_par_iter()
-        .zip_eq(weights.deep.as_slice().unwrap())
-        .map(|(codeword, &weight)| codeword.into_par_iter().map(|c| c * weight).collect())
-        .reduce(
-            || vec![XFieldElement::ZERO; short_domain.length],
-            |left, right| left.into_iter().zip(right).map(|(l, r)| l + r).collect(),
-        );
+            profiler!(start "quotient" ("CC"));
+            let quotient_segments_combination_polynomial = quotient_segment_polynomials
+                .into_iter()
+                .zip_eq(weights.quot_segments)
+                .fold(Polynomial::zero(), |acc, (poly, w)| acc + poly * w);
+            let quotient_segments_combination_codeword =
+                short_domain.evaluate(&quotient_segments_combination_polynomial);
+            profiler!(stop "quotient");
+
+            profiler!(stop "linear combination");
+
+            profiler!(start "DEEP");
+            // There are (at least) two possible ways to perform the DEEP update.
+            // 1. The one used here, where main & aux codewords are DEEP'd twice: once with the out-of-
+            //    domain point for the current row (i.e., α) and once using the out-of-domain point for
+            //    the next row (i.e., ω·α). The DEEP update's denominator is a degree-1 polynomial in
+            //    both cases, namely (ω^i - α) and (ω^i - ω·α) respectively.
+            // 2. One where the main & aux codewords are DEEP'd only once, using the degree-2 polynomial
+            //    (ω^i - α)·(ω^i - ω·α) as the denominator. This requires a linear interpolation in the
+            //    numerator: b(ω^i) - i((b(α), α) + (b(ω·α), ω·α))(w^i).
+            //
+            // In either case, the DEEP'd quotient polynomial is an additional summand for the
+            // combination codeword: (q(ω^i) - q(α)) / (ω^i - α).
+            // All (three or two) summands are weighted and summed to form the combination codeword.
+            // The weights are sampled through the Fiat-Shamir heuristic.
+            //
+            // Both approaches are sound. The first approach is more efficient, as it requires fewer
+            // operations.
+            profiler!(start "main&aux curr row");
+            let out_of_domain_curr_row_main_and_aux_value =
+                main_and_aux_combination_polynomial.evaluate(out_of_domain_point_curr_row);
+            let main_and_aux_curr_row_deep_codeword = Self::deep_codeword(
+                &main_and_aux_codeword,
+                short_domain,
+                out_of_domain_point_curr_row,
+                out_of_domain_curr_row_main_and_aux_value,
+            );
+            profiler!(stop "main&aux curr row");
+
+            profiler!(start "main&aux next row");
+            let out_of_domain_next_row_main_and_aux_value =
+                main_and_aux_combination_polynomial.evaluate(out_of_domain_point_next_row);
+            let main_and_aux_next_row_deep_codeword = Self::deep_codeword(
+                &main_and_aux_codeword,
+                short_domain,
+                out_of_domain_point_next_row,
+                out_of_domain_next_row_main_and_aux_value,
+            );
+            profiler!(stop "main&aux next row");
+
+            profiler!(start "segmented quotient");
+            let out_of_domain_curr_row_quot_segments_value =
+                quotient_segments_combination_polynomial
+                    .evaluate(out_of_domain_point_curr_row_pow_num_segments);
+            let quotient_segments_curr_row_deep_codeword = Self::deep_codeword(
+                &quotient_segments_combination_codeword,
+                short_domain,
+                out_of_domain_point_curr_row_pow_num_segments,
+                out_of_domain_curr_row_quot_segments_value,
+            );
+            profiler!(stop "segmented quotient");
+            profiler!(stop "DEEP");
+
+            profiler!(start "combined DEEP polynomial");
+            profiler!(start "sum" ("CC"));
+            let deep_codeword = [
+                main_and_aux_curr_row_deep_codeword,
+                main_and_aux_next_row_deep_codeword,
+                quotient_segments_curr_row_deep_codeword,
+            ]
+            .into_par_iter()
+            .zip_eq(weights.deep.as_slice().unwrap())
+            .map(|(codeword, &weight)| codeword.into_par_iter().map(|c| c * weight).collect())
+            .reduce(
+                || vec![XFieldElement::ZERO; short_domain.length],
+                |left, right| left.into_iter().zip(right).map(|(l, r)| l + r).collect(),
+            );
 
-        profiler!(stop "sum");
-        let fri_combination_codeword = if fri_domain_is_short_domain {
-            deep_codeword
-        } else {
-            profiler!(start "LDE" ("LDE"));
-            let deep_codeword = quotient_domain.low_degree_extension(&deep_codeword, fri.domain);
-            profiler!(stop "LDE");
-            deep_codeword
+            profiler!(stop "sum");
+            let fri_combination_codeword = if fri_domain_is_short_domain {
+                deep_codeword
+            } else {
+                profiler!(start "LDE" ("LDE"));
+                let deep_codeword =
+                    quotient_domain.low_degree_extension(&deep_codeword, fri.domain);
+                profiler!(stop "LDE");
+                deep_codeword
+            };
+            assert_eq!(fri.domain.length, fri_combination_codeword.len());
+            profiler!(stop "combined DEEP polynomial");
+            checkpoints
+                .save_fri_codeword(0, &fri_combination_codeword)
+                .map_err(checkpoint_error)?;
+            fri_combination_codeword
         };
-        assert_eq!(fri.domain.length, fri_combination_codeword.len());
-        profiler!(stop "combined DEEP polynomial");
 
         profiler!(start "FRI");
-        let revealed_current_row_indices =
-            fri.prove(&fri_combination_codeword, &mut proof_stream)?;
+        let revealed_current_row_indices = fri
+            .prove_with_progress(
+                &fri_combination_codeword,
+                &mut proof_stream,
+                progress,
+                memory_plan.keep_fri_round_codewords,
+                &checkpoints,
+            )
+            .map_err(|err| match err {
+                FriProvingError::Cancelled => ProvingError::Cancelled,
+                FriProvingError::CheckpointError(kind) => ProvingError::CheckpointError(kind),
+                err => err.into(),
+            })?;
         assert_eq!(
             self.parameters.num_collinearity_checks,
             revealed_current_row_indices.len()
         );
         profiler!(stop "FRI");
 
+        progress.enter(ProvingPhase::OpenTraceRows)?;
         profiler!(start "open trace leafs");
         // Open leafs of zipped codewords at indicated positions
         let main_row_err = |row: Vec<_>| ProvingError::TableRowConversionError {
@@ -451,6 +720,8 @@ impl Prover {
         ));
         profiler!(stop "open trace leafs");
 
+        progress.enter(ProvingPhase::Done)?;
+        checkpoints.clear();
         Ok(proof_stream.into())
     }
 
@@ -474,10 +745,15 @@ impl Prover {
         quotient_domain: ArithmeticDomain,
         challenges: &Challenges,
         quotient_combination_weights: &[XFieldElement],
-    ) -> (
-        Array2<XFieldElement>,
-        Array1<Polynomial<'static, XFieldElement>>,
-    ) {
+        progress: &ProgressMonitor,
+        memory_plan: &MemoryPlan,
+    ) -> Result<
+        (
+            Array2<XFieldElement>,
+            Array1<Polynomial<'static, XFieldElement>>,
+        ),
+        ProvingError,
+    > {
         let (Some(main_quotient_domain_codewords), Some(aux_quotient_domain_codewords)) = (
             main_table.quotient_domain_table(),
             aux_table.quotient_domain_table(),
@@ -507,24 +783,27 @@ impl Prover {
                     aux_table,
                     challenges,
                     quotient_combination_weights,
-                );
+                    progress,
+                    memory_plan.randomized_trace_len_to_working_domain_len_ratio,
+                )?;
             profiler!(stop "quotient calculation (just-in-time)");
 
-            return (
+            return Ok((
                 fri_domain_quotient_segment_codewords,
                 quotient_segment_polynomials,
-            );
+            ));
         };
 
         profiler!(start "quotient calculation (cached)" ("CC"));
-        let quotient_codeword = all_quotients_combined(
+        let quotient_codeword = all_quotients_combined_unless_cancelled(
             main_quotient_domain_codewords,
             aux_quotient_domain_codewords,
             main_table.trace_domain(),
             quotient_domain,
             challenges,
             quotient_combination_weights,
-        );
+            &progress.cancellation_token(),
+        )?;
         let quotient_codeword = Array1::from(quotient_codeword);
         assert_eq!(quotient_domain.length, quotient_codeword.len());
         profiler!(stop "quotient calculation (cached)");
@@ -538,10 +817,10 @@ impl Prover {
             Self::fri_domain_segment_polynomials(quotient_segment_polynomials.view(), fri_domain);
         profiler!(stop "quotient LDE");
 
-        (
+        Ok((
             fri_domain_quotient_segment_codewords,
             quotient_segment_polynomials,
-        )
+        ))
     }
 
     /// Computes the quotient segments in a memory-friendly way, i.e., without ever
@@ -549,33 +828,42 @@ impl Prover {
     /// extrapolated over cosets of the trace domain, and the quotients are computed
     /// there. The resulting coset-quotients are linearly recombined to produce the
     /// quotient segment codewords.
+    ///
+    /// Checks for cancellation once per coset and while evaluating the AIR. If
+    /// proving is cancelled, the execution trace is left in an unspecified state.
     fn compute_quotient_segments_with_jit_lde(
         main_table: &mut MasterMainTable,
         aux_table: &mut MasterAuxTable,
         challenges: &Challenges,
         quotient_combination_weights: &[XFieldElement],
-    ) -> (
-        Array2<XFieldElement>,
-        Array1<Polynomial<'static, XFieldElement>>,
-    ) {
-        // This parameter regulates a time-memory tradeoff. Semantically, it is the
-        // ratio of the randomized trace length to the length of the domain used for
-        // calculating the quotient segments (aka “working domain”). When this factor is
-        // larger, there are _more_ cosets of _smaller_ size over which the trace
-        // polynomials are evaluated.
+        progress: &ProgressMonitor,
+        randomized_trace_len_to_working_domain_len_ratio: usize,
+    ) -> Result<
+        (
+            Array2<XFieldElement>,
+            Array1<Polynomial<'static, XFieldElement>>,
+        ),
+        ProvingError,
+    > {
+        // The parameter `randomized_trace_len_to_working_domain_len_ratio` regulates a
+        // time-memory tradeoff. Semantically, it is the ratio of the randomized trace
+        // length to the length of the domain used for calculating the quotient segments
+        // (aka “working domain”). When this factor is larger, there are _more_ cosets
+        // of _smaller_ size over which the trace polynomials are evaluated.
         // Must be a power of two and lie in 2..=randomized_trace_domain.length.
         //
         // The requirement for the working domain to be at most as long as the trace
         // domain, i.e., at most half the size of the randomzied trace domain, is
         // explained below.
-        const RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO: usize = 2;
-        const NUM_COSETS: usize =
-            NUM_QUOTIENT_SEGMENTS * RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO;
+        let cancellation_token = progress.cancellation_token();
+        let ratio = randomized_trace_len_to_working_domain_len_ratio;
+        let num_cosets = NUM_QUOTIENT_SEGMENTS * ratio;
 
-        debug_assert!(RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO.is_power_of_two());
+        debug_assert!(ratio.is_power_of_two());
+        debug_assert!(ratio >= 2);
 
         let mut working_domain = main_table.randomized_trace_domain();
-        for _ in 0..RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO.ilog2() {
+        for _ in 0..ratio.ilog2() {
             working_domain = working_domain.halve().unwrap();
         }
         let working_domain = working_domain;
@@ -583,9 +871,9 @@ impl Prover {
         let fri_domain = main_table.fri_domain();
 
         let num_rows = working_domain.length;
-        let coset_root_order = (num_rows * NUM_COSETS).try_into().unwrap();
+        let coset_root_order = (num_rows * num_cosets).try_into().unwrap();
 
-        // the powers of ι define `NUM_COSETS`-many cosets of the working domain
+        // the powers of ι define `num_cosets`-many cosets of the working domain
         let iota = BFieldElement::primitive_root_of_unity(coset_root_order).unwrap();
         let psi = fri_domain.offset;
 
@@ -593,11 +881,23 @@ impl Prover {
         profiler!(start "zero-initialization");
         // column majority (“`F`”) for contiguous column slices
         let mut quotient_multicoset_evaluations =
-            ndarray_helper::par_zeros((num_rows, NUM_COSETS).f());
+            ndarray_helper::par_zeros((num_rows, num_cosets).f());
         let mut main_columns =
             ndarray_helper::par_zeros((num_rows, MasterMainTable::NUM_COLUMNS).f());
         let mut aux_columns =
             ndarray_helper::par_zeros((num_rows, MasterAuxTable::NUM_COLUMNS).f());
+
+        // If the working domain is shorter than the trace domain, the “next row” of
+        // any row in the working domain is not part of that domain. Those next rows
+        // are evaluated separately, on the working domain shifted by the trace
+        // domain's generator.
+        let mut next_row_columns = (working_domain.length < trace_domain.length).then(|| {
+            let next_main_columns =
+                ndarray_helper::par_zeros((num_rows, MasterMainTable::NUM_COLUMNS).f());
+            let next_aux_columns =
+                ndarray_helper::par_zeros((num_rows, MasterAuxTable::NUM_COLUMNS).f());
+            (next_main_columns, next_aux_columns)
+        });
         profiler!(stop "zero-initialization");
 
         profiler!(start "fetch trace randomizers");
@@ -627,99 +927,127 @@ impl Prover {
         profiler!(stop "poly interpolate");
 
         profiler!(start "calculate quotients");
+        let evaluate_randomized_trace =
+            |working_domain: ArithmeticDomain,
+             main_columns: &mut Array2<BFieldElement>,
+             aux_columns: &mut Array2<XFieldElement>| {
+                profiler!(start "poly evaluate" ("LDE"));
+                Zip::from(main_table.trace_table().axis_iter(Axis(1)))
+                    .and(main_columns.axis_iter_mut(Axis(1)))
+                    .par_for_each(|trace_column, target_column| {
+                        let trace_poly = Polynomial::new_borrowed(trace_column.as_slice().unwrap());
+                        Array1::from(working_domain.evaluate(&trace_poly)).move_into(target_column);
+                    });
+                Zip::from(aux_table.trace_table().axis_iter(Axis(1)))
+                    .and(aux_columns.axis_iter_mut(Axis(1)))
+                    .par_for_each(|trace_column, target_column| {
+                        let trace_poly = Polynomial::new_borrowed(trace_column.as_slice().unwrap());
+                        Array1::from(working_domain.evaluate(&trace_poly)).move_into(target_column);
+                    });
+                profiler!(stop "poly evaluate");
+
+                // A _randomized_ trace interpolant is:
+                //
+                //    trace_interpolant + trace_zerofier·trace_randomizer
+                //    ╶───────┬───────╴   ╶──────────────┬─────────────╴
+                //            ╵                          │
+                //   was just moved into                 ╵
+                //  `{main, aux}_columns`          still missing
+                //
+                //
+                // Knowing the shape of the trace zerofier (see also `domain.zerofier()`), and
+                // with the length of the trace domain being `n`, this is:
+                //
+                //  trace_zerofier·trace_randomizer = (X^n - 1)·trace_randomizer
+                //
+                // For reasons of efficiency, all three components (interpolant, zerofier, and
+                // randomizer) are evaluated over the `working_domain`, i.e., they are
+                // codewords. The parameter `randomized_trace_len_to_working_domain_len_ratio`
+                // defines the length of the working domain in relation to the randomized trace
+                // domain. Let the length of the working domain `m`, the generator of the
+                // working domain a primitive mth root of unity ξ, and the working domain's
+                // offset γ.
+                //
+                // If the length of the working domain `m` is less than or equal the length of
+                // the trace domain, i.e., if m <= n or equivalently, if parameter
+                //  `randomized_trace_len_to_working_domain_len_ratio` >= 2, then evaluating the
+                // trace zerofier (X^n - 1) on the `i`th working domain value gives:
+                //
+                //   (X^n - 1)(ξ^i·γ) = (ξ^i·γ)^n - 1 = 1^i · γ^n - 1 = γ^n - 1
+                //
+                // In other words, the trace_zerofier codeword over the working domain is
+                // [working_domain_offset^n - 1; m].
+                //
+                // Should a future re-design want to consider a working domain of length equal
+                // to the randomized trace domain, or in other words, should it consider a
+                // `randomized_trace_len_to_working_domain_len_ratio` of 1, then the
+                // trace_zerofier's contribution below needs to be generalized. On
+                // working_domain's value `i`, the zerofier contribution would then be
+                // (-1)^i·γ^n - 1. In particular, note the term (-1)^i, which is absent from the
+                // trace randomizer when evaluated on working domains at most as long as the
+                // trace domain.
+                assert!(working_domain.length <= trace_domain.length);
+
+                profiler!(start "trace randomizers" ("LDE"));
+                let trace_domain_len = u64::try_from(trace_domain.length).unwrap();
+                let zerofier = working_domain.offset.mod_pow(trace_domain_len) - BFieldElement::ONE;
+
+                Zip::from(main_columns.axis_iter_mut(Axis(1)))
+                    .and(main_trace_randomizers.axis_iter(Axis(0)))
+                    .par_for_each(|mut column, randomizer_polynomial| {
+                        let randomizer_codeword =
+                            working_domain.evaluate(&randomizer_polynomial[[]]);
+                        for (cell, randomizer) in column.iter_mut().zip(randomizer_codeword) {
+                            *cell += zerofier * randomizer;
+                        }
+                    });
+                Zip::from(aux_columns.axis_iter_mut(Axis(1)))
+                    .and(aux_trace_randomizers.axis_iter(Axis(0)))
+                    .par_for_each(|mut column, randomizer_polynomial| {
+                        let randomizer_codeword =
+                            working_domain.evaluate(&randomizer_polynomial[[]]);
+                        for (cell, randomizer) in column.iter_mut().zip(randomizer_codeword) {
+                            *cell += zerofier * randomizer;
+                        }
+                    });
+                profiler!(stop "trace randomizers");
+            };
+
         for (coset_index, quotient_column) in
             (0..).zip(quotient_multicoset_evaluations.columns_mut())
         {
+            progress.check_cancellation()?;
+
             // always also offset by fri domain offset to avoid division-by-zero errors
             let working_domain = working_domain.with_offset(iota.mod_pow(coset_index) * psi);
-            profiler!(start "poly evaluate" ("LDE"));
-            Zip::from(main_table.trace_table().axis_iter(Axis(1)))
-                .and(main_columns.axis_iter_mut(Axis(1)))
-                .par_for_each(|trace_column, target_column| {
-                    let trace_poly = Polynomial::new_borrowed(trace_column.as_slice().unwrap());
-                    Array1::from(working_domain.evaluate(&trace_poly)).move_into(target_column);
-                });
-            Zip::from(aux_table.trace_table().axis_iter(Axis(1)))
-                .and(aux_columns.axis_iter_mut(Axis(1)))
-                .par_for_each(|trace_column, target_column| {
-                    let trace_poly = Polynomial::new_borrowed(trace_column.as_slice().unwrap());
-                    Array1::from(working_domain.evaluate(&trace_poly)).move_into(target_column);
-                });
-            profiler!(stop "poly evaluate");
-
-            // A _randomized_ trace interpolant is:
-            //
-            //    trace_interpolant + trace_zerofier·trace_randomizer
-            //    ╶───────┬───────╴   ╶──────────────┬─────────────╴
-            //            ╵                          │
-            //   was just moved into                 ╵
-            //  `{main, aux}_columns`          still missing
-            //
-            //
-            // Knowing the shape of the trace zerofier (see also `domain.zerofier()`), and
-            // with the length of the trace domain being `n`, this is:
-            //
-            //  trace_zerofier·trace_randomizer = (X^n - 1)·trace_randomizer
-            //
-            // For reasons of efficiency, all three components (interpolant, zerofier, and
-            // randomizer) are evaluated over the `working_domain`, i.e., they are
-            // codewords. The constant `RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO`
-            // defines the length of the working domain in relation to the randomized trace
-            // domain. Let the length of the working domain `m`, the generator of the
-            // working domain a primitive mth root of unity ξ, and the working domain's
-            // offset γ.
-            //
-            // If the length of the working domain `m` is less than or equal the length of
-            // the trace domain, i.e., if m <= n or equivalently, if constant
-            //  `RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO` >= 2, then evaluating the
-            // trace zerofier (X^n - 1) on the `i`th working domain value gives:
-            //
-            //   (X^n - 1)(ξ^i·γ) = (ξ^i·γ)^n - 1 = 1^i · γ^n - 1 = γ^n - 1
-            //
-            // In other words, the trace_zerofier codeword over the working domain is
-            // [working_domain_offset^n - 1; m].
-            //
-            // Should a future re-design want to consider a working domain of length equal
-            // to the randomized trace domain, or in other words, should it consider a
-            // `RANDOMIZED_TRACE_LEN_TO_WORKING_DOMAIN_LEN_RATIO` of 1, then the
-            // trace_zerofier's contribution below needs to be generalized. On
-            // working_domain's value `i`, the zerofier contribution would then be
-            // (-1)^i·γ^n - 1. In particular, note the term (-1)^i, which is absent from the
-            // trace randomizer when evaluated on working domains at most as long as the
-            // trace domain.
-            assert!(working_domain.length <= trace_domain.length);
-
-            profiler!(start "trace randomizers" ("LDE"));
-            let trace_domain_len = u64::try_from(trace_domain.length).unwrap();
-            let zerofier = working_domain.offset.mod_pow(trace_domain_len) - BFieldElement::ONE;
-
-            Zip::from(main_columns.axis_iter_mut(Axis(1)))
-                .and(main_trace_randomizers.axis_iter(Axis(0)))
-                .par_for_each(|mut column, randomizer_polynomial| {
-                    let randomizer_codeword = working_domain.evaluate(&randomizer_polynomial[[]]);
-                    for (cell, randomizer) in column.iter_mut().zip(randomizer_codeword) {
-                        *cell += zerofier * randomizer;
-                    }
-                });
-            Zip::from(aux_columns.axis_iter_mut(Axis(1)))
-                .and(aux_trace_randomizers.axis_iter(Axis(0)))
-                .par_for_each(|mut column, randomizer_polynomial| {
-                    let randomizer_codeword = working_domain.evaluate(&randomizer_polynomial[[]]);
-                    for (cell, randomizer) in column.iter_mut().zip(randomizer_codeword) {
-                        *cell += zerofier * randomizer;
-                    }
-                });
-            profiler!(stop "trace randomizers");
+            evaluate_randomized_trace(working_domain, &mut main_columns, &mut aux_columns);
 
             profiler!(start "AIR evaluation" ("AIR"));
-            let all_quotients = all_quotients_combined(
-                main_columns.view(),
-                aux_columns.view(),
-                trace_domain,
-                working_domain,
-                challenges,
-                quotient_combination_weights,
-            );
+            let all_quotients = match &mut next_row_columns {
+                None => all_quotients_combined_unless_cancelled(
+                    main_columns.view(),
+                    aux_columns.view(),
+                    trace_domain,
+                    working_domain,
+                    challenges,
+                    quotient_combination_weights,
+                    &cancellation_token,
+                )?,
+                Some((next_main_columns, next_aux_columns)) => {
+                    let next_offset = working_domain.offset * trace_domain.generator;
+                    let next_domain = working_domain.with_offset(next_offset);
+                    evaluate_randomized_trace(next_domain, next_main_columns, next_aux_columns);
+                    all_quotients_combined_with_next_rows(
+                        [main_columns.view(), next_main_columns.view()],
+                        [aux_columns.view(), next_aux_columns.view()],
+                        trace_domain,
+                        working_domain,
+                        challenges,
+                        quotient_combination_weights,
+                        &cancellation_token,
+                    )?
+                }
+            };
             Array1::from(all_quotients).move_into(quotient_column);
             profiler!(stop "AIR evaluation");
         }
@@ -747,7 +1075,7 @@ impl Prover {
             .for_each(|mut column| ntt(column.as_slice_mut().unwrap()));
         profiler!(stop "restore original trace");
 
-        segmentification
+        Ok(segmentification)
     }
 
     /// Map a matrix whose columns represent the evaluation of a high-degree
@@ -919,9 +1247,7 @@ impl Prover {
             .for_each(|mut row| intt(row.as_slice_mut().unwrap()));
 
         // scale every row by Ψ^-k · ι^(-k(j+i·M))
-        let num_threads = std::thread::available_parallelism()
-            .map(|t| t.get())
-            .unwrap_or(1);
+        let num_threads = rayon::current_num_threads();
         let chunk_size = (num_output_rows / num_threads).max(1);
         let iota_inverse = iota.inverse();
         let psi_inverse = psi.inverse();
@@ -1043,27 +1369,74 @@ impl Prover {
     }
 }
 
-impl Verifier {
-    pub fn new(parameters: Stark) -> Self {
-        Self { parameters }
+/// A [`Verifier`] prepared for proofs of one padded height. Holds everything
+/// that does not depend on the proof itself, like the [FRI](Fri) domain and the
+/// generator of the trace domain. Verifying a proof then only does
+/// proof-specific work.
+///
+/// Build it once per [parameters](Stark) and padded height, and re-use it for
+/// every proof of that padded height.
+#[derive(Debug, Copy, Clone)]
+pub struct PreparedVerifier {
+    parameters: Stark,
+    padded_height: usize,
+    fri: Fri,
+    merkle_tree_height: usize,
+    trace_domain_generator: BFieldElement,
+    trace_domain_generator_inverse: BFieldElement,
+}
+
+impl PreparedVerifier {
+    /// # Errors
+    ///
+    /// Returns an error if the padded height is not a power of two, or if the
+    /// [parameters](Stark) do not permit proofs of that padded height.
+    pub fn new(parameters: Stark, padded_height: usize) -> Result<Self, VerificationError> {
+        let fri = parameters.fri(padded_height)?;
+        let trace_domain_generator = ArithmeticDomain::generator_for_length(padded_height as u64)?;
+
+        Ok(Self {
+            parameters,
+            padded_height,
+            fri,
+            merkle_tree_height: fri.domain.length.ilog2() as usize,
+            trace_domain_generator,
+            trace_domain_generator_inverse: trace_domain_generator.inverse(),
+        })
     }
 
-    /// See also [`Stark::verify`].
-    pub fn verify(self, claim: &Claim, proof: &Proof) -> Result<(), VerificationError> {
-        profiler!(start "deserialize");
-        let mut proof_stream = ProofStream::try_from(proof)?;
-        profiler!(stop "deserialize");
+    /// The padded height of the proofs this verifier is prepared for.
+    pub fn padded_height(&self) -> usize {
+        self.padded_height
+    }
 
-        profiler!(start "Fiat-Shamir: Claim" ("hash"));
-        proof_stream.alter_fiat_shamir_state_with(claim);
-        profiler!(stop "Fiat-Shamir: Claim");
+    /// Like [`Verifier::verify`], but rejects proofs of any padded height other
+    /// than the [one prepared for](Self::padded_height).
+    pub fn verify(&self, claim: &Claim, proof: &Proof) -> Result<(), VerificationError> {
+        let (proof_stream, padded_height) = Verifier::open(claim, proof)?;
+        if padded_height != self.padded_height {
+            return Err(VerificationError::PaddedHeightMismatch {
+                expected: self.padded_height,
+                actual: padded_height,
+            });
+        }
 
-        profiler!(start "derive additional parameters");
-        let log_2_padded_height = proof_stream.dequeue()?.try_into_log2_padded_height()?;
-        let padded_height = 1 << log_2_padded_height;
-        let fri = self.parameters.fri(padded_height)?;
-        let merkle_tree_height = fri.domain.length.ilog2() as usize;
-        profiler!(stop "derive additional parameters");
+        self.verify_opened(claim, proof_stream)
+    }
+
+    fn verify_opened(
+        &self,
+        claim: &Claim,
+        mut proof_stream: ProofStream,
+    ) -> Result<(), VerificationError> {
+        let Self {
+            parameters,
+            padded_height,
+            fri,
+            merkle_tree_height,
+            trace_domain_generator,
+            trace_domain_generator_inverse,
+        } = *self;
 
         profiler!(start "Fiat-Shamir 1" ("hash"));
         let main_merkle_tree_root = proof_stream.dequeue()?.try_into_merkle_root()?;
@@ -1078,7 +1451,6 @@ impl Verifier {
         profiler!(stop "Fiat-Shamir 1");
 
         profiler!(start "dequeue ood point and rows" ("hash"));
-        let trace_domain_generator = ArithmeticDomain::generator_for_length(padded_height as u64)?;
         let out_of_domain_point_curr_row = proof_stream.sample_scalars(1)[0];
         let out_of_domain_point_next_row = trace_domain_generator * out_of_domain_point_curr_row;
         let out_of_domain_point_curr_row_pow_num_segments =
@@ -1134,7 +1506,7 @@ impl Verifier {
         let initial_zerofier_inv = (out_of_domain_point_curr_row - bfe!(1)).inverse();
         let consistency_zerofier_inv =
             (out_of_domain_point_curr_row.mod_pow_u32(padded_height as u32) - bfe!(1)).inverse();
-        let except_last_row = out_of_domain_point_curr_row - trace_domain_generator.inverse();
+        let except_last_row = out_of_domain_point_curr_row - trace_domain_generator_inverse;
         let transition_zerofier_inv = except_last_row * consistency_zerofier_inv;
         let terminal_zerofier_inv = except_last_row.inverse(); // i.e., only last row
         profiler!(stop "zerofiers");
@@ -1178,12 +1550,12 @@ impl Verifier {
         profiler!(stop "Fiat-Shamir 2");
 
         profiler!(start "sum out-of-domain values" ("CC"));
-        let out_of_domain_curr_row_main_and_aux_value = Self::linearly_sum_main_and_aux_row(
+        let out_of_domain_curr_row_main_and_aux_value = Verifier::linearly_sum_main_and_aux_row(
             out_of_domain_curr_main_row.view(),
             out_of_domain_curr_aux_row.view(),
             main_and_aux_codeword_weights.view(),
         );
-        let out_of_domain_next_row_main_and_aux_value = Self::linearly_sum_main_and_aux_row(
+        let out_of_domain_next_row_main_and_aux_value = Verifier::linearly_sum_main_and_aux_row(
             out_of_domain_next_main_row.view(),
             out_of_domain_next_aux_row.view(),
             main_and_aux_codeword_weights.view(),
@@ -1256,7 +1628,7 @@ impl Verifier {
         let revealed_quotient_segments_elements =
             proof_stream.dequeue()?.try_into_quot_segments_elements()?;
         let revealed_quotient_segments_digests =
-            Self::hash_quotient_segment_elements(&revealed_quotient_segments_elements);
+            Verifier::hash_quotient_segment_elements(&revealed_quotient_segments_elements);
         let revealed_quotient_authentication_structure = proof_stream
             .dequeue()?
             .try_into_authentication_structure()?;
@@ -1275,19 +1647,19 @@ impl Verifier {
         profiler!(stop "check leafs");
 
         profiler!(start "linear combination");
-        if self.parameters.num_collinearity_checks != revealed_current_row_indices.len() {
+        if parameters.num_collinearity_checks != revealed_current_row_indices.len() {
             return Err(VerificationError::IncorrectNumberOfRowIndices);
         };
-        if self.parameters.num_collinearity_checks != revealed_fri_values.len() {
+        if parameters.num_collinearity_checks != revealed_fri_values.len() {
             return Err(VerificationError::IncorrectNumberOfFRIValues);
         };
-        if self.parameters.num_collinearity_checks != revealed_quotient_segments_elements.len() {
+        if parameters.num_collinearity_checks != revealed_quotient_segments_elements.len() {
             return Err(VerificationError::IncorrectNumberOfQuotientSegmentElements);
         };
-        if self.parameters.num_collinearity_checks != main_table_rows.len() {
+        if parameters.num_collinearity_checks != main_table_rows.len() {
             return Err(VerificationError::IncorrectNumberOfMainTableRows);
         };
-        if self.parameters.num_collinearity_checks != aux_table_rows.len() {
+        if parameters.num_collinearity_checks != aux_table_rows.len() {
             return Err(VerificationError::IncorrectNumberOfAuxTableRows);
         };
 
@@ -1303,7 +1675,7 @@ impl Verifier {
             let current_fri_domain_value = fri.domain.domain_value(row_idx as u32);
 
             profiler!(start "main & aux elements" ("CC"));
-            let main_and_aux_curr_row_element = Self::linearly_sum_main_and_aux_row(
+            let main_and_aux_curr_row_element = Verifier::linearly_sum_main_and_aux_row(
                 main_row.view(),
                 aux_row.view(),
                 main_and_aux_codeword_weights.view(),
@@ -1348,6 +1720,86 @@ impl Verifier {
         profiler!(stop "linear combination");
         Ok(())
     }
+}
+
+impl Verifier {
+    pub fn new(parameters: Stark) -> Self {
+        Self { parameters }
+    }
+
+    /// See also [`Stark::verify`].
+    pub fn verify(self, claim: &Claim, proof: &Proof) -> Result<(), VerificationError> {
+        let (proof_stream, padded_height) = Self::open(claim, proof)?;
+
+        profiler!(start "derive additional parameters");
+        let prepared_verifier = PreparedVerifier::new(self.parameters, padded_height)?;
+        profiler!(stop "derive additional parameters");
+
+        prepared_verifier.verify_opened(claim, proof_stream)
+    }
+
+    /// Verify many proofs at once. Proofs of the same padded height share one
+    /// [`PreparedVerifier`], and the proofs are verified in parallel.
+    ///
+    /// Succeeds if and only if every proof is valid for its claim. Otherwise, the
+    /// error lists the index and the reason of every failed verification.
+    pub fn verify_batch(
+        self,
+        claims_and_proofs: &[(Claim, Proof)],
+    ) -> Result<(), BatchVerificationError> {
+        let opened_proofs = claims_and_proofs
+            .par_iter()
+            .map(|(claim, proof)| Self::open(claim, proof))
+            .collect::<Vec<_>>();
+
+        profiler!(start "derive additional parameters");
+        let prepared_verifiers = opened_proofs
+            .iter()
+            .filter_map(|opened| opened.as_ref().ok())
+            .map(|&(_, padded_height)| padded_height)
+            .unique()
+            .filter_map(|h| Some((h, PreparedVerifier::new(self.parameters, h).ok()?)))
+            .collect::<HashMap<_, _>>();
+        profiler!(stop "derive additional parameters");
+
+        let verify = |claim, (proof_stream, padded_height)| {
+            let prepared_verifier = match prepared_verifiers.get(&padded_height) {
+                Some(&prepared_verifier) => prepared_verifier,
+                None => PreparedVerifier::new(self.parameters, padded_height)?,
+            };
+            prepared_verifier.verify_opened(claim, proof_stream)
+        };
+        let failures = opened_proofs
+            .into_par_iter()
+            .zip(claims_and_proofs)
+            .enumerate()
+            .filter_map(|(i, (opened, (claim, _)))| {
+                opened.and_then(|o| verify(claim, o)).err().map(|e| (i, e))
+            })
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(BatchVerificationError {
+            num_proofs: claims_and_proofs.len(),
+            failures,
+        })
+    }
+
+    /// Deserialize the proof, commit to the claim, and read the padded height.
+    fn open(claim: &Claim, proof: &Proof) -> Result<(ProofStream, usize), VerificationError> {
+        profiler!(start "deserialize");
+        let mut proof_stream = ProofStream::try_from(proof)?;
+        profiler!(stop "deserialize");
+
+        profiler!(start "Fiat-Shamir: Claim" ("hash"));
+        proof_stream.alter_fiat_shamir_state_with(claim);
+        profiler!(stop "Fiat-Shamir: Claim");
+
+        let log_2_padded_height = proof_stream.dequeue()?.try_into_log2_padded_height()?;
+        Ok((proof_stream, 1 << log_2_padded_height))
+    }
 
     fn hash_quotient_segment_elements(quotient_segment_rows: &[QuotientSegments]) -> Vec<Digest> {
         let interpret_xfe_as_bfes = |xfe: XFieldElement| xfe.coefficients.to_vec();
@@ -1393,20 +1845,66 @@ impl Stark {
             "FRI expansion factor must be greater than one."
         );
 
-        let fri_expansion_factor = 1 << log2_of_fri_expansion_factor;
-        let num_collinearity_checks = security_level / log2_of_fri_expansion_factor;
+        let stark = Stark {
+            security_level,
+            fri_expansion_factor: 1 << log2_of_fri_expansion_factor,
+            num_trace_randomizers: 0,
+            num_collinearity_checks: 0,
+            fri_folding_factor: Self::default_fri_folding_factor(),
+            num_grinding_bits: 0,
+        };
+        stark.with_grinding_bits(0)
+    }
+
+    fn default_fri_folding_factor() -> usize {
+        2
+    }
+
+    /// Set the factor by which every round of [FRI](Fri) shrinks the codeword.
+    /// The [security level](Self::security_level) is unaffected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the folding factor is not a power of 2 greater than 1.
+    #[must_use]
+    pub fn with_fri_folding_factor(mut self, fri_folding_factor: usize) -> Self {
+        assert!(
+            fri_folding_factor > 1 && fri_folding_factor.is_power_of_two(),
+            "FRI folding factor must be a power of 2 greater than 1"
+        );
+        self.fri_folding_factor = fri_folding_factor;
+        self
+    }
+
+    /// Replace some of the [collinearity checks](Self::num_collinearity_checks)
+    /// with [proof of work](Self::num_grinding_bits), keeping the
+    /// [security level](Self::security_level) the same. This makes proofs
+    /// smaller and verification faster, at the cost of additional proving time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of grinding bits is not smaller than the security
+    /// level.
+    #[must_use]
+    pub fn with_grinding_bits(mut self, num_grinding_bits: usize) -> Self {
+        assert!(
+            num_grinding_bits < self.security_level.max(1),
+            "proof of work must not make up the entire security level"
+        );
+
+        let log2_of_fri_expansion_factor = self.fri_expansion_factor.ilog2() as usize;
+        let num_collinearity_checks =
+            (self.security_level - num_grinding_bits) / log2_of_fri_expansion_factor;
 
         let num_out_of_domain_rows = 2;
         let num_trace_randomizers = num_collinearity_checks
             + num_out_of_domain_rows * x_field_element::EXTENSION_DEGREE
             + NUM_QUOTIENT_SEGMENTS * x_field_element::EXTENSION_DEGREE;
 
-        Stark {
-            security_level,
-            fri_expansion_factor,
-            num_trace_randomizers,
-            num_collinearity_checks,
-        }
+        self.num_collinearity_checks = num_collinearity_checks;
+        self.num_trace_randomizers = num_trace_randomizers;
+        self.num_grinding_bits = num_grinding_bits;
+        self
     }
 
     /// Prove the correctness of the given [Claim] using the given
@@ -1462,11 +1960,13 @@ impl Stark {
         let coset_offset = BFieldElement::generator();
         let domain = ArithmeticDomain::of_length(fri_domain_length)?.with_offset(coset_offset);
 
-        Fri::new(
+        let fri = Fri::new(
             domain,
             self.fri_expansion_factor,
             self.num_collinearity_checks,
-        )
+        )?
+        .with_folding_factor(self.fri_folding_factor)?;
+        Ok(fri.with_num_grinding_bits(self.num_grinding_bits))
     }
 
     /// Given `f(x)` (the in-domain evaluation of polynomial `f` in `x`), the domain point `x` at
@@ -1502,7 +2002,12 @@ impl<'a> Arbitrary<'a> for Stark {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let security_level = u.int_in_range(1..=640)?;
         let log_2_of_fri_expansion_factor = u.int_in_range(1..=8)?;
-        Ok(Self::new(security_level, log_2_of_fri_expansion_factor))
+        let num_grinding_bits = u.int_in_range(0..=security_level.min(8) - 1)?;
+        let log_2_of_fri_folding_factor = u.int_in_range(1..=4)?;
+        let stark = Self::new(security_level, log_2_of_fri_expansion_factor)
+            .with_grinding_bits(num_grinding_bits)
+            .with_fri_folding_factor(1 << log_2_of_fri_folding_factor);
+        Ok(stark)
     }
 }
 
@@ -1601,15 +2106,16 @@ pub(crate) mod tests {
 
     use super::*;
     use crate::config::CacheDecision;
+    use crate::error::FriValidationError;
     use crate::error::InstructionError;
     use crate::shared_tests::construct_master_main_table;
-    use crate::shared_tests::low_security_stark;
-    use crate::shared_tests::prove_and_verify;
-    use crate::shared_tests::ProgramAndInput;
-    use crate::shared_tests::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
     use crate::table::auxiliary_table;
     use crate::table::auxiliary_table::Evaluable;
     use crate::table::master_table::MasterAuxTable;
+    use crate::test_utils::low_security_stark;
+    use crate::test_utils::prove_and_verify;
+    use crate::test_utils::ProgramAndInput;
+    use crate::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
     use crate::triton_program;
     use crate::vm::tests::property_based_test_program_for_and;
     use crate::vm::tests::property_based_test_program_for_assert_vector;
@@ -1728,10 +2234,19 @@ pub(crate) mod tests {
         let max_degree = stark.max_degree(padded_height);
         let quot_dom = Prover::quotient_domain(fri_dom, max_degree).unwrap();
 
+        let progress = ProgressMonitor::default();
         debug_assert!(main.fri_domain_table().is_none());
         debug_assert!(aux.fri_domain_table().is_none());
-        let jit_segments =
-            Prover::compute_quotient_segments(&mut main, &mut aux, quot_dom, &ch, &weights);
+        let jit_segments = Prover::compute_quotient_segments(
+            &mut main,
+            &mut aux,
+            quot_dom,
+            &ch,
+            &weights,
+            &progress,
+            &MemoryPlan::default(),
+        )
+        .unwrap();
 
         debug_assert!(main.fri_domain_table().is_none());
         main.maybe_low_degree_extend_all_columns();
@@ -1741,12 +2256,40 @@ pub(crate) mod tests {
         aux.maybe_low_degree_extend_all_columns();
         debug_assert!(aux.fri_domain_table().is_some());
 
-        let cache_segments =
-            Prover::compute_quotient_segments(&mut main, &mut aux, quot_dom, &ch, &weights);
+        let cache_segments = Prover::compute_quotient_segments(
+            &mut main,
+            &mut aux,
+            quot_dom,
+            &ch,
+            &weights,
+            &progress,
+            &MemoryPlan::default(),
+        )
+        .unwrap();
 
         assert_eq!(jit_segments, cache_segments);
     }
 
+    #[test]
+    fn quotient_segments_are_independent_of_working_domain_length() {
+        let mut rng = StdRng::seed_from_u64(0x1d0c_5e6f_a2e9_4b37);
+        let weights = rng.gen::<[XFieldElement; MasterAuxTable::NUM_CONSTRAINTS]>();
+
+        let program = ProgramAndInput::new(triton_program!(halt));
+        let (_, _, mut main, mut aux, ch) = master_tables_for_low_security_level(program);
+        let progress = ProgressMonitor::default();
+        let mut quotient_segments = |ratio| {
+            Prover::compute_quotient_segments_with_jit_lde(
+                &mut main, &mut aux, &ch, &weights, &progress, ratio,
+            )
+            .unwrap()
+        };
+
+        let segments = quotient_segments(2);
+        assert_eq!(segments, quotient_segments(4));
+        assert_eq!(segments, quotient_segments(8));
+    }
+
     /// [`Stark::compute_quotient_segments`] takes mutable references to both, the
     /// main and the auxiliary tables. It is vital that certain information is
     /// _not_ mutated.
@@ -1777,8 +2320,17 @@ pub(crate) mod tests {
                 assert!(aux.fri_domain_table().is_some());
             }
 
-            let _segments =
-                Prover::compute_quotient_segments(&mut main, &mut aux, quot_dom, &ch, &weights);
+            let progress = ProgressMonitor::default();
+            let _segments = Prover::compute_quotient_segments(
+                &mut main,
+                &mut aux,
+                quot_dom,
+                &ch,
+                &weights,
+                &progress,
+                &MemoryPlan::default(),
+            )
+            .unwrap();
 
             assert_eq!(original_main_trace, main.trace_table());
             assert_eq!(original_aux_trace, aux.trace_table());
@@ -1817,6 +2369,68 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn proof_is_independent_of_thread_pool() {
+        let program = ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+            .with_input(bfe_vec![25]);
+        let claim = program.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program.program,
+            program.public_input,
+            program.non_determinism,
+        )
+        .unwrap();
+
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        let seed = [7; 32];
+        let prover = || Prover::new(stark).set_randomness_seed_which_may_break_zero_knowledge(seed);
+        let thread_pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+        let proof = prover().prove(&claim, &aet).unwrap();
+        let single_threaded_proof = prover()
+            .with_thread_pool(Arc::new(thread_pool))
+            .prove(&claim, &aet)
+            .unwrap();
+        let limited_proof = prover()
+            .with_max_num_threads(2)
+            .prove(&claim, &aet)
+            .unwrap();
+        assert_eq!(proof, single_threaded_proof);
+        assert_eq!(proof, limited_proof);
+    }
+
+    #[test]
+    fn prover_with_max_num_threads_does_not_use_more_threads() {
+        let program = ProgramAndInput::new(triton_program!(halt));
+        let claim = program.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program.program,
+            program.public_input,
+            program.non_determinism,
+        )
+        .unwrap();
+
+        let observed_num_threads = Arc::new(std::sync::Mutex::new(vec![]));
+        let num_threads = Arc::clone(&observed_num_threads);
+        let observer = move |_| {
+            num_threads
+                .lock()
+                .unwrap()
+                .push(rayon::current_num_threads())
+        };
+        Prover::new(low_security_stark(
+            DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS,
+        ))
+        .with_max_num_threads(3)
+        .with_progress_observer(observer)
+        .prove(&claim, &aet)
+        .unwrap();
+
+        let observed_num_threads = observed_num_threads.lock().unwrap();
+        assert!(!observed_num_threads.is_empty());
+        assert!(observed_num_threads.iter().all(|&n| n == 3));
+    }
+
     #[test]
     fn print_ram_table_example_for_specification() {
         let program = triton_program!(
@@ -2776,6 +3390,176 @@ pub(crate) mod tests {
         let _verdict = stark.verify(&claim, &proof);
     }
 
+    #[test]
+    fn batch_verification_reports_failed_proofs() {
+        let fibonacci = |n| {
+            ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+                .with_input(bfe_array![n])
+        };
+        let log_2_fri_expansion_factor = DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+        let (short_claim, short_proof) = prove_and_verify(fibonacci(3), log_2_fri_expansion_factor);
+        let (long_claim, long_proof) = prove_and_verify(fibonacci(300), log_2_fri_expansion_factor);
+        let (halt_claim, halt_proof) =
+            prove_and_verify(test_program_for_halt(), log_2_fri_expansion_factor);
+        assert!(short_proof.padded_height().unwrap() != long_proof.padded_height().unwrap());
+
+        let verifier = Verifier::new(low_security_stark(log_2_fri_expansion_factor));
+        let valid_batch = [
+            (short_claim.clone(), short_proof.clone()),
+            (long_claim.clone(), long_proof.clone()),
+            (halt_claim.clone(), halt_proof.clone()),
+        ];
+        assert!(let Ok(()) = verifier.verify_batch(&valid_batch));
+        assert!(let Ok(()) = verifier.verify_batch(&[]));
+
+        let wrong_output_claim = long_claim.clone().with_output(bfe_vec![42]);
+        let batch = [
+            (short_claim.clone(), short_proof),
+            (wrong_output_claim, long_proof.clone()),
+            (halt_claim, halt_proof),
+            (short_claim, long_proof),
+            (long_claim, Proof(vec![])),
+        ];
+        let_assert!(Err(err) = verifier.verify_batch(&batch));
+        assert!(batch.len() == err.num_proofs);
+        let failed_indices = err.failures.iter().map(|&(i, _)| i).collect_vec();
+        assert!(vec![1, 3, 4] == failed_indices);
+    }
+
+    #[proptest(cases = 20)]
+    fn batch_verification_agrees_with_individual_verification(
+        #[strategy(arb())] stark: Stark,
+        #[strategy(vec(arb(), 0..10))] claims_and_proofs: Vec<(Claim, Proof)>,
+    ) {
+        let verifier = Verifier::new(stark);
+        let individually_failed_indices = claims_and_proofs
+            .iter()
+            .positions(|(claim, proof)| verifier.verify(claim, proof).is_err())
+            .collect_vec();
+
+        let batch_failed_indices = match verifier.verify_batch(&claims_and_proofs) {
+            Ok(()) => vec![],
+            Err(err) => err.failures.into_iter().map(|(i, _)| i).collect(),
+        };
+        prop_assert_eq!(individually_failed_indices, batch_failed_indices);
+    }
+
+    #[test]
+    fn prepared_verifier_only_verifies_proofs_of_its_padded_height() {
+        let fibonacci = |n| {
+            ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+                .with_input(bfe_array![n])
+        };
+        let log_2_fri_expansion_factor = DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
+        let (claim, proof) = prove_and_verify(fibonacci(3), log_2_fri_expansion_factor);
+        let (other_claim, other_proof) = prove_and_verify(fibonacci(4), log_2_fri_expansion_factor);
+        let (long_claim, long_proof) = prove_and_verify(fibonacci(300), log_2_fri_expansion_factor);
+
+        let padded_height = proof.padded_height().unwrap();
+        assert!(padded_height == other_proof.padded_height().unwrap());
+        let long_padded_height = long_proof.padded_height().unwrap();
+        assert!(padded_height != long_padded_height);
+
+        let stark = low_security_stark(log_2_fri_expansion_factor);
+        let prepared_verifier = PreparedVerifier::new(stark, padded_height).unwrap();
+        assert!(padded_height == prepared_verifier.padded_height());
+        assert!(let Ok(()) = prepared_verifier.verify(&claim, &proof));
+        assert!(let Ok(()) = prepared_verifier.verify(&other_claim, &other_proof));
+
+        let_assert!(Err(err) = prepared_verifier.verify(&other_claim, &proof));
+        assert!(let VerificationError::OutOfDomainQuotientValueMismatch = err);
+
+        let_assert!(Err(err) = prepared_verifier.verify(&long_claim, &long_proof));
+        let_assert!(VerificationError::PaddedHeightMismatch { expected, actual } = err);
+        assert!(padded_height == expected);
+        assert!(long_padded_height == actual);
+    }
+
+    #[test]
+    fn grinding_reduces_number_of_collinearity_checks_at_same_security_level() {
+        let stark = Stark::default();
+        let grinding_stark = stark.with_grinding_bits(20);
+        assert!(stark.security_level == grinding_stark.security_level);
+        assert!(stark.num_collinearity_checks - 10 == grinding_stark.num_collinearity_checks);
+        assert!(stark.num_trace_randomizers > grinding_stark.num_trace_randomizers);
+        assert!(stark == grinding_stark.with_grinding_bits(0));
+    }
+
+    #[test]
+    fn prove_and_verify_with_grinding() {
+        let program_and_input =
+            ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+                .with_input(bfe_array![100]);
+        let claim = program_and_input.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program_and_input.program,
+            program_and_input.public_input,
+            program_and_input.non_determinism,
+        )
+        .unwrap();
+
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        let grinding_stark = stark.with_grinding_bits(8);
+        let proof = Prover::new(grinding_stark)
+            .set_randomness_seed_which_may_break_zero_knowledge([8; 32])
+            .prove(&claim, &aet)
+            .unwrap();
+        assert!(let Ok(()) = grinding_stark.verify(&claim, &proof));
+        assert!(let Err(_) = stark.verify(&claim, &proof));
+
+        let mut proof_stream = ProofStream::try_from(&proof).unwrap();
+        let_assert!(
+            Some(ProofItem::ProofOfWork(nonce)) = proof_stream
+                .items
+                .iter_mut()
+                .find(|item| matches!(item, ProofItem::ProofOfWork(_)))
+        );
+        *nonce += 1;
+        let tampered_proof = proof_stream.into();
+        let_assert!(Err(err) = grinding_stark.verify(&claim, &tampered_proof));
+        let_assert!(VerificationError::FriValidationError(err) = err);
+        assert!(let FriValidationError::InsufficientProofOfWork = err);
+    }
+
+    #[proptest(cases = 3)]
+    fn prove_and_verify_with_larger_fri_folding_factor(
+        #[strategy(2_usize..=4)] log_2_fri_folding_factor: usize,
+    ) {
+        let program_and_input =
+            ProgramAndInput::new(crate::example_programs::FIBONACCI_SEQUENCE.clone())
+                .with_input(bfe_array![100]);
+        let claim = program_and_input.claim().unwrap();
+        let (aet, _) = VM::trace_execution(
+            program_and_input.program,
+            program_and_input.public_input,
+            program_and_input.non_determinism,
+        )
+        .unwrap();
+
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        let folding_stark = stark.with_fri_folding_factor(1 << log_2_fri_folding_factor);
+        let padded_height = aet.padded_height();
+        let num_rounds = stark.fri(padded_height).unwrap().num_rounds();
+        let num_folding_rounds = folding_stark.fri(padded_height).unwrap().num_rounds();
+        prop_assert!(num_folding_rounds < num_rounds);
+
+        let proof = folding_stark.prove(&claim, &aet).unwrap();
+        assert!(let Ok(()) = folding_stark.verify(&claim, &proof));
+        assert!(let Err(_) = stark.verify(&claim, &proof));
+    }
+
+    #[test]
+    #[should_panic(expected = "power of 2")]
+    fn fri_folding_factor_must_be_power_of_two() {
+        let _stark = Stark::default().with_fri_folding_factor(3);
+    }
+
+    #[test]
+    fn verifier_cannot_be_prepared_for_padded_height_that_is_not_a_power_of_two() {
+        let stark = low_security_stark(DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS);
+        assert!(let Err(_) = PreparedVerifier::new(stark, 3 << 10));
+    }
+
     #[proptest]
     fn negative_log_2_floor(
         #[strategy(arb())]
@@ -0,0 +1,412 @@
+//! Tests for the [standard library](isa::stdlib) of Triton assembly. They live
+//! here because running the functions requires Triton VM.
+
+use std::collections::HashMap;
+
+use assert2::assert;
+use assert2::let_assert;
+use isa::stdlib::arithmetic;
+use isa::stdlib::list;
+use isa::stdlib::memory;
+use isa::stdlib::merkle;
+use isa::stdlib::polynomial;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest_arbitrary_interop::arb;
+use test_strategy::proptest;
+use twenty_first::prelude::*;
+
+use crate::error::SnippetTestError;
+use crate::prelude::*;
+use crate::snippet_test::SnippetTest;
+
+/// The clock cycles of a [`SnippetTest`] that are not spent in the tested
+/// function: the `call` of the function, the `return` to the test, and the
+/// final `halt`.
+const OVERHEAD_CLOCK_CYCLES: u32 = 3;
+
+/// A [`SnippetTest`] that calls the given function of the standard library.
+fn test_function(function: Vec<LabelledInstruction>) -> SnippetTest {
+    let_assert!(Some(LabelledInstruction::Label(label)) = function.first());
+    SnippetTest::new(triton_asm!(call {label} return {&function}))
+}
+
+/// Run the function and return the number of clock cycles it took, panicking if
+/// it does not behave as expected.
+fn clock_cycles(test: SnippetTest) -> u32 {
+    match test.run() {
+        Ok(final_state) => final_state.cycle_count - OVERHEAD_CLOCK_CYCLES,
+        Err(err) => panic!("{err}"),
+    }
+}
+
+fn assert_crashes(test: SnippetTest) {
+    let_assert!(Err(SnippetTestError::Crash(_)) = test.run());
+}
+
+fn u64_limbs(x: u64) -> Vec<BFieldElement> {
+    bfe_vec![x >> 32, x & u64::from(u32::MAX)]
+}
+
+fn u128_limbs(x: u128) -> Vec<BFieldElement> {
+    (0..4).rev().map(|i| bfe!((x >> (32 * i)) as u32)).collect()
+}
+
+fn u64_test(function: Vec<LabelledInstruction>, a: u64, b: u64) -> SnippetTest {
+    test_function(function).with_initial_stack([u64_limbs(a), u64_limbs(b)].concat())
+}
+
+fn u128_test(function: Vec<LabelledInstruction>, a: u128, b: u128) -> SnippetTest {
+    test_function(function).with_initial_stack([u128_limbs(a), u128_limbs(b)].concat())
+}
+
+#[proptest]
+fn u64_add(a: u64, b: u64) {
+    let test = u64_test(arithmetic::u64_add(), a, b);
+    let Some(sum) = a.checked_add(b) else {
+        assert_crashes(test);
+        return Ok(());
+    };
+    assert!(13 == clock_cycles(test.expect_stack(u64_limbs(sum))));
+}
+
+#[proptest]
+fn u64_sub(a: u64, b: u64) {
+    let test = u64_test(arithmetic::u64_sub(), a, b);
+    let Some(difference) = a.checked_sub(b) else {
+        assert_crashes(test);
+        return Ok(());
+    };
+    assert!(18 == clock_cycles(test.expect_stack(u64_limbs(difference))));
+}
+
+#[proptest]
+fn u64_lt(a: u64, #[strategy(prop_oneof![Just(#a), arb()])] b: u64) {
+    let test = u64_test(arithmetic::u64_lt(), a, b).expect_stack(bfe_vec![u64::from(a < b)]);
+    assert!(14 == clock_cycles(test));
+}
+
+#[proptest]
+fn u64_eq(a: u64, #[strategy(prop_oneof![Just(#a), arb()])] b: u64) {
+    let test = u64_test(arithmetic::u64_eq(), a, b).expect_stack(bfe_vec![u64::from(a == b)]);
+    assert!(7 == clock_cycles(test));
+}
+
+#[proptest]
+fn u64_mul(a: u64, b: u64) {
+    let product = u128::from(a) * u128::from(b);
+    let test = u64_test(arithmetic::u64_mul(), a, b).expect_stack(u128_limbs(product));
+    assert!(33 == clock_cycles(test));
+}
+
+#[proptest]
+fn u64_mul_of_extreme_values(
+    #[strategy(prop_oneof![Just(0), Just(1), Just(u64::MAX)])] a: u64,
+    #[strategy(prop_oneof![Just(0), Just(1), Just(u64::MAX)])] b: u64,
+) {
+    let product = u128::from(a) * u128::from(b);
+    let test = u64_test(arithmetic::u64_mul(), a, b).expect_stack(u128_limbs(product));
+    assert!(33 == clock_cycles(test));
+}
+
+#[proptest]
+fn u64_shr(a: u64, #[strategy(0_u32..32)] shift: u32) {
+    let stack = [u64_limbs(a), bfe_vec![shift]].concat();
+    let test = test_function(arithmetic::u64_shr())
+        .with_initial_stack(stack)
+        .expect_stack(u64_limbs(a >> shift));
+    assert!(23 == clock_cycles(test));
+}
+
+#[proptest]
+fn u64_shr_by_too_much_crashes(a: u64, #[strategy(32_u32..)] shift: u32) {
+    let stack = [u64_limbs(a), bfe_vec![shift]].concat();
+    assert_crashes(test_function(arithmetic::u64_shr()).with_initial_stack(stack));
+}
+
+#[proptest]
+fn u128_add(a: u128, b: u128) {
+    let test = u128_test(arithmetic::u128_add(), a, b);
+    let Some(sum) = a.checked_add(b) else {
+        assert_crashes(test);
+        return Ok(());
+    };
+    assert!(23 == clock_cycles(test.expect_stack(u128_limbs(sum))));
+}
+
+#[proptest]
+fn u128_add_with_carry_through_all_limbs(#[strategy(1_u128..)] b: u128) {
+    let a = u128::MAX - b + 1;
+    assert_crashes(u128_test(arithmetic::u128_add(), a, b));
+
+    let a = a - 1;
+    let test = u128_test(arithmetic::u128_add(), a, b).expect_stack(u128_limbs(u128::MAX));
+    assert!(23 == clock_cycles(test));
+}
+
+#[proptest]
+fn u128_sub(a: u128, b: u128) {
+    let test = u128_test(arithmetic::u128_sub(), a, b);
+    let Some(difference) = a.checked_sub(b) else {
+        assert_crashes(test);
+        return Ok(());
+    };
+    assert!(36 == clock_cycles(test.expect_stack(u128_limbs(difference))));
+}
+
+#[proptest]
+fn u128_sub_with_borrow_through_all_limbs(#[strategy(1_u128..)] b: u128) {
+    let test = u128_test(arithmetic::u128_sub(), b, b - 1).expect_stack(u128_limbs(1));
+    assert!(36 == clock_cycles(test));
+    assert_crashes(u128_test(arithmetic::u128_sub(), b - 1, b));
+}
+
+#[proptest]
+fn u128_lt(a: u128, #[strategy(prop_oneof![Just(#a), arb()])] b: u128) {
+    let test = u128_test(arithmetic::u128_lt(), a, b).expect_stack(bfe_vec![u64::from(a < b)]);
+    assert!(36 == clock_cycles(test));
+}
+
+#[proptest]
+fn u128_lt_decided_by_least_significant_limb(a: u128, #[strategy(0_u128..1 << 32)] offset: u128) {
+    let b = a.saturating_add(offset);
+    let test = u128_test(arithmetic::u128_lt(), a, b).expect_stack(bfe_vec![u64::from(a < b)]);
+    assert!(36 == clock_cycles(test));
+}
+
+#[proptest]
+fn u128_eq(a: u128, #[strategy(prop_oneof![Just(#a), arb()])] b: u128) {
+    let test = u128_test(arithmetic::u128_eq(), a, b).expect_stack(bfe_vec![u64::from(a == b)]);
+    assert!(15 == clock_cycles(test));
+}
+
+#[test]
+fn comparing_non_u32_limbs_crashes() {
+    let not_a_u32 = bfe!(1_u64 << 32);
+    let stack = [bfe_vec![0, not_a_u32], u64_limbs(0)].concat();
+    assert_crashes(test_function(arithmetic::u64_lt()).with_initial_stack(stack));
+}
+
+/// A list with the given elements at the given address, as RAM.
+fn list_ram(
+    address: BFieldElement,
+    elements: &[BFieldElement],
+) -> HashMap<BFieldElement, BFieldElement> {
+    let length = bfe!(elements.len() as u64);
+    (0..)
+        .map(|i| address + bfe!(i))
+        .zip([vec![length], elements.to_vec()].concat())
+        .collect()
+}
+
+#[proptest]
+fn list_length(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 0..10))] elements: Vec<BFieldElement>,
+) {
+    let test = test_function(list::length())
+        .with_initial_stack(bfe_vec![address])
+        .with_initial_ram(list_ram(address, &elements))
+        .expect_stack(bfe_vec![elements.len() as u64]);
+    assert!(3 == clock_cycles(test));
+}
+
+#[proptest]
+fn list_get(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 1..10))] elements: Vec<BFieldElement>,
+    #[strategy(0..#elements.len())] index: usize,
+) {
+    let test = test_function(list::get())
+        .with_initial_stack(bfe_vec![address, index as u64])
+        .with_initial_ram(list_ram(address, &elements))
+        .expect_stack(bfe_vec![elements[index]]);
+    assert!(11 == clock_cycles(test));
+}
+
+#[proptest]
+fn list_get_out_of_bounds_crashes(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 0..10))] elements: Vec<BFieldElement>,
+    #[strategy(#elements.len()..20)] index: usize,
+) {
+    let test = test_function(list::get())
+        .with_initial_stack(bfe_vec![address, index as u64])
+        .with_initial_ram(list_ram(address, &elements));
+    assert_crashes(test);
+}
+
+#[proptest]
+fn list_set(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 1..10))] elements: Vec<BFieldElement>,
+    #[strategy(0..#elements.len())] index: usize,
+    #[strategy(arb())] element: BFieldElement,
+) {
+    let mut new_elements = elements.clone();
+    new_elements[index] = element;
+    let test = test_function(list::set())
+        .with_initial_stack(bfe_vec![element, address, index as u64])
+        .with_initial_ram(list_ram(address, &elements))
+        .expect_stack(vec![])
+        .expect_ram(list_ram(address, &new_elements));
+    assert!(11 == clock_cycles(test));
+}
+
+#[proptest]
+fn list_set_out_of_bounds_crashes(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 0..10))] elements: Vec<BFieldElement>,
+    #[strategy(#elements.len()..20)] index: usize,
+    #[strategy(arb())] element: BFieldElement,
+) {
+    let test = test_function(list::set())
+        .with_initial_stack(bfe_vec![element, address, index as u64])
+        .with_initial_ram(list_ram(address, &elements));
+    assert_crashes(test);
+}
+
+#[proptest]
+fn list_push(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 0..10))] elements: Vec<BFieldElement>,
+    #[strategy(arb())] element: BFieldElement,
+) {
+    let new_elements = [elements.clone(), vec![element]].concat();
+    let test = test_function(list::push())
+        .with_initial_stack(bfe_vec![address, element])
+        .with_initial_ram(list_ram(address, &elements))
+        .expect_stack(vec![])
+        .expect_ram(list_ram(address, &new_elements));
+    assert!(11 == clock_cycles(test));
+}
+
+#[proptest]
+fn list_pop(
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(vec(arb(), 1..10))] elements: Vec<BFieldElement>,
+) {
+    let (&last, remaining) = elements.split_last().unwrap();
+
+    // The popped element stays in RAM.
+    let mut expected_ram = list_ram(address, &elements);
+    expected_ram.insert(address, bfe!(remaining.len() as u64));
+    let test = test_function(list::pop())
+        .with_initial_stack(bfe_vec![address])
+        .with_initial_ram(list_ram(address, &elements))
+        .expect_stack(bfe_vec![last])
+        .expect_ram(expected_ram);
+    assert!(19 == clock_cycles(test));
+}
+
+#[proptest]
+fn popping_from_empty_list_crashes(#[strategy(arb())] address: BFieldElement) {
+    let test = test_function(list::pop()).with_initial_stack(bfe_vec![address]);
+    assert_crashes(test);
+}
+
+#[proptest]
+fn memcpy(
+    #[strategy(vec(arb(), 0..20))] words: Vec<BFieldElement>,
+    #[strategy(0_u64..1 << 20)] src: u64,
+    #[strategy(1_u64 << 30..1 << 31)] dst: u64,
+) {
+    let region = |start: u64| (start..).map(BFieldElement::new).zip(words.clone());
+    let initial_ram = region(src).collect::<HashMap<_, _>>();
+    let expected_ram = region(src).chain(region(dst)).collect::<HashMap<_, _>>();
+
+    let num_words = words.len() as u64;
+    let test = test_function(memory::memcpy())
+        .with_initial_stack(bfe_vec![dst, src, num_words])
+        .with_initial_ram(initial_ram)
+        .expect_stack(vec![])
+        .expect_ram(expected_ram);
+    assert!(8 + 13 * num_words as u32 == clock_cycles(test));
+}
+
+#[proptest]
+fn memcpy_to_overlapping_region_below_source(
+    #[strategy(vec(arb(), 1..20))] words: Vec<BFieldElement>,
+    #[strategy(1..=#words.len())] distance: usize,
+) {
+    let num_words = words.len();
+    let src = distance as u64;
+    let initial_ram = (src..)
+        .map(BFieldElement::new)
+        .zip(words.clone())
+        .collect::<HashMap<_, _>>();
+
+    let final_state = test_function(memory::memcpy())
+        .with_initial_stack(bfe_vec![0, src, num_words as u64])
+        .with_initial_ram(initial_ram)
+        .expect_stack(vec![])
+        .run()
+        .unwrap();
+    let copy = (0..num_words as u64)
+        .map(|address| final_state.ram[&bfe!(address)])
+        .collect::<Vec<_>>();
+    assert!(words == copy);
+}
+
+#[proptest(cases = 20)]
+fn merkle_root(
+    #[strategy(0_usize..8)] height: usize,
+    #[strategy(vec(arb(), 1 << #height))] leaves: Vec<Digest>,
+    #[strategy(0..#leaves.len())] leaf_index: usize,
+) {
+    let merkle_tree = MerkleTree::new::<CpuParallel>(&leaves).unwrap();
+    let authentication_path = merkle_tree.authentication_structure(&[leaf_index]).unwrap();
+
+    let leaf = leaves[leaf_index].reversed().values();
+    let root = merkle_tree.root().reversed().values();
+    let initial_stack = [bfe_vec![leaf_index as u64, height as u64], leaf.to_vec()].concat();
+    let test = test_function(merkle::root())
+        .with_initial_stack(initial_stack)
+        .with_non_determinism(NonDeterminism::default().with_digests(authentication_path))
+        .expect_stack(root.to_vec());
+
+    let expected_clock_cycles = match height {
+        0 => 26,
+        _ => 27 + 2 * height as u32,
+    };
+    assert!(expected_clock_cycles == clock_cycles(test));
+}
+
+#[proptest]
+fn merkle_root_of_out_of_bounds_leaf_crashes(
+    #[strategy(0_u64..8)] height: u64,
+    #[strategy(1_u64 << #height..1 << 10)] leaf_index: u64,
+) {
+    let initial_stack = [bfe_vec![leaf_index, height], bfe_vec![0; Digest::LEN]].concat();
+    assert_crashes(test_function(merkle::root()).with_initial_stack(initial_stack));
+}
+
+#[proptest]
+fn merkle_root_of_too_high_tree_crashes(#[strategy(32_u64..100)] height: u64) {
+    let initial_stack = [bfe_vec![0, height], bfe_vec![0; Digest::LEN]].concat();
+    assert_crashes(test_function(merkle::root()).with_initial_stack(initial_stack));
+}
+
+#[proptest]
+fn xfe_polynomial_evaluation(
+    #[strategy(vec(arb(), 0..10))] coefficients: Vec<XFieldElement>,
+    #[strategy(arb())] address: BFieldElement,
+    #[strategy(arb())] x: XFieldElement,
+) {
+    let ram = (0..)
+        .map(|i| address + bfe!(i))
+        .zip(coefficients.iter().flat_map(|c| c.coefficients))
+        .collect::<HashMap<_, _>>();
+    let num_coefficients = coefficients.len() as u64;
+    let value = Polynomial::new(coefficients).evaluate_in_same_field(x);
+
+    let stack = |xfe: XFieldElement| xfe.coefficients.into_iter().rev();
+    let initial_stack = bfe_vec![address, num_coefficients]
+        .into_iter()
+        .chain(stack(x));
+    let test = test_function(polynomial::xfe_evaluate())
+        .with_initial_stack(initial_stack.collect())
+        .with_initial_ram(ram)
+        .expect_stack(stack(value).collect());
+    assert!(24 + 16 * num_coefficients as u32 == clock_cycles(test));
+}
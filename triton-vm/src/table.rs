@@ -20,6 +20,7 @@ pub mod hash;
 pub mod jump_stack;
 pub mod lookup;
 pub mod master_table;
+mod memory_mapped;
 pub mod op_stack;
 pub mod processor;
 pub mod program;
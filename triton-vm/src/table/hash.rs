@@ -613,9 +613,9 @@ pub(crate) mod tests {
     use std::collections::HashMap;
     use strum::IntoEnumIterator;
 
-    use crate::shared_tests::ProgramAndInput;
     use crate::stark::tests::master_tables_for_low_security_level;
     use crate::table::master_table::MasterTable;
+    use crate::test_utils::ProgramAndInput;
     use crate::triton_asm;
     use crate::triton_program;
     use crate::vm::VM;
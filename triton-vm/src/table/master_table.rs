@@ -102,11 +102,13 @@ use crate::ndarray_helper;
 use crate::ndarray_helper::horizontal_multi_slice_mut;
 use crate::ndarray_helper::partial_sums;
 use crate::profiler::profiler;
+use crate::progress::CancellationToken;
 use crate::stark::NUM_RANDOMIZER_POLYNOMIALS;
 use crate::table::auxiliary_table::all_degrees_with_origin;
 use crate::table::auxiliary_table::DegreeWithOrigin;
 use crate::table::auxiliary_table::Evaluable;
 use crate::table::degree_lowering::DegreeLoweringTable;
+use crate::table::memory_mapped::MemoryMappedArray2;
 use crate::table::processor::ClkJumpDiffs;
 use crate::table::AuxiliaryRow;
 use crate::table::MainRow;
@@ -133,7 +135,8 @@ use crate::table::TraceTable;
 ///    The results are stored on the [`MasterMainTable`] for quick access later.
 ///    If there is not enough RAM, then the low-degree extensions of the trace columns will be
 ///    computed and sometimes recomputed just-in-time, and the memory freed afterward.
-///    The caching behavior [can be forced][overwrite_cache].
+///    The caching behavior [can be forced][overwrite_cache], which also allows
+///    caching the low-degree extensions in memory-mapped temporary files.
 /// 5. The [`MasterMainTable`] is used to derive the [`MasterAuxiliaryTable`][master_aux_table]
 ///     using logic from the individual tables.
 /// 6. The [`MasterAuxiliaryTable`][master_aux_table] is trace-randomized.
@@ -224,17 +227,43 @@ where
     /// [table]: Self::quotient_domain_table
     /// [cached]: crate::config::overwrite_lde_trace_caching_to
     fn maybe_low_degree_extend_all_columns(&mut self) {
+        let decision = crate::config::cache_lde_trace();
+        self.maybe_low_degree_extend_all_columns_subject_to(decision, &CancellationToken::new())
+            .expect("a fresh token is never cancelled");
+    }
+
+    /// Like [`Self::maybe_low_degree_extend_all_columns`], but with the given
+    /// caching decision. `None` means the decision is made automatically, based on
+    /// free memory.
+    ///
+    /// Stops early with [`ProvingError::Cancelled`] if the given token is
+    /// cancelled, in which case nothing is cached.
+    #[doc(hidden)]
+    fn maybe_low_degree_extend_all_columns_subject_to(
+        &mut self,
+        decision: Option<CacheDecision>,
+        cancellation_token: &CancellationToken,
+    ) -> Result<(), ProvingError> {
         let evaluation_domain = self.evaluation_domain();
         let num_rows = evaluation_domain.length;
         let num_elements = num_rows * Self::NUM_COLUMNS;
 
         let mut extended_trace = Vec::with_capacity(0);
-        match crate::config::cache_lde_trace() {
-            Some(CacheDecision::NoCache) => return,
+        let mut memory_mapped_trace = None;
+        match decision {
+            Some(CacheDecision::NoCache) => return Ok(()),
             Some(CacheDecision::Cache) => extended_trace.reserve_exact(num_elements),
+            Some(CacheDecision::CacheOnDisk) => {
+                // Without a temporary file, fall back to just-in-time low-degree extension.
+                let shape = [num_rows, Self::NUM_COLUMNS];
+                let Ok(trace) = MemoryMappedArray2::from_elem(shape, Self::Field::ZERO) else {
+                    return Ok(());
+                };
+                memory_mapped_trace = Some(trace);
+            }
             None => {
                 let Ok(()) = extended_trace.try_reserve_exact(num_elements) else {
-                    return;
+                    return Ok(());
                 };
             }
         };
@@ -249,50 +278,72 @@ where
         Zip::from(column_indices.view())
             .and(interpolation_polynomials.axis_iter_mut(Axis(0)))
             .par_for_each(|&col_idx, poly| {
+                if cancellation_token.is_cancelled() {
+                    return;
+                }
                 let column_interpolant = self.randomized_column_interpolant(col_idx);
                 Array0::from_elem((), column_interpolant).move_into(poly);
             });
         profiler!(stop "interpolation");
+        if let Err(cancelled) = cancellation_token.check() {
+            profiler!(stop "LDE");
+            return Err(cancelled);
+        }
 
         profiler!(start "resize");
-        assert!(extended_trace.capacity() >= num_elements);
-        extended_trace
-            .spare_capacity_mut()
-            .par_iter_mut()
-            .for_each(|e| *e = MaybeUninit::new(Self::Field::ZERO));
-
-        unsafe {
-            // Speed up initialization through parallelization.
-            //
-            // SAFETY:
-            // 1. The capacity is sufficiently large – see above `assert!`.
-            // 2. The length is set to equal (or less than) the capacity.
-            // 3. Each element in the spare capacity is initialized.
-            extended_trace.set_len(num_elements);
-        }
-        let mut extended_columns =
-            Array2::from_shape_vec([num_rows, Self::NUM_COLUMNS], extended_trace).unwrap();
+        let mut extended_columns = if let Some(trace) = memory_mapped_trace {
+            LowDegreeExtendedTable::on_disk(trace)
+        } else {
+            assert!(extended_trace.capacity() >= num_elements);
+            extended_trace
+                .spare_capacity_mut()
+                .par_iter_mut()
+                .for_each(|e| *e = MaybeUninit::new(Self::Field::ZERO));
+
+            unsafe {
+                // Speed up initialization through parallelization.
+                //
+                // SAFETY:
+                // 1. The capacity is sufficiently large – see above `assert!`.
+                // 2. The length is set to equal (or less than) the capacity.
+                // 3. Each element in the spare capacity is initialized.
+                extended_trace.set_len(num_elements);
+            }
+            let extended_trace =
+                Array2::from_shape_vec([num_rows, Self::NUM_COLUMNS], extended_trace).unwrap();
+            LowDegreeExtendedTable::in_memory(extended_trace)
+        };
         profiler!(stop "resize");
 
         profiler!(start "evaluation");
-        Zip::from(extended_columns.axis_iter_mut(Axis(1)))
+        Zip::from(extended_columns.view_mut().axis_iter_mut(Axis(1)))
             .and(interpolation_polynomials.axis_iter(Axis(0)))
             .par_for_each(|lde_column, interpolant| {
+                if cancellation_token.is_cancelled() {
+                    return;
+                }
                 let lde_codeword = evaluation_domain.evaluate(&interpolant[()]);
                 Array1::from(lde_codeword).move_into(lde_column);
             });
         profiler!(stop "evaluation");
+        if let Err(cancelled) = cancellation_token.check() {
+            profiler!(stop "LDE");
+            return Err(cancelled);
+        }
+
         profiler!(start "memoize");
         self.memoize_low_degree_extended_table(extended_columns);
         profiler!(stop "memoize");
         profiler!(stop "LDE");
+
+        Ok(())
     }
 
     /// Not intended for direct use, but through [`Self::maybe_low_degree_extend_all_columns`].
     #[doc(hidden)]
     fn memoize_low_degree_extended_table(
         &mut self,
-        low_degree_extended_columns: Array2<Self::Field>,
+        low_degree_extended_columns: LowDegreeExtendedTable<Self::Field>,
     );
 
     #[doc(hidden)]
@@ -405,23 +456,50 @@ where
 
     /// Compute a Merkle tree of the FRI domain table. Every row gives one leaf in the tree.
     fn merkle_tree(&self) -> MerkleTree {
+        self.merkle_tree_unless_cancelled(&CancellationToken::new())
+            .expect("a fresh token is never cancelled")
+    }
+
+    /// Like [`Self::merkle_tree`], but stops early with
+    /// [`ProvingError::Cancelled`] if the given token is cancelled.
+    #[doc(hidden)]
+    fn merkle_tree_unless_cancelled(
+        &self,
+        cancellation_token: &CancellationToken,
+    ) -> Result<MerkleTree, ProvingError> {
         profiler!(start "leafs");
-        let hashed_rows = self.hash_all_fri_domain_rows();
+        let hashed_rows = self.hash_all_fri_domain_rows_unless_cancelled(cancellation_token);
         profiler!(stop "leafs");
+        let hashed_rows = hashed_rows?;
 
         profiler!(start "Merkle tree" ("hash"));
         let merkle_tree = MerkleTree::new::<CpuParallel>(&hashed_rows).unwrap();
         profiler!(stop "Merkle tree");
 
-        merkle_tree
+        Ok(merkle_tree)
     }
 
     fn hash_all_fri_domain_rows(&self) -> Vec<Digest> {
+        self.hash_all_fri_domain_rows_unless_cancelled(&CancellationToken::new())
+            .expect("a fresh token is never cancelled")
+    }
+
+    /// Like [`Self::hash_all_fri_domain_rows`], but stops early with
+    /// [`ProvingError::Cancelled`] if the given token is cancelled.
+    #[doc(hidden)]
+    fn hash_all_fri_domain_rows_unless_cancelled(
+        &self,
+        cancellation_token: &CancellationToken,
+    ) -> Result<Vec<Digest>, ProvingError> {
         if let Some(fri_domain_table) = self.fri_domain_table() {
             profiler!(start "hash rows" ("hash"));
             let all_rows = fri_domain_table.axis_iter(Axis(0)).into_par_iter();
             let all_digests = all_rows
-                .map(|row| Tip5::hash_varlen(&row.iter().flat_map(|e| e.encode()).collect_vec()))
+                .map(|row| {
+                    cancellation_token.check()?;
+                    let row = row.iter().flat_map(|e| e.encode()).collect_vec();
+                    Ok(Tip5::hash_varlen(&row))
+                })
                 .collect();
             profiler!(stop "hash rows");
 
@@ -430,9 +508,7 @@ where
 
         // Now knowing that the low-degree extensions are not cached, hash all FRI
         // domain rows of the table using just-in-time low-degree-extension.
-        let num_threads = std::thread::available_parallelism()
-            .map(|x| x.get())
-            .unwrap_or(1);
+        let num_threads = rayon::current_num_threads();
         let eval_domain = self.evaluation_domain();
         let mut sponge_states = vec![SpongeWithPendingAbsorb::new(); eval_domain.length];
 
@@ -444,11 +520,16 @@ where
             Zip::from(column_indices)
                 .and(codewords.axis_iter_mut(Axis(1)))
                 .par_for_each(|&col_idx, target_column| {
+                    if cancellation_token.is_cancelled() {
+                        return;
+                    }
                     let column_interpolant = self.randomized_column_interpolant(col_idx);
                     let lde_codeword = eval_domain.evaluate(&column_interpolant);
                     Array1::from(lde_codeword).move_into(target_column);
                 });
             profiler!(stop "LDE");
+            cancellation_token.check()?;
+
             profiler!(start "hash rows" ("hash"));
             sponge_states
                 .par_iter_mut()
@@ -457,10 +538,12 @@ where
             profiler!(stop "hash rows");
         }
 
-        sponge_states
+        let all_digests = sponge_states
             .into_par_iter()
             .map(|sponge| sponge.finalize())
-            .collect()
+            .collect();
+
+        Ok(all_digests)
     }
 
     /// The linear combination of the trace-randomized columns using the given
@@ -641,6 +724,59 @@ impl SpongeWithPendingAbsorb {
     }
 }
 
+/// A [low-degree extended][lde] table, held either in RAM or, if so
+/// [configured][CacheDecision::CacheOnDisk], in a memory-mapped temporary file.
+///
+/// [lde]: MasterTable::maybe_low_degree_extend_all_columns
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct LowDegreeExtendedTable<F>(LowDegreeExtendedStorage<F>);
+
+#[derive(Debug)]
+enum LowDegreeExtendedStorage<F> {
+    InMemory(Array2<F>),
+    OnDisk(MemoryMappedArray2<F>),
+}
+
+impl<F> LowDegreeExtendedTable<F>
+where
+    F: Copy + Send + Sync,
+{
+    fn in_memory(table: Array2<F>) -> Self {
+        Self(LowDegreeExtendedStorage::InMemory(table))
+    }
+
+    fn on_disk(table: MemoryMappedArray2<F>) -> Self {
+        Self(LowDegreeExtendedStorage::OnDisk(table))
+    }
+
+    fn view(&self) -> ArrayView2<F> {
+        match &self.0 {
+            LowDegreeExtendedStorage::InMemory(table) => table.view(),
+            LowDegreeExtendedStorage::OnDisk(table) => table.view(),
+        }
+    }
+
+    fn view_mut(&mut self) -> ArrayViewMut2<F> {
+        match &mut self.0 {
+            LowDegreeExtendedStorage::InMemory(table) => table.view_mut(),
+            LowDegreeExtendedStorage::OnDisk(table) => table.view_mut(),
+        }
+    }
+}
+
+impl<F> Clone for LowDegreeExtendedTable<F>
+where
+    F: Copy + Send + Sync,
+{
+    fn clone(&self) -> Self {
+        match &self.0 {
+            LowDegreeExtendedStorage::InMemory(table) => Self::in_memory(table.clone()),
+            LowDegreeExtendedStorage::OnDisk(table) => Self::on_disk(table.clone()),
+        }
+    }
+}
+
 /// See [`MasterTable`].
 #[derive(Debug, Clone)]
 pub struct MasterMainTable {
@@ -662,7 +798,7 @@ pub struct MasterMainTable {
     trace_table: Array2<BFieldElement>,
     trace_randomizer_seed: <StdRng as SeedableRng>::Seed,
 
-    low_degree_extended_table: Option<Array2<BFieldElement>>,
+    low_degree_extended_table: Option<LowDegreeExtendedTable<BFieldElement>>,
 }
 
 /// See [`MasterTable`].
@@ -678,7 +814,7 @@ pub struct MasterAuxTable {
     trace_table: Array2<XFieldElement>,
     trace_randomizer_seed: <StdRng as SeedableRng>::Seed,
 
-    low_degree_extended_table: Option<Array2<XFieldElement>>,
+    low_degree_extended_table: Option<LowDegreeExtendedTable<XFieldElement>>,
 }
 
 impl MasterTable for MasterMainTable {
@@ -711,20 +847,20 @@ impl MasterTable for MasterMainTable {
     }
 
     fn quotient_domain_table(&self) -> Option<ArrayView2<BFieldElement>> {
-        let table = &self.low_degree_extended_table.as_ref()?;
+        let table = self.low_degree_extended_table.as_ref()?.view();
         let nrows = table.nrows();
 
         if self.quotient_domain.length < nrows {
             let unit_distance = nrows / self.quotient_domain.length;
-            Some(table.slice(s![0..nrows;unit_distance, ..]))
+            Some(table.slice_move(s![0..nrows;unit_distance, ..]))
         } else {
-            Some(table.view())
+            Some(table)
         }
     }
 
     fn memoize_low_degree_extended_table(
         &mut self,
-        low_degree_extended_columns: Array2<BFieldElement>,
+        low_degree_extended_columns: LowDegreeExtendedTable<BFieldElement>,
     ) {
         self.low_degree_extended_table = Some(low_degree_extended_columns);
     }
@@ -734,13 +870,13 @@ impl MasterTable for MasterMainTable {
     }
 
     fn fri_domain_table(&self) -> Option<ArrayView2<BFieldElement>> {
-        let table = self.low_degree_extended_table.as_ref()?;
+        let table = self.low_degree_extended_table.as_ref()?.view();
         let nrows = table.nrows();
         if nrows > self.fri_domain.length {
             let unit_step = nrows / self.fri_domain.length;
-            Some(table.slice(s![0..nrows;unit_step, ..]))
+            Some(table.slice_move(s![0..nrows;unit_step, ..]))
         } else {
-            Some(table.view())
+            Some(table)
         }
     }
 
@@ -784,19 +920,19 @@ impl MasterTable for MasterAuxTable {
     }
 
     fn quotient_domain_table(&self) -> Option<ArrayView2<XFieldElement>> {
-        let table = self.low_degree_extended_table.as_ref()?;
+        let table = self.low_degree_extended_table.as_ref()?.view();
         let nrows = table.nrows();
         if nrows > self.quotient_domain.length {
             let unit_distance = nrows / self.quotient_domain.length;
-            Some(table.slice(s![0..nrows;unit_distance, ..]))
+            Some(table.slice_move(s![0..nrows;unit_distance, ..]))
         } else {
-            Some(table.view())
+            Some(table)
         }
     }
 
     fn memoize_low_degree_extended_table(
         &mut self,
-        low_degree_extended_columns: Array2<XFieldElement>,
+        low_degree_extended_columns: LowDegreeExtendedTable<XFieldElement>,
     ) {
         self.low_degree_extended_table = Some(low_degree_extended_columns);
     }
@@ -806,13 +942,13 @@ impl MasterTable for MasterAuxTable {
     }
 
     fn fri_domain_table(&self) -> Option<ArrayView2<XFieldElement>> {
-        let table = self.low_degree_extended_table.as_ref()?;
+        let table = self.low_degree_extended_table.as_ref()?.view();
         let nrows = table.nrows();
         if nrows > self.fri_domain.length {
             let unit_step = nrows / self.fri_domain.length;
-            Some(table.slice(s![0..nrows;unit_step, ..]))
+            Some(table.slice_move(s![0..nrows;unit_step, ..]))
         } else {
-            Some(table.view())
+            Some(table)
         }
     }
 
@@ -1223,14 +1359,86 @@ pub fn all_quotients_combined(
     challenges: &Challenges,
     quotient_weights: &[XFieldElement],
 ) -> Vec<XFieldElement> {
-    assert_eq!(
-        quotient_domain.length,
-        quotient_domain_master_main_table.nrows(),
-    );
-    assert_eq!(
-        quotient_domain.length,
-        quotient_domain_master_aux_table.nrows()
-    );
+    all_quotients_combined_unless_cancelled(
+        quotient_domain_master_main_table,
+        quotient_domain_master_aux_table,
+        trace_domain,
+        quotient_domain,
+        challenges,
+        quotient_weights,
+        &CancellationToken::new(),
+    )
+    .expect("a fresh token is never cancelled")
+}
+
+/// Like [`all_quotients_combined`], but stops early with
+/// [`ProvingError::Cancelled`] if the given token is cancelled.
+pub(crate) fn all_quotients_combined_unless_cancelled(
+    quotient_domain_master_main_table: ArrayView2<BFieldElement>,
+    quotient_domain_master_aux_table: ArrayView2<XFieldElement>,
+    trace_domain: ArithmeticDomain,
+    quotient_domain: ArithmeticDomain,
+    challenges: &Challenges,
+    quotient_weights: &[XFieldElement],
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<XFieldElement>, ProvingError> {
+    let unit_distance = quotient_domain.length / trace_domain.length;
+    quotients_combined(
+        [quotient_domain_master_main_table; 2],
+        [quotient_domain_master_aux_table; 2],
+        unit_distance,
+        [trace_domain, quotient_domain],
+        challenges,
+        quotient_weights,
+        cancellation_token,
+    )
+}
+
+/// Like [`all_quotients_combined`], but for a quotient domain that is shorter
+/// than the trace domain. For such a domain, the “next row” of any row is not
+/// part of the domain. Instead, the next rows are the evaluations of the
+/// trace's interpolants on the quotient domain, shifted by the trace domain's
+/// generator.
+pub(crate) fn all_quotients_combined_with_next_rows(
+    [main_table, next_main_table]: [ArrayView2<BFieldElement>; 2],
+    [aux_table, next_aux_table]: [ArrayView2<XFieldElement>; 2],
+    trace_domain: ArithmeticDomain,
+    quotient_domain: ArithmeticDomain,
+    challenges: &Challenges,
+    quotient_weights: &[XFieldElement],
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<XFieldElement>, ProvingError> {
+    debug_assert!(quotient_domain.length <= trace_domain.length);
+    quotients_combined(
+        [main_table, next_main_table],
+        [aux_table, next_aux_table],
+        0,
+        [trace_domain, quotient_domain],
+        challenges,
+        quotient_weights,
+        cancellation_token,
+    )
+}
+
+/// The `next_row_distance` is the distance between the index of a row in the
+/// `current_*_table` and the index of its next row in the `next_*_table`.
+fn quotients_combined(
+    [current_main_table, next_main_table]: [ArrayView2<BFieldElement>; 2],
+    [current_aux_table, next_aux_table]: [ArrayView2<XFieldElement>; 2],
+    next_row_distance: usize,
+    [trace_domain, quotient_domain]: [ArithmeticDomain; 2],
+    challenges: &Challenges,
+    quotient_weights: &[XFieldElement],
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<XFieldElement>, ProvingError> {
+    for num_rows in [
+        current_main_table.nrows(),
+        current_aux_table.nrows(),
+        next_main_table.nrows(),
+        next_aux_table.nrows(),
+    ] {
+        assert_eq!(quotient_domain.length, num_rows);
+    }
     assert_eq!(MasterAuxTable::NUM_CONSTRAINTS, quotient_weights.len());
 
     let init_section_end = MasterAuxTable::NUM_INITIAL_CONSTRAINTS;
@@ -1256,12 +1464,12 @@ pub fn all_quotients_combined(
     let quotient_codeword = (0..quotient_domain.length)
         .into_par_iter()
         .map(|row_index| {
-            let unit_distance = quotient_domain.length / trace_domain.length;
-            let next_row_index = (row_index + unit_distance) % quotient_domain.length;
-            let current_row_main = quotient_domain_master_main_table.row(row_index);
-            let current_row_aux = quotient_domain_master_aux_table.row(row_index);
-            let next_row_main = quotient_domain_master_main_table.row(next_row_index);
-            let next_row_aux = quotient_domain_master_aux_table.row(next_row_index);
+            cancellation_token.check()?;
+            let next_row_index = (row_index + next_row_distance) % quotient_domain.length;
+            let current_row_main = current_main_table.row(row_index);
+            let current_row_aux = current_aux_table.row(row_index);
+            let next_row_main = next_main_table.row(next_row_index);
+            let next_row_aux = next_aux_table.row(next_row_index);
 
             let initial_constraint_values = MasterAuxTable::evaluate_initial_constraints(
                 current_row_main,
@@ -1308,7 +1516,7 @@ pub fn all_quotients_combined(
                 &quotient_weights[tran_section_end..],
             );
             quotient_value += terminal_inner_product * terminal_zerofier_inverse[row_index];
-            quotient_value
+            Ok(quotient_value)
         })
         .collect();
     profiler!(stop "evaluate AIR, compute quotient codeword");
@@ -1369,11 +1577,11 @@ mod tests {
     use crate::constraints::static_air_constraint_evaluation_tasm;
     use crate::memory_layout::DynamicTasmConstraintEvaluationMemoryLayout;
     use crate::memory_layout::StaticTasmConstraintEvaluationMemoryLayout;
-    use crate::shared_tests::ProgramAndInput;
     use crate::stark::tests::master_main_table_for_low_security_level;
     use crate::stark::tests::master_tables_for_low_security_level;
     use crate::table::degree_lowering::DegreeLoweringAuxColumn;
     use crate::table::degree_lowering::DegreeLoweringMainColumn;
+    use crate::test_utils::ProgramAndInput;
     use crate::triton_program;
 
     use super::*;
@@ -1499,6 +1707,68 @@ mod tests {
         row_hashes_are_identical(aux_table);
     }
 
+    #[test]
+    fn low_degree_extension_and_row_hashing_stop_once_cancelled() {
+        fn work_stops<FF>(mut table: impl MasterTable<Field = FF>)
+        where
+            Standard: Distribution<FF>,
+            XFieldElement: Add<FF, Output = XFieldElement>,
+        {
+            let token = CancellationToken::new();
+            token.cancel();
+
+            let hash_rows = table.hash_all_fri_domain_rows_unless_cancelled(&token);
+            assert_eq!(Err(ProvingError::Cancelled), hash_rows);
+
+            let cache = Some(CacheDecision::Cache);
+            let lde = table.maybe_low_degree_extend_all_columns_subject_to(cache, &token);
+            assert_eq!(Err(ProvingError::Cancelled), lde);
+            assert!(table.fri_domain_table().is_none());
+        }
+
+        let program = ProgramAndInput::new(triton_program!(halt));
+        let (_, _, main_table, aux_table, _) = master_tables_for_low_security_level(program);
+        work_stops(main_table);
+        work_stops(aux_table);
+    }
+
+    #[test]
+    fn low_degree_extended_table_is_independent_of_caching_on_disk() {
+        fn tables_are_identical<FF>(mut table: impl MasterTable<Field = FF> + Clone)
+        where
+            FF: Debug + PartialEq,
+            Standard: Distribution<FF>,
+            XFieldElement: Add<FF, Output = XFieldElement>,
+        {
+            let mut disk_table = table.clone();
+            let token = CancellationToken::new();
+            let cache = Some(CacheDecision::Cache);
+            let cache_on_disk = Some(CacheDecision::CacheOnDisk);
+            table
+                .maybe_low_degree_extend_all_columns_subject_to(cache, &token)
+                .unwrap();
+            disk_table
+                .maybe_low_degree_extend_all_columns_subject_to(cache_on_disk, &token)
+                .unwrap();
+
+            assert!(disk_table.fri_domain_table().is_some());
+            assert_eq!(table.fri_domain_table(), disk_table.fri_domain_table());
+            assert_eq!(
+                table.quotient_domain_table(),
+                disk_table.quotient_domain_table()
+            );
+            assert_eq!(
+                disk_table.fri_domain_table(),
+                disk_table.clone().fri_domain_table()
+            );
+        }
+
+        let program = ProgramAndInput::new(triton_program!(halt));
+        let (_, _, main_table, aux_table, _) = master_tables_for_low_security_level(program);
+        tables_are_identical(main_table);
+        tables_are_identical(aux_table);
+    }
+
     #[proptest]
     fn revealing_rows_is_independent_of_fri_table_caching(row_indices: Vec<usize>) {
         fn revealed_rows_are_identical<FF>(
@@ -0,0 +1,156 @@
+//! Two-dimensional arrays that live in memory-mapped temporary files instead of
+//! RAM. The operating system pages the data in and out as needed, trading
+//! speed for memory.
+
+use std::io;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::mem::MaybeUninit;
+
+use memmap2::MmapMut;
+use ndarray::ArrayView2;
+use ndarray::ArrayViewMut2;
+use rayon::prelude::*;
+
+/// A row-major, two-dimensional array, backed by an anonymous temporary file.
+/// The file is deleted once the array is dropped, or if the process dies.
+///
+/// The temporary file is created in the directory given by the environment
+/// variable `TMPDIR`, or the operating system's default temporary directory if
+/// `TMPDIR` is unset.
+#[derive(Debug)]
+pub(crate) struct MemoryMappedArray2<T> {
+    mmap: MmapMut,
+    shape: [usize; 2],
+    _element: PhantomData<T>,
+}
+
+impl<T> MemoryMappedArray2<T>
+where
+    T: Copy + Send + Sync,
+{
+    /// Create an array of the given shape, with every element set to `elem`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be created, resized, or
+    /// mapped into memory.
+    pub fn from_elem(shape: [usize; 2], elem: T) -> io::Result<Self> {
+        let [num_rows, num_columns] = shape;
+        let num_elements = num_rows
+            .checked_mul(num_columns)
+            .ok_or_else(|| io::Error::other("too many elements"))?;
+        let mut mmap = Self::map_temporary_file(num_elements)?;
+
+        // SAFETY:
+        // 1. The mapping is aligned for `T` – see `map_temporary_file`.
+        // 2. The mapping is large enough to hold `num_elements` many `T`s.
+        // 3. Any bit pattern is a valid `MaybeUninit<T>`.
+        let uninit_elements = unsafe {
+            let ptr = mmap.as_mut_ptr().cast::<MaybeUninit<T>>();
+            std::slice::from_raw_parts_mut(ptr, num_elements)
+        };
+        uninit_elements
+            .par_iter_mut()
+            .for_each(|e| *e = MaybeUninit::new(elem));
+
+        Ok(Self {
+            mmap,
+            shape,
+            _element: PhantomData,
+        })
+    }
+
+    fn map_temporary_file(num_elements: usize) -> io::Result<MmapMut> {
+        let num_bytes = num_elements
+            .checked_mul(size_of::<T>())
+            .ok_or_else(|| io::Error::other("too many bytes"))?;
+        let file = tempfile::tempfile()?;
+        file.set_len(u64::try_from(num_bytes).map_err(io::Error::other)?)?;
+
+        // SAFETY: The file is anonymous: it cannot be opened, much less modified, by
+        // any other process.
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        assert!(mmap.as_ptr().cast::<T>().is_aligned());
+
+        Ok(mmap)
+    }
+
+    fn num_elements(&self) -> usize {
+        self.shape[0] * self.shape[1]
+    }
+
+    pub fn view(&self) -> ArrayView2<T> {
+        // SAFETY: All elements have been initialized in `from_elem`, and the mapping
+        // is aligned and large enough.
+        let elements = unsafe {
+            let ptr = self.mmap.as_ptr().cast::<T>();
+            std::slice::from_raw_parts(ptr, self.num_elements())
+        };
+        ArrayView2::from_shape(self.shape, elements).unwrap()
+    }
+
+    pub fn view_mut(&mut self) -> ArrayViewMut2<T> {
+        let num_elements = self.num_elements();
+
+        // SAFETY: All elements have been initialized in `from_elem`, and the mapping
+        // is aligned and large enough.
+        let elements = unsafe {
+            let ptr = self.mmap.as_mut_ptr().cast::<T>();
+            std::slice::from_raw_parts_mut(ptr, num_elements)
+        };
+        ArrayViewMut2::from_shape(self.shape, elements).unwrap()
+    }
+}
+
+impl<T> Clone for MemoryMappedArray2<T>
+where
+    T: Copy + Send + Sync,
+{
+    /// # Panics
+    ///
+    /// Panics if the temporary file for the clone cannot be created.
+    fn clone(&self) -> Self {
+        let mut mmap = Self::map_temporary_file(self.num_elements())
+            .expect("cloning a memory-mapped array requires a temporary file");
+        mmap.copy_from_slice(&self.mmap);
+
+        Self {
+            mmap,
+            shape: self.shape,
+            _element: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::assert;
+    use ndarray::Array2;
+    use proptest::collection::vec;
+    use proptest_arbitrary_interop::arb;
+    use test_strategy::proptest;
+    use twenty_first::prelude::*;
+
+    use super::*;
+
+    #[proptest(cases = 20)]
+    fn memory_mapped_array_behaves_like_array(
+        #[strategy(1_usize..100)] num_rows: usize,
+        #[strategy(1_usize..20)] num_columns: usize,
+        #[strategy(arb())] fill: XFieldElement,
+        #[strategy(vec(arb(), #num_rows * #num_columns))] elements: Vec<XFieldElement>,
+    ) {
+        let shape = [num_rows, num_columns];
+        let mut memory_mapped = MemoryMappedArray2::from_elem(shape, fill).unwrap();
+        assert!(Array2::from_elem(shape, fill) == memory_mapped.view());
+
+        let array = Array2::from_shape_vec(shape, elements).unwrap();
+        memory_mapped.view_mut().assign(&array);
+        assert!(array == memory_mapped.view());
+
+        let clone = memory_mapped.clone();
+        drop(memory_mapped);
+        assert!(array == clone.view());
+    }
+}
@@ -791,9 +791,9 @@ pub(crate) mod tests {
     use test_strategy::proptest;
 
     use crate::error::InstructionError::DivisionByZero;
-    use crate::shared_tests::ProgramAndInput;
     use crate::stark::tests::master_tables_for_low_security_level;
     use crate::table::master_table::MasterTable;
+    use crate::test_utils::ProgramAndInput;
     use crate::NonDeterminism;
 
     use super::*;
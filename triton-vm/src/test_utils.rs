@@ -0,0 +1,514 @@
+//! Helpers for testing programs that run on Triton VM, and Triton VM itself.
+//!
+//! Available with feature `test-utils`. The [`Stark`] parameters used here are
+//! chosen for speed, not security. Do not use them in production.
+
+use std::collections::HashMap;
+
+use arbitrary::Arbitrary;
+use arbitrary::Unstructured;
+use isa::instruction::AnInstruction;
+use isa::op_stack::OpStackElement;
+use isa::op_stack::NUM_OP_STACK_REGISTERS;
+use proptest::collection::vec;
+use proptest::collection::SizeRange;
+use proptest::prelude::*;
+use proptest_arbitrary_interop::arb;
+use proptest_arbitrary_interop::arb_sized;
+use twenty_first::prelude::*;
+
+use crate::error::VMError;
+use crate::fri::AuthenticationStructure;
+use crate::prelude::*;
+use crate::proof_item::FriResponse;
+
+/// The log₂ of the FRI expansion factor that keeps test proofs cheap.
+pub const DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS: usize = 2;
+
+/// A Merkle tree with some revealed leaves and the corresponding
+/// authentication structure.
+#[derive(Debug, Clone, test_strategy::Arbitrary)]
+pub struct LeavedMerkleTreeTestData {
+    #[strategy(1..=10_usize)]
+    pub tree_height: usize,
+
+    #[strategy(vec(arb(), 1 << #tree_height))]
+    pub leaves: Vec<XFieldElement>,
+
+    #[strategy(vec(0..#leaves.len(), 1..=#leaves.len()))]
+    pub revealed_indices: Vec<usize>,
+
+    #[strategy(Just(#leaves.iter().map(|&x| x.into()).collect()))]
+    pub leaves_as_digests: Vec<Digest>,
+
+    #[strategy(Just(MerkleTree::new::<CpuParallel>(&#leaves_as_digests).unwrap()))]
+    pub merkle_tree: MerkleTree,
+
+    #[strategy(Just(#revealed_indices.iter().map(|&i| #leaves[i]).collect()))]
+    pub revealed_leaves: Vec<XFieldElement>,
+
+    #[strategy(Just(#merkle_tree.authentication_structure(&#revealed_indices).unwrap()))]
+    pub auth_structure: AuthenticationStructure,
+}
+
+impl LeavedMerkleTreeTestData {
+    pub fn root(&self) -> Digest {
+        self.merkle_tree.root()
+    }
+
+    pub fn leaves(&self) -> &[XFieldElement] {
+        &self.leaves
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn into_fri_response(self) -> FriResponse {
+        FriResponse {
+            auth_structure: self.auth_structure,
+            revealed_leaves: self.revealed_leaves,
+        }
+    }
+}
+
+/// Prove correct execution of the supplied program, then verify said proof.
+///
+/// Returns the claim and the proof for further inspection.
+///
+/// # Panics
+///
+/// Panics if the program crashes, or if proving or verification fails.
+pub fn prove_and_verify(
+    program_and_input: ProgramAndInput,
+    log_2_fri_expansion_factor: usize,
+) -> (Claim, Proof) {
+    let ProgramAndInput {
+        program,
+        public_input,
+        non_determinism,
+    } = program_and_input;
+
+    let claim = Claim::about_program(&program).with_input(public_input.clone());
+    let (aet, public_output) = VM::trace_execution(program, public_input, non_determinism).unwrap();
+    let claim = claim.with_output(public_output);
+
+    let stark = low_security_stark(log_2_fri_expansion_factor);
+    let proof = stark.prove(&claim, &aet).unwrap();
+    stark.verify(&claim, &proof).unwrap();
+
+    (claim, proof)
+}
+
+/// [`Stark`] parameters that make for fast proofs. Not secure.
+pub fn low_security_stark(log_expansion_factor: usize) -> Stark {
+    let security_level = 32;
+    Stark::new(security_level, log_expansion_factor)
+}
+
+/// Program and associated inputs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProgramAndInput {
+    pub program: Program,
+    pub public_input: PublicInput,
+    pub non_determinism: NonDeterminism,
+}
+
+impl ProgramAndInput {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            public_input: PublicInput::default(),
+            non_determinism: NonDeterminism::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_input<PI: Into<PublicInput>>(mut self, public_input: PI) -> Self {
+        self.public_input = public_input.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_non_determinism<ND: Into<NonDeterminism>>(mut self, non_determinism: ND) -> Self {
+        self.non_determinism = non_determinism.into();
+        self
+    }
+
+    pub fn public_input(&self) -> PublicInput {
+        self.public_input.clone()
+    }
+
+    pub fn non_determinism(&self) -> NonDeterminism {
+        self.non_determinism.clone()
+    }
+
+    /// A thin wrapper around [`VM::run`].
+    pub fn run(self) -> Result<Vec<BFieldElement>, VMError> {
+        let public_input = self.public_input();
+        let non_determinism = self.non_determinism();
+        VM::run(self.program, public_input, non_determinism)
+    }
+
+    /// The [`Claim`] of correct execution, including the program's output.
+    pub fn claim(&self) -> Result<Claim, VMError> {
+        let output = self.clone().run()?;
+        let claim = Claim::about_program(&self.program)
+            .with_input(self.public_input())
+            .with_output(output);
+
+        Ok(claim)
+    }
+}
+
+prop_compose! {
+    /// Public input of the given length, consisting of arbitrary field elements.
+    pub fn arbitrary_public_input(len: impl Into<SizeRange>)(
+        individual_tokens in vec(arb(), len),
+    ) -> PublicInput {
+        PublicInput::new(individual_tokens)
+    }
+}
+
+prop_compose! {
+    /// Non-determinism with the given number of individual tokens and digests,
+    /// and the given number of initialized RAM cells, all arbitrary.
+    pub fn arbitrary_non_determinism(
+        num_individual_tokens: impl Into<SizeRange>,
+        num_digests: impl Into<SizeRange>,
+        num_ram_cells: impl Into<SizeRange>,
+    )(
+        individual_tokens in vec(arb::<BFieldElement>(), num_individual_tokens),
+        digests in vec(arb::<Digest>(), num_digests),
+        ram in vec((arb::<BFieldElement>(), arb::<BFieldElement>()), num_ram_cells),
+    ) -> NonDeterminism {
+        NonDeterminism::new(individual_tokens)
+            .with_digests(digests)
+            .with_ram(ram.into_iter().collect::<HashMap<_, _>>())
+    }
+}
+
+prop_compose! {
+    /// Field elements that are valid operands for instructions like `lt`, `and`,
+    /// or `pow`, which require their arguments to be u32s.
+    pub fn arbitrary_u32_operands(len: impl Into<SizeRange>)(
+        operands in vec(any::<u32>(), len),
+    ) -> Vec<BFieldElement> {
+        operands.into_iter().map(BFieldElement::from).collect()
+    }
+}
+
+/// A random program, together with inputs for which it is guaranteed to halt
+/// gracefully, _i.e._, without crashing.
+///
+/// Any instruction of Triton VM can occur. Where necessary, an instruction is
+/// preceded by setup code: u32 instructions get u32 operands, assertions are
+/// satisfiable, Sponge instructions come after a `sponge_init`, and the op stack
+/// never becomes too shallow. Instructions that read input come with matching
+/// public or secret input. Instruction `call` targets straight-line
+/// subroutines, and `skiz` only guards instructions that are safe to skip.
+/// There are no loops.
+///
+/// See also [`arbitrary_halting_program`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HaltingProgram(pub ProgramAndInput);
+
+impl<'a> Arbitrary<'a> for HaltingProgram {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        HaltingProgramGenerator::new(u).generate().map(Self)
+    }
+}
+
+impl From<HaltingProgram> for ProgramAndInput {
+    fn from(program: HaltingProgram) -> Self {
+        program.0
+    }
+}
+
+/// A program with inputs for which it halts gracefully. See [`HaltingProgram`].
+pub fn arbitrary_halting_program() -> impl Strategy<Value = ProgramAndInput> {
+    arb_sized::<HaltingProgram>(1 << 12).prop_map(ProgramAndInput::from)
+}
+
+#[derive(Debug)]
+struct HaltingProgramGenerator<'u, 'a> {
+    u: &'u mut Unstructured<'a>,
+    code: Vec<LabelledInstruction>,
+    subroutines: Vec<LabelledInstruction>,
+    stack_depth: usize,
+    sponge_is_initialized: bool,
+    public_input: Vec<BFieldElement>,
+    secret_input: Vec<BFieldElement>,
+    secret_digests: Vec<Digest>,
+    num_subroutines: usize,
+}
+
+impl<'u, 'a> HaltingProgramGenerator<'u, 'a> {
+    const MAX_NUM_INSTRUCTIONS: usize = 100;
+    const MAX_NUM_SUBROUTINE_INSTRUCTIONS: usize = 10;
+    const MAX_CALL_DEPTH: usize = 3;
+
+    fn new(u: &'u mut Unstructured<'a>) -> Self {
+        Self {
+            u,
+            code: vec![],
+            subroutines: vec![],
+            stack_depth: NUM_OP_STACK_REGISTERS,
+            sponge_is_initialized: false,
+            public_input: vec![],
+            secret_input: vec![],
+            secret_digests: vec![],
+            num_subroutines: 0,
+        }
+    }
+
+    fn generate(mut self) -> arbitrary::Result<ProgramAndInput> {
+        let num_instructions = self.u.int_in_range(0..=Self::MAX_NUM_INSTRUCTIONS)?;
+        for _ in 0..num_instructions {
+            self.instruction(0)?;
+        }
+        self.emit(AnInstruction::Halt);
+
+        let code = [self.code, self.subroutines].concat();
+        let non_determinism =
+            NonDeterminism::new(self.secret_input).with_digests(self.secret_digests);
+        let program_and_input = ProgramAndInput::new(Program::new(&code))
+            .with_input(self.public_input)
+            .with_non_determinism(non_determinism);
+
+        Ok(program_and_input)
+    }
+
+    /// Append a random instruction, including any setup it requires.
+    fn instruction(&mut self, call_depth: usize) -> arbitrary::Result<()> {
+        let instruction = self.u.arbitrary::<AnInstruction<String>>()?;
+        match instruction {
+            AnInstruction::Halt
+            | AnInstruction::Return
+            | AnInstruction::Recurse
+            | AnInstruction::RecurseOrReturn => return Ok(()),
+            AnInstruction::Call(_) if call_depth >= Self::MAX_CALL_DEPTH => return Ok(()),
+            AnInstruction::Call(_) => return self.subroutine(call_depth + 1),
+            AnInstruction::Skiz => {
+                self.emit(AnInstruction::Skiz);
+                return self.skippable_instruction();
+            }
+            AnInstruction::Assert => self.push(bfe!(1)),
+            AnInstruction::AssertVector => {
+                for _ in 0..Digest::LEN {
+                    self.emit(AnInstruction::Dup(OpStackElement::ST4));
+                }
+            }
+            AnInstruction::SpongeAbsorb
+            | AnInstruction::SpongeAbsorbMem
+            | AnInstruction::SpongeSqueeze
+                if !self.sponge_is_initialized =>
+            {
+                self.emit(AnInstruction::SpongeInit);
+                self.sponge_is_initialized = true;
+            }
+            AnInstruction::Invert => self.push_non_zero()?,
+            AnInstruction::XInvert => {
+                self.push_arbitrary()?;
+                self.push_arbitrary()?;
+                self.push_non_zero()?;
+            }
+            AnInstruction::Lt | AnInstruction::And | AnInstruction::Xor => {
+                self.push_u32()?;
+                self.push_u32()?;
+            }
+            AnInstruction::Log2Floor | AnInstruction::PopCount => self.push_non_zero_u32()?,
+            AnInstruction::Pow => {
+                self.push_u32()?;
+                self.push_arbitrary()?;
+            }
+            AnInstruction::DivMod => {
+                self.push_non_zero_u32()?;
+                self.push_u32()?;
+            }
+            AnInstruction::MerkleStep | AnInstruction::MerkleStepMem => {
+                self.push_u32()?;
+                self.emit(AnInstruction::Place(OpStackElement::ST5));
+            }
+            _ => (),
+        }
+
+        match &instruction {
+            AnInstruction::SpongeInit => self.sponge_is_initialized = true,
+            AnInstruction::MerkleStep => self.secret_digests.push(self.u.arbitrary()?),
+            AnInstruction::Divine(n) => {
+                for _ in 0..n.num_words() {
+                    self.secret_input.push(self.u.arbitrary()?);
+                }
+            }
+            AnInstruction::ReadIo(n) => {
+                for _ in 0..n.num_words() {
+                    self.public_input.push(self.u.arbitrary()?);
+                }
+            }
+            _ => (),
+        }
+
+        self.emit(instruction);
+        Ok(())
+    }
+
+    /// Call a new subroutine with random, straight-line code. The subroutine is
+    /// generated at the call site, since that is where it is executed.
+    fn subroutine(&mut self, call_depth: usize) -> arbitrary::Result<()> {
+        let label = format!("subroutine_{}", self.num_subroutines);
+        self.num_subroutines += 1;
+        self.emit(AnInstruction::Call(label.clone()));
+
+        let caller_code = std::mem::take(&mut self.code);
+        let num_instructions = self
+            .u
+            .int_in_range(0..=Self::MAX_NUM_SUBROUTINE_INSTRUCTIONS)?;
+        for _ in 0..num_instructions {
+            self.instruction(call_depth)?;
+        }
+        self.emit(AnInstruction::Return);
+        let body = std::mem::replace(&mut self.code, caller_code);
+
+        self.subroutines.push(LabelledInstruction::Label(label));
+        self.subroutines.extend(body);
+        Ok(())
+    }
+
+    /// An instruction that can neither fail nor change the size of the op stack.
+    fn skippable_instruction(&mut self) -> arbitrary::Result<()> {
+        let instruction = match self.u.choose_index(5)? {
+            0 => AnInstruction::Nop,
+            1 => AnInstruction::AddI(self.u.arbitrary()?),
+            2 => AnInstruction::Swap(self.u.arbitrary()?),
+            3 => AnInstruction::Pick(self.u.arbitrary()?),
+            _ => AnInstruction::Place(self.u.arbitrary()?),
+        };
+        self.emit(instruction);
+        Ok(())
+    }
+
+    fn push(&mut self, element: BFieldElement) {
+        self.emit(AnInstruction::Push(element));
+    }
+
+    fn push_arbitrary(&mut self) -> arbitrary::Result<()> {
+        let element = self.u.arbitrary()?;
+        self.push(element);
+        Ok(())
+    }
+
+    fn push_non_zero(&mut self) -> arbitrary::Result<()> {
+        let element = self.u.int_in_range(1..=BFieldElement::MAX)?;
+        self.push(bfe!(element));
+        Ok(())
+    }
+
+    fn push_u32(&mut self) -> arbitrary::Result<()> {
+        let element = self.u.arbitrary::<u32>()?;
+        self.push(bfe!(element));
+        Ok(())
+    }
+
+    fn push_non_zero_u32(&mut self) -> arbitrary::Result<()> {
+        let element = self.u.int_in_range(1..=u32::MAX)?;
+        self.push(bfe!(element));
+        Ok(())
+    }
+
+    /// Append the instruction, first growing the op stack if it would otherwise
+    /// become too shallow.
+    fn emit(&mut self, instruction: AnInstruction<String>) {
+        let stack_size_influence = instruction.op_stack_size_influence();
+        let num_popped = usize::try_from(-stack_size_influence).unwrap_or(0);
+        let num_pushed = usize::try_from(stack_size_influence).unwrap_or(0);
+
+        // Since all fallible instructions get fresh operands, any padding works.
+        while self.stack_depth < NUM_OP_STACK_REGISTERS + num_popped {
+            let padding = AnInstruction::Push(bfe!(0));
+            self.code.push(LabelledInstruction::Instruction(padding));
+            self.stack_depth += 1;
+        }
+
+        self.stack_depth = self.stack_depth + num_pushed - num_popped;
+        self.code
+            .push(LabelledInstruction::Instruction(instruction));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use assert2::assert;
+    use isa::instruction::ALL_INSTRUCTIONS;
+    use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use test_strategy::proptest;
+
+    use super::*;
+
+    #[proptest]
+    fn halting_program_halts(
+        #[strategy(arbitrary_halting_program())] program_and_input: ProgramAndInput,
+    ) {
+        if let Err(err) = program_and_input.run() {
+            panic!("{err}");
+        }
+    }
+
+    #[proptest(cases = 3)]
+    fn halting_program_can_be_proven(
+        #[strategy(arbitrary_halting_program())] program_and_input: ProgramAndInput,
+    ) {
+        prove_and_verify(
+            program_and_input,
+            DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS,
+        );
+    }
+
+    #[test]
+    fn halting_programs_contain_all_kinds_of_instructions() {
+        let mut rng = StdRng::seed_from_u64(0x3a3c_83f0_31d4_1e49);
+        let mut seen_instructions = HashSet::new();
+        for _ in 0..50 {
+            let randomness = (0..10_000).map(|_| rng.gen()).collect_vec();
+            let mut unstructured = Unstructured::new(&randomness);
+            let program = HaltingProgram::arbitrary(&mut unstructured)
+                .unwrap()
+                .0
+                .program;
+            seen_instructions.extend(program.into_iter().map(|i| i.name()));
+        }
+
+        let never_generated = ["recurse", "recurse_or_return"];
+        for instruction in ALL_INSTRUCTIONS {
+            let name = instruction.name();
+            assert!(seen_instructions.contains(name) || never_generated.contains(&name));
+        }
+    }
+
+    #[proptest]
+    fn u32_operands_are_u32s(
+        #[strategy(arbitrary_u32_operands(0..10))] operands: Vec<BFieldElement>,
+    ) {
+        assert!(operands.iter().all(|op| u32::try_from(*op).is_ok()));
+    }
+
+    #[proptest(cases = 5)]
+    fn arbitrary_input_is_read_as_given(
+        #[strategy(arbitrary_public_input(1..10))] public_input: PublicInput,
+        #[strategy(arbitrary_non_determinism(1..10, 0..3, 0..5))] non_determinism: NonDeterminism,
+    ) {
+        let program = triton_program!(read_io 1 divine 1 write_io 2 halt);
+        let program_and_input = ProgramAndInput::new(program)
+            .with_input(public_input.clone())
+            .with_non_determinism(non_determinism.clone());
+        let claim = program_and_input.claim().unwrap();
+
+        let expected_output = [non_determinism.individual_tokens[0], public_input[0]];
+        assert!(expected_output == claim.output.as_slice());
+    }
+}
@@ -33,6 +33,7 @@ use twenty_first::prelude::*;
 use twenty_first::util_types::sponge;
 
 use crate::aet::AlgebraicExecutionTrace;
+use crate::coverage::ExecutionCoverage;
 use crate::error::VMError;
 use crate::execution_trace_profiler::ExecutionTraceProfile;
 use crate::execution_trace_profiler::ExecutionTraceProfiler;
@@ -212,7 +213,10 @@ impl VM {
         while !state.halting {
             if let Ok(Instruction::Call(address)) = state.current_instruction() {
                 let label = program.label_for_address(address.value());
-                profiler.enter_span(label);
+                match program.source_location(address.value()) {
+                    Some(location) => profiler.enter_span_at(label, location),
+                    None => profiler.enter_span(label),
+                }
             }
 
             match state.step() {
@@ -228,6 +232,35 @@ impl VM {
 
         Ok((state.public_output, profiler.finish()))
     }
+
+    /// Run Triton VM with the given public and secret input, recording how often
+    /// each instruction of the [`Program`] is executed. The resulting
+    /// [coverage report](ExecutionCoverage) points at the source code of
+    /// instructions that were never executed, if the program was
+    /// [parsed from source code](Program::from_code).
+    ///
+    /// See also [`profile`][profile].
+    ///
+    /// [profile]: Self::profile
+    pub fn coverage(
+        program: Program,
+        public_input: PublicInput,
+        non_determinism: NonDeterminism,
+    ) -> VMResult<(Vec<BFieldElement>, ExecutionCoverage)> {
+        let mut execution_counts = vec![0; program.len_bwords()];
+        let mut state = VMState::new(program.clone(), public_input, non_determinism);
+        while !state.halting {
+            if let Some(count) = execution_counts.get_mut(state.instruction_pointer) {
+                *count += 1;
+            }
+            if let Err(err) = state.step() {
+                return Err(VMError::new(err, state));
+            }
+        }
+
+        let coverage = ExecutionCoverage::new(&program, &execution_counts);
+        Ok((state.public_output, coverage))
+    }
 }
 
 impl VMState {
@@ -1529,11 +1562,11 @@ pub(crate) mod tests {
     use test_strategy::proptest;
     use twenty_first::math::other::random_elements;
 
-    use crate::shared_tests::prove_and_verify;
-    use crate::shared_tests::LeavedMerkleTreeTestData;
-    use crate::shared_tests::ProgramAndInput;
-    use crate::shared_tests::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
     use crate::stark::tests::program_executing_every_instruction;
+    use crate::test_utils::prove_and_verify;
+    use crate::test_utils::LeavedMerkleTreeTestData;
+    use crate::test_utils::ProgramAndInput;
+    use crate::test_utils::DEFAULT_LOG2_FRI_EXPANSION_FACTOR_FOR_TESTS;
 
     use super::*;
 
@@ -1624,6 +1657,34 @@ pub(crate) mod tests {
         let_assert!(InstructionError::JumpStackIsEmpty = err.source);
     }
 
+    #[test]
+    fn profile_points_to_source_location_of_subroutines() {
+        let source_code = "call foo halt\nfoo:\n    push 1 pop 1 return";
+        let program = Program::from_code(source_code)
+            .unwrap()
+            .with_source_file("main.tasm");
+        let (_, profile) = VM::profile(program, [].into(), [].into()).unwrap();
+
+        let_assert!([line] = profile.profile.as_slice());
+        let_assert!(Some(location) = line.source_location());
+        assert!("main.tasm:3:5" == location.to_string());
+        assert!(line.to_string().contains("main.tasm:3:5"));
+    }
+
+    #[test]
+    fn vm_error_points_to_source_location_of_failing_instruction() {
+        let source_code = "push 1\npush 0\n  assert\nhalt";
+        let program = Program::from_code(source_code)
+            .unwrap()
+            .with_source_file("main.tasm");
+        let_assert!(Err(err) = VM::run(program, [].into(), [].into()));
+
+        let_assert!(Some(location) = err.source_location());
+        assert!(3 == location.line);
+        assert!(3 == location.column);
+        assert!(err.to_string().contains("at main.tasm:3:3"));
+    }
+
     #[proptest]
     fn from_various_types_to_public_input(#[strategy(arb())] tokens: Vec<BFieldElement>) {
         let public_input = PublicInput::new(tokens.clone());
@@ -1648,6 +1709,23 @@ pub(crate) mod tests {
         assert!(NonDeterminism::new(vec![]) == [].into());
     }
 
+    #[test]
+    fn program_can_read_its_static_data() {
+        let program = Program::from_code(
+            "
+            .const ADDRESS = 42
+            .data ADDRESS [7, 8]
+            push ADDRESS addi 1 read_mem 2 pop 1
+            mul write_io 1
+            halt
+            ",
+        )
+        .unwrap();
+        let non_determinism = NonDeterminism::default().with_ram(program.static_data());
+        let_assert!(Ok(output) = VM::run(program, [].into(), non_determinism));
+        assert!(vec![bfe!(56)] == output);
+    }
+
     #[test]
     fn initialise_table() {
         let program = crate::example_programs::GREATEST_COMMON_DIVISOR.clone();
@@ -1669,6 +1747,31 @@ pub(crate) mod tests {
         assert!(bfe!(14) == stdout[0]);
     }
 
+    #[test]
+    fn structured_control_flow_executes_as_specified() {
+        let program = triton_program!(
+            read_io 1
+            if { push 10 } else { push 20 }
+            write_io 1
+
+            push 0
+            repeat 3 { dup 0 write_io 1 }
+            pop 1
+
+            // count from st5 up to st6
+            push 4 push 0 push 0 push 0 push 0 push 0 push 0
+            while { pick 5 addi 1 dup 0 write_io 1 place 5 }
+            halt
+        );
+
+        let expected_loop_output = bfe_vec![3, 2, 1, 1, 2, 3, 4];
+        for (input, branch_output) in [(1, 10), (0, 20)] {
+            let_assert!(Ok(output) = VM::run(program.clone(), bfe_vec![input].into(), [].into()));
+            assert!(bfe!(branch_output) == output[0]);
+            assert!(expected_loop_output == output[1..]);
+        }
+    }
+
     #[test]
     fn crash_triton_vm_and_print_vm_error() {
         let crashing_program = triton_program!(push 2 assert halt);